@@ -0,0 +1,12 @@
+//! μHouse-rs on an Arduino Mega 2560 with an SSD1306 over I2C
+//! 
+//! Wire SDA to D20 and SCL to D21. Other firmware features are added with
+//! `--features uhouse/<feature>`, such as `--features uhouse/fps`.
+
+#![no_std]
+#![no_main]
+
+#[uhouse::entry]
+fn main() -> ! {
+    uhouse::run()
+}
@@ -0,0 +1,12 @@
+//! μHouse-rs on an Arduino UNO with an SSD1306 over I2C
+//! 
+//! Wire SDA to A4 and SCL to A5. Other firmware features are added with
+//! `--features uhouse/<feature>`, such as `--features uhouse/fps`.
+
+#![no_std]
+#![no_main]
+
+#[uhouse::entry]
+fn main() -> ! {
+    uhouse::run()
+}
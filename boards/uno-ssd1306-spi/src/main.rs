@@ -0,0 +1,13 @@
+//! μHouse-rs on an Arduino UNO with an SSD1306 over SPI
+//! 
+//! Wire SCK to D13, MOSI to D11, CS to D10, DC to D9 and RES to D8. Other
+//! firmware features are added with `--features uhouse/<feature>`, such as
+//! `--features uhouse/fps`.
+
+#![no_std]
+#![no_main]
+
+#[uhouse::entry]
+fn main() -> ! {
+    uhouse::run()
+}
@@ -0,0 +1,153 @@
+#!/usr/bin/env python3
+# Decode the compressed framebuffer stream of the frame-stream feature
+#
+# Usage: scripts/decode-stream.py STREAM [OUT_DIR]
+#        scripts/decode-stream.py --self-test
+#
+# Reads the stream from STREAM, a file or a serial device already set to
+# 57600 baud raw (e.g. `stty -F /dev/ttyACM0 57600 raw`), and writes each
+# frame to OUT_DIR (default `frames`) as a binary PBM image. Frames failing
+# their CRC are reported and skipped. The format is described in
+# uhouse/src/framestream.rs and uhouse-core/src/rle.rs.
+#
+# --self-test round-trips random frames through a reference encoder and the
+# decoder.
+
+import os
+import random
+import sys
+
+WIDTH = 128
+HEIGHT = 64
+FRAME_BYTES = WIDTH * HEIGHT // 8
+MAGIC = b"UHF1"
+MAX_RUN = 128
+
+
+def crc16(data, crc=0xFFFF):
+    """CRC-16/CCITT-FALSE, as computed by uhouse_core::validate::crc16_update"""
+    for byte in data:
+        crc ^= byte << 8
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x1021) if crc & 0x8000 else crc << 1
+            crc &= 0xFFFF
+    return crc
+
+
+def encode(data):
+    """Reference encoder matching uhouse_core::rle::encode"""
+    out = bytearray()
+
+    def repeat_len(at):
+        n = 1
+        while at + n < len(data) and n < MAX_RUN and data[at + n] == data[at]:
+            n += 1
+        return n
+
+    i = 0
+    while i < len(data):
+        repeat = repeat_len(i)
+        if repeat >= 2:
+            out += bytes([0x80 | (repeat - 1), data[i]])
+            i += repeat
+            continue
+        start = i
+        i += 1
+        while i < len(data) and i - start < MAX_RUN and repeat_len(i) < 2:
+            i += 1
+        out.append(i - start - 1)
+        out += data[start:i]
+    return bytes(out)
+
+
+def decode(read):
+    """Decode one framebuffer, reading bytes with `read(n)`"""
+    frame = bytearray()
+    while len(frame) < FRAME_BYTES:
+        control = read(1)[0]
+        if control < 0x80:
+            frame += read(control + 1)
+        else:
+            frame += read(1) * (control - 0x7F)
+    if len(frame) != FRAME_BYTES:
+        raise ValueError("run past the end of the frame")
+    return bytes(frame)
+
+
+def to_pbm(frame):
+    """Convert a framebuffer in display order to a binary PBM image"""
+    rows = bytearray()
+    for y in range(HEIGHT):
+        row = 0
+        for x in range(WIDTH):
+            bit = frame[(y >> 3) * WIDTH + x] >> (y & 7) & 1
+            row = row << 1 | bit
+        rows += row.to_bytes(WIDTH // 8, "big")
+    return b"P4\n%d %d\n" % (WIDTH, HEIGHT) + bytes(rows)
+
+
+def self_test():
+    rng = random.Random(0)
+    for n in range(200):
+        # Sparse frames like a wireframe, with the odd dense one
+        density = 0.5 if n % 10 == 0 else 0.02
+        frame = bytes(
+            rng.getrandbits(8) if rng.random() < density else 0
+            for _ in range(FRAME_BYTES)
+        )
+        encoded = encode(frame)
+        stream = iter(encoded)
+        decoded = decode(lambda k: bytes(next(stream) for _ in range(k)))
+        assert decoded == frame, "frame %d does not round-trip" % n
+    print("ok")
+
+
+def main():
+    if sys.argv[1:] == ["--self-test"]:
+        self_test()
+        return
+    if len(sys.argv) not in (2, 3):
+        sys.exit("usage: decode-stream.py STREAM [OUT_DIR] | --self-test")
+
+    out_dir = sys.argv[2] if len(sys.argv) == 3 else "frames"
+    os.makedirs(out_dir, exist_ok=True)
+
+    with open(sys.argv[1], "rb", buffering=0) as stream:
+        def read(n):
+            data = b""
+            while len(data) < n:
+                chunk = stream.read(n - len(data))
+                if not chunk:
+                    raise EOFError
+                data += chunk
+            return data
+
+        count = 0
+        window = b""
+        try:
+            while True:
+                # Resynchronize on the magic after a bad frame or on start
+                window = (window + read(1))[-len(MAGIC):]
+                if window != MAGIC:
+                    continue
+                window = b""
+                try:
+                    frame = decode(read)
+                except ValueError:
+                    print("frame %d: bad run, skipped" % count, file=sys.stderr)
+                    continue
+                crc = int.from_bytes(read(2), "little")
+                if crc != crc16(frame):
+                    print("frame %d: bad crc, skipped" % count, file=sys.stderr)
+                    continue
+                path = os.path.join(out_dir, "frame-%05d.pbm" % count)
+                with open(path, "wb") as out:
+                    out.write(to_pbm(frame))
+                count += 1
+        except EOFError:
+            pass
+    print("%d frames" % count)
+
+
+if __name__ == "__main__":
+    main()
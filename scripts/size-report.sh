@@ -0,0 +1,46 @@
+#!/bin/sh
+# Report flash usage of a release build by module and crate
+#
+# Usage: scripts/size-report.sh [cargo build arguments...]
+#
+# Builds with the given arguments (e.g. `--features fps`), prints the total
+# from `avr-size` and a per-module breakdown of code and progmem from
+# `avr-nm`, and fails if flash usage exceeds FLASH_CEILING bytes. The default
+# ceiling matches FLASH_BUDGET in uhouse/build.rs.
+
+set -e
+
+# The firmware is built from its own directory, where its Cargo
+# configuration selects the AVR target
+cd "$(dirname "$0")/../uhouse"
+
+FLASH_CEILING=${FLASH_CEILING:-32256}
+ELF=target/avr-atmega328p/release/uhouse.elf
+
+cargo build --release "$@"
+
+avr-size --format=avr --mcu=atmega328p "$ELF"
+
+# Group flash symbols by their first two path segments, e.g. `uhouse_core::mesh`
+# or `ssd1306::mode`, and sum their sizes
+avr-nm --print-size --size-sort --radix=d --demangle "$ELF" |
+    awk '$3 ~ /^[tTrR]$/ {
+        name = $4
+        for (i = 5; i <= NF; i++) name = name " " $i
+        sub(/^</, "", name)
+        n = split(name, path, "::")
+        module = (n > 2) ? path[1] "::" path[2] : path[1]
+        sizes[module] += $2
+    }
+    END {
+        for (module in sizes) printf "%8d  %s\n", sizes[module], module
+    }' |
+    sort -rn
+
+# Flash holds .text and the initial values of .data
+used=$(avr-size -A "$ELF" | awk '$1 == ".text" || $1 == ".data" { sum += $2 } END { print sum }')
+
+if [ "$used" -gt "$FLASH_CEILING" ]; then
+    echo "error: flash usage of $used bytes exceeds the ceiling of $FLASH_CEILING bytes" >&2
+    exit 1
+fi
@@ -0,0 +1,213 @@
+//! Keyframe animation of parts of a mesh
+//! 
+//! A [`Part`] is a named range of the vertices of a mesh, such as the door
+//! or the car of the house, moved as one before the whole mesh is rotated
+//! into view. Each part follows its own list of [`Keyframe`]s, turning about
+//! a pivot and moving by an offset, interpolated between keyframes in fixed
+//! point. The keyframes of every part are stored one part after another in a
+//! single table in program memory, the way strips are.
+//! 
+//! All parts of an object share one clock, which loops back to the start
+//! after the object's period. A part holds its first keyframe until the
+//! clock reaches it and its last keyframe after.
+
+use core::ops::Range;
+
+use avr_progmem::wrapper::ProgMem;
+
+use crate::rotor::{Axis, Rotor};
+use crate::vec::*;
+
+/// Pose of a part at one frame of the loop
+#[derive(Copy, Clone)]
+pub struct Keyframe {
+    /// Frame of the loop the part reaches this pose at
+    pub time: u16,
+
+    /// Movement of the part away from where the mesh has it
+    pub offset: Vec3,
+
+    /// Angle turned about the pivot of the part, in 65536ths of a turn, see
+    /// [`sine`](crate::sine)
+    /// 
+    /// The part turns the shorter way between two keyframes, so keyframes
+    /// must be less than half a turn apart.
+    pub angle: u16,
+}
+
+impl Keyframe {
+
+    /// Pose reached at frame `time`
+    pub const fn new(time: u16, offset: Vec3, angle: u16) -> Self {
+        Self { time, offset, angle }
+    }
+}
+
+/// Range of the vertices of a mesh moved together
+#[derive(Copy, Clone)]
+pub struct Part {
+    /// First vertex of the part
+    pub first: u8,
+
+    /// Vertex after the last one of the part
+    pub end: u8,
+
+    /// Axis the part turns about
+    pub axis: Axis,
+
+    /// Point the part turns about, in model space
+    pub pivot: Vec3,
+
+    /// Number of keyframes of the part, following those of the parts before
+    /// it in the table of keyframes
+    pub keys: u8,
+}
+
+impl Part {
+
+    /// Part of the vertices `verts`, turning about `axis` through `pivot`
+    /// and following the next `keys` keyframes
+    pub const fn new(verts: Range<u8>, axis: Axis, pivot: Vec3, keys: u8) -> Self {
+        Self {
+            first: verts.start,
+            end: verts.end,
+            axis,
+            pivot,
+            keys,
+        }
+    }
+
+    /// Pose of the part at `frame`, with its keyframes starting at `first`
+    fn pose<const K: usize>(&self, keys: &ProgMem<[Keyframe; K]>, first: usize, frame: u16) -> Pose {
+        // Find the keyframes either side of the frame
+        let mut from = keys.load_at(first);
+        let mut to = from;
+        for n in 1..self.keys as usize {
+            to = keys.load_at(first + n);
+            if to.time > frame {
+                break;
+            }
+            from = to;
+        }
+
+        let (offset, angle) = if frame <= from.time || to.time <= frame {
+            (from.offset, from.angle)
+        } else {
+            let t = ((frame - from.time) as u32*0x1000 / (to.time - from.time) as u32) as i32;
+            let turn = to.angle.wrapping_sub(from.angle) as i16 as i32;
+            (
+                from.offset.lerp(to.offset, t as IFixed),
+                from.angle.wrapping_add(((turn*t) >> 12) as u16),
+            )
+        };
+
+        Pose {
+            verts: self.first..self.end,
+            axis: self.axis,
+            pivot: self.pivot,
+            offset,
+            rotor: Rotor::from_angle(angle),
+        }
+    }
+}
+
+/// Total number of keyframes the parts follow, for checking the table of
+/// keyframes at compile time
+pub const fn key_count(parts: &[Part]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        count += parts[i].keys as usize;
+        i += 1;
+    }
+    count
+}
+
+/// Whether every part has a keyframe and vertices within `num_verts`, for
+/// checking the parts at compile time
+pub const fn parts_fit(parts: &[Part], num_verts: usize) -> bool {
+    let mut i = 0;
+    while i < parts.len() {
+        let part = &parts[i];
+        if part.keys == 0 || part.first > part.end || part.end as usize > num_verts {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Part placed for the current frame
+#[derive(Clone)]
+pub struct Pose {
+    verts: Range<u8>,
+    axis: Axis,
+    pivot: Vec3,
+    offset: Vec3,
+    rotor: Rotor,
+}
+
+impl Pose {
+
+    /// Move a vertex of the part into place
+    pub fn apply(&self, v: Vec3) -> Vec3 {
+        self.rotor.apply_about(self.axis, v - self.pivot) + self.pivot + self.offset
+    }
+}
+
+/// Move vertex `i` into place by the pose of the part it belongs to, if any
+pub fn pose_vertex(poses: &[Pose], i: usize, v: Vec3) -> Vec3 {
+    poses
+        .iter()
+        .find(|pose| pose.verts.contains(&(i as u8)))
+        .map_or(v, |pose| pose.apply(v))
+}
+
+/// Progress along the keyframes of an object
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct AnimationState {
+    /// Frames since the start of the current loop
+    frame: u16,
+}
+
+impl AnimationState {
+
+    /// Start at the beginning of the loop
+    pub const fn new() -> Self {
+        Self { frame: 0 }
+    }
+
+    /// Advance by one frame, looping back after `period` frames
+    pub fn advance(&mut self, period: u16) {
+        self.frame += 1;
+        if self.frame >= period {
+            self.frame = 0;
+        }
+    }
+
+    /// Frames since the start of the current loop
+    pub fn frame(&self) -> u16 {
+        self.frame
+    }
+
+    /// Place every part for the current frame
+    pub fn poses<const P: usize, const K: usize>(
+        &self,
+        parts: &ProgMem<[Part; P]>,
+        keys: &ProgMem<[Keyframe; K]>,
+    ) -> [Pose; P] {
+        let mut first = 0;
+        core::array::from_fn(|i| {
+            let part = parts.load_at(i);
+            let pose = part.pose(keys, first, self.frame);
+            first += part.keys as usize;
+            pose
+        })
+    }
+}
+
+impl Default for AnimationState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
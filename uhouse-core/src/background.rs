@@ -0,0 +1,245 @@
+//! Backdrop drawn behind the scene
+//! 
+//! A [`Backdrop`] is drawn into the frame before the wireframe, turning with
+//! the rotation of the scene so that the scene looks to be seen by a camera
+//! moving through a still world rather than spinning in front of a blank
+//! screen. Only the rotation is followed, not the orbit or depth of the
+//! scene, so the backdrop stays centered on the screen.
+//! 
+//! The starfield lies on two shells around the center of the scene, two and
+//! three times as far from it as the camera, so the nearer stars sweep past
+//! faster than the farther ones. Their directions are kept in program memory.
+//! The horizon is where a level ground at any depth below the scene meets the
+//! sky, with the ground below it filled with a sparse ordered dither.
+
+use avr_progmem::progmem;
+
+use crate::clip::{Rect, NEAR};
+use crate::fill::dither;
+use crate::projection::{Project, GUARD_RADIUS};
+use crate::raster::{draw_line, Endpoints, Style};
+use crate::units::{Raw, World};
+use crate::vec::*;
+
+progmem! {
+
+    /// Direction of every star from the center of the scene, as a unit vector
+    /// 
+    /// Spread evenly over the sphere along a golden-angle spiral, from the
+    /// equations `y = 1 - (2*i + 1)/128`, `r = sqrt(1 - y*y)` and
+    /// `(x, z) = r*(cos(i*a), sin(i*a))` with `a = pi*(3 - sqrt(5))`, scaled
+    /// by 4096.
+    static progmem STARS: [Vec3<Raw>; 128] = [
+        vec3!( 0x1ff,  0xfe0,  0x000), vec3!(-0x28a,  0xfa0,  0x254),
+        vec3!( 0x063,  0xf60, -0x469), vec3!( 0x32d,  0xf20,  0x424),
+        vec3!(-0x5ce,  0xee0, -0x107), vec3!( 0x57a,  0xea0, -0x37c),
+        vec3!(-0x1d3,  0xe60,  0x6c9), vec3!(-0x377,  0xe20, -0x6ab),
+        vec3!( 0x77c,  0xde0,  0x2bc), vec3!(-0x7c1,  0xda0,  0x333),
+        vec3!( 0x3b9,  0xd60, -0x7f4), vec3!( 0x2bd,  0xd20,  0x8bb),
+        vec3!(-0x838,  0xce0, -0x4c3), vec3!( 0x99a,  0xca0, -0x21c),
+        vec3!(-0x5d5,  0xc60,  0x84c), vec3!(-0x157,  0xc20, -0xa5a),
+        vec3!( 0x833,  0xbe0,  0x6e9), vec3!(-0xafc,  0xba0,  0x074),
+        vec3!( 0x7fa,  0xb60, -0x7f0), vec3!(-0x088,  0xb20,  0xb7d),
+        vec3!(-0x785,  0xae0, -0x903), vec3!( 0xbdb,  0xaa0,  0x198),
+        vec3!(-0xa00,  0xa60,  0x6f5), vec3!( 0x2b8,  0xa20, -0xc16),
+        vec3!( 0x642,  0x9e0,  0xaec), vec3!(-0xc2d,  0x9a0, -0x3e2),
+        vec3!( 0xbc5,  0x960, -0x570), vec3!(-0x513,  0x920,  0xc20),
+        vec3!(-0x481,  0x8e0, -0xc87), vec3!( 0xbee,  0x8a0,  0x645),
+        vec3!(-0xd2f,  0x860,  0x37a), vec3!( 0x774,  0x820, -0xb98),
+        vec3!( 0x25c,  0x7e0,  0xdba), vec3!(-0xb1f,  0x7a0, -0x89d),
+        vec3!( 0xe27,  0x760, -0x12c), vec3!(-0x9ba,  0x720,  0xa84),
+        vec3!( 0x012,  0x6e0, -0xe73), vec3!( 0x9c9,  0x6a0,  0xac9),
+        vec3!(-0xe9d,  0x660, -0x15b), vec3!( 0xbc6,  0x620, -0x8f0),
+        vec3!(-0x2aa,  0x5e0,  0xea4), vec3!(-0x7fa,  0x5a0, -0xcad),
+        vec3!( 0xe89,  0x560,  0x3fc), vec3!(-0xd7c,  0x520,  0x6ec),
+        vec3!( 0x54c,  0x4e0, -0xe4a), vec3!( 0x5c6,  0x4a0,  0xe30),
+        vec3!(-0xde8,  0x460, -0x697), vec3!( 0xec6,  0x420, -0x48e),
+        vec3!(-0x7d9,  0x3e0,  0xd65), vec3!(-0x346,  0x3a0, -0xf3d),
+        vec3!( 0xcc0,  0x360,  0x90f), vec3!(-0xf92,  0x320,  0x1f1),
+        vec3!( 0xa34,  0x2e0, -0xbfc), vec3!( 0x093,  0x2a0,  0xfc6),
+        vec3!(-0xb1b,  0x260, -0xb45), vec3!( 0xfd6,  0x220,  0x0cf),
+        vec3!(-0xc40,  0x1e0,  0xa1f), vec3!( 0x233,  0x1a0, -0xfc4),
+        vec3!( 0x90a,  0x160,  0xd21), vec3!(-0xf8e,  0x120, -0x394),
+        vec3!( 0xde6,  0x0e0, -0x7e0), vec3!(-0x4ef,  0x0a0,  0xf35),
+        vec3!(-0x6a3,  0x060, -0xe8e), vec3!( 0xebb,  0x020,  0x640),
+        vec3!(-0xf15, -0x020,  0x557), vec3!( 0x783, -0x060, -0xe1f),
+        vec3!( 0x400, -0x0a0,  0xf7b), vec3!(-0xd65, -0x0e0, -0x8b5),
+        vec3!( 0xfbe, -0x120, -0x2a0), vec3!(-0x9d2, -0x160,  0xc8e),
+        vec3!(-0x13d, -0x1a0, -0xfdf), vec3!( 0xb9d, -0x1e0,  0xad9),
+        vec3!(-0xfdc, -0x220, -0x028), vec3!( 0xbc5, -0x260, -0xa93),
+        vec3!(-0x188, -0x2a0,  0xfb5), vec3!(-0x974, -0x2e0, -0xc95),
+        vec3!( 0xf6d, -0x320,  0x2e2), vec3!(-0xd47, -0x360,  0x844),
+        vec3!( 0x431, -0x3a0, -0xf02), vec3!( 0x705, -0x3e0,  0xdd8),
+        vec3!(-0xe78, -0x420, -0x572), vec3!( 0xe48, -0x460, -0x5bc),
+        vec3!(-0x6a1, -0x4a0,  0xdcf), vec3!(-0x46b, -0x4e0, -0xe96),
+        vec3!( 0xd0a, -0x520,  0x7ba), vec3!(-0xec0, -0x560,  0x318),
+        vec3!( 0x8bc, -0x5a0, -0xc2b), vec3!( 0x1c5, -0x5e0,  0xec7),
+        vec3!(-0xb35, -0x620, -0x9a3), vec3!( 0xeab, -0x660, -0x077),
+        vec3!(-0xa6c, -0x6a0,  0xa2c), vec3!( 0x0cf, -0x6e0, -0xe6d),
+        vec3!( 0x912, -0x720,  0xb17), vec3!(-0xe0e, -0x760, -0x208),
+        vec3!( 0xba0, -0x7a0, -0x7ec), vec3!(-0x330, -0x7e0,  0xd8f),
+        vec3!(-0x6bc, -0x820, -0xc07), vec3!( 0xcf2, -0x860,  0x445),
+        vec3!(-0xc4a, -0x8a0,  0x588), vec3!( 0x542, -0x8e0, -0xc3b),
+        vec3!( 0x454, -0x920,  0xc69), vec3!(-0xb6b, -0x960, -0x625),
+        vec3!( 0xc64, -0x9a0, -0x323), vec3!(-0x6e9, -0x9e0,  0xa86),
+        vec3!(-0x1fb, -0xa20, -0xc3b), vec3!( 0x98f, -0xa60,  0x78d),
+        vec3!(-0xbee, -0xaa0,  0x0df), vec3!( 0x80e, -0xae0, -0x889),
+        vec3!(-0x02b, -0xb20,  0xb80), vec3!(-0x77a, -0xb60, -0x868),
+        vec3!( 0xaf0, -0xba0,  0x11f), vec3!(-0x89b, -0xbe0,  0x666),
+        vec3!( 0x1f8, -0xc20, -0xa41), vec3!( 0x551, -0xc60,  0x8a3),
+        vec3!(-0x974, -0xca0, -0x2b1), vec3!( 0x87e, -0xce0, -0x441),
+        vec3!(-0x344, -0xd20,  0x88c), vec3!(-0x33b, -0xd60, -0x82a),
+        vec3!( 0x78b, -0xda0,  0x3ab), vec3!(-0x7a3, -0xde0,  0x246),
+        vec3!( 0x3dd, -0xe20, -0x672), vec3!( 0x168, -0xe60,  0x6e2),
+        vec3!(-0x541, -0xea0, -0x3cf), vec3!( 0x5db, -0xee0, -0x0ac),
+        vec3!(-0x36c, -0xf20,  0x3f1), vec3!(-0x01e, -0xf60, -0x46d),
+        vec3!( 0x265, -0xfa0,  0x27a), vec3!(-0x1fe, -0xfe0, -0x01f),
+    ];
+}
+
+/// Every this many stars, one is on the near shell and drawn larger
+const NEAR_EVERY: usize = 4;
+
+/// Radius of the near and far shells of stars, in distances of the camera
+/// from the center of the scene
+const SHELLS: (IFixed, IFixed) = (2, 3);
+
+/// Shade of the ground below the horizon, see
+/// [`fill::dither`](crate::fill::dither)
+const GROUND_SHADE: u8 = 2;
+
+/// One unit of [`Raw`], the length of the star directions
+const UNIT: IFixed = 0x1000;
+
+const _: () = assert!(
+    (SHELLS.1 + 1) as i32*UNIT as i32 <= IFixed::MAX as i32,
+    "the far shell of stars does not fit an IFixed",
+);
+
+/// What is drawn behind the scene
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Backdrop {
+    /// Stars all around, the nearer sweeping past faster
+    Starfield,
+
+    /// A dithered ground up to a solid horizon line
+    Horizon,
+}
+
+impl Backdrop {
+
+    /// Draw the backdrop within `screen` for a scene turned by `rotation`
+    /// and centered on `center`, projected with `P`
+    /// 
+    /// Pixels are only ever set, so it is drawn into a cleared frame before
+    /// the scene.
+    pub fn draw<P: Project, F: FnMut(u32, u32)>(
+        self,
+        rotation: &Mat3,
+        center: Vec2,
+        screen: &Rect,
+        put_pixel: F,
+    ) {
+        match self {
+            Backdrop::Starfield => draw_stars::<P, F>(rotation, center, screen, put_pixel),
+            Backdrop::Horizon => draw_horizon::<P, F>(rotation, center, screen, put_pixel),
+        }
+    }
+}
+
+/// Draw every star in front of the camera, the near ones two pixels across
+fn draw_stars<P: Project, F: FnMut(u32, u32)>(
+    rotation: &Mat3,
+    center: Vec2,
+    screen: &Rect,
+    mut put_pixel: F,
+) {
+    // With the camera a unit from the center of the scene, as the
+    // projection is the same at any scale
+    for i in 0..STARS.len() {
+        let near = i % NEAR_EVERY == 0;
+        let shell = if near { SHELLS.0 } else { SHELLS.1 };
+        let d = rotation.apply(STARS.load_at(i));
+        let view: Vec3<World> = vec3!(d.x*shell, d.y*shell, d.z*shell + UNIT);
+        if view.z < NEAR {
+            continue;
+        }
+
+        let star = P::project(view) + center;
+        let size = if near { 2 } else { 1 };
+        for y in star.y..star.y + size {
+            for x in star.x..star.x + size {
+                if screen.contains(vec2!(x, y)) {
+                    put_pixel(x as u32, y as u32);
+                }
+            }
+        }
+    }
+}
+
+/// Draw the ground below the horizon and the horizon line over it
+/// 
+/// A pixel shows the ground if the ray through it from the camera heads
+/// down. With the ray in view space being the offset of the pixel from the
+/// center and the focal length of the projection, that is where a linear
+/// function of the offset is positive, so each row of ground is a single
+/// span and takes one divide.
+fn draw_horizon<P: Project, F: FnMut(u32, u32)>(
+    rotation: &Mat3,
+    center: Vec2,
+    screen: &Rect,
+    mut put_pixel: F,
+) {
+    let down: Vec3<Raw> = rotation.apply(vec3!(0, UNIT, 0));
+
+    // Pixels off center of a point as far to the side as it is deep
+    let focal = P::project(vec3!(UNIT, 0, UNIT)).x as i32;
+    let (a, b, c) = (down.x as i32, down.y as i32, down.z as i32*focal);
+
+    for y in screen.min.y..screen.max.y {
+        // Ground where a*x + row > 0, with x off center
+        let row = b*(y - center.y) as i32 + c;
+        let (lo, hi) = if a > 0 {
+            ((-row).div_euclid(a) + 1, i32::MAX)
+        } else if a < 0 {
+            (i32::MIN, (row - 1).div_euclid(-a) + 1)
+        } else if row > 0 {
+            (i32::MIN, i32::MAX)
+        } else {
+            continue;
+        };
+        let lo = lo.saturating_add(center.x as i32).max(screen.min.x as i32);
+        let hi = hi.saturating_add(center.x as i32).min(screen.max.x as i32);
+
+        // Only visit the columns the dither sets in this row
+        for phase in 0..4 {
+            if !dither(GROUND_SHADE, phase, y as u32) {
+                continue;
+            }
+            let first = lo + (phase as i32 - lo).rem_euclid(4);
+            for x in (first..hi).step_by(4) {
+                put_pixel(x as u32, y as u32);
+            }
+        }
+    }
+
+    // Looking straight up or down, the horizon is out of sight
+    let length_squared = a*a + b*b;
+    if length_squared == 0 {
+        return;
+    }
+
+    // Point of the horizon nearest the center, and the horizon well past
+    // the screen either way from it
+    let nearest = (-c*a/length_squared, -c*b/length_squared);
+    if nearest.0.abs() > GUARD_RADIUS as i32 || nearest.1.abs() > GUARD_RADIUS as i32 {
+        return;
+    }
+    let longest = a.abs().max(b.abs());
+    let along = (-b*GUARD_RADIUS as i32/longest, a*GUARD_RADIUS as i32/longest);
+    let end = |sign: i32| vec2!(
+        (nearest.0 + sign*along.0) as IFixed + center.x,
+        (nearest.1 + sign*along.1) as IFixed + center.y
+    );
+    draw_line(put_pixel, end(-1), end(1), Endpoints::Both, Style::Solid, screen);
+}
@@ -0,0 +1,274 @@
+//! Bounding spheres of whole objects
+//! 
+//! An object entirely off screen still has every one of its vertices
+//! transformed and every segment clipped away one at a time. Each object
+//! declared with [`scene!`](crate::scene!) has a [`Sphere`] in program memory
+//! holding all of its vertices, and [`Sphere::on_screen`] transforms only its
+//! center and projects the corners of the box around it, so that an object
+//! outside the view is skipped before any of its vertices are transformed.
+
+use crate::animation::{Keyframe, Part};
+use crate::clip::{Rect, NEAR};
+use crate::vec::*;
+
+/// Pixels the projected box is widened by on every side
+/// 
+/// Covers the rounding of the rotation matrix and of the perspective
+/// divide, which may move a vertex slightly outside the exact projection of
+/// its sphere.
+const MARGIN: IFixed = 2;
+
+/// How far the vertices of an object reach in every pose
+struct Reach {
+    /// Corners of the bounding box
+    min: [i32; 3],
+    max: [i32; 3],
+
+    /// Distance to the farthest point from where it was measured, rounded up
+    farthest: u32,
+}
+
+impl Reach {
+
+    /// Reach of `verts` and `morph` in every pose of the `parts`, measured
+    /// from `from`, see [`Sphere::around`]
+    const fn of(verts: &[Vec3], morph: &[Vec3], parts: &[Part], keys: &[Keyframe], from: [i32; 3]) -> Self {
+        let mut reach = Self { min: [i32::MAX; 3], max: [i32::MIN; 3], farthest: 0 };
+        let sets = [verts, morph];
+        let mut s = 0;
+        while s < sets.len() {
+            let mut i = 0;
+            while i < sets[s].len() {
+                let v = coords(sets[s][i]);
+                match part_of(parts, i) {
+                    None => reach = reach.add(v, 0, from),
+                    Some((p, first)) => {
+                        let pivot = coords(parts[p].pivot);
+                        // One more for the rounding of the turn
+                        let radius = distance(v, pivot) + 1;
+                        let mut k = first;
+                        while k < first + parts[p].keys as usize {
+                            let offset = coords(keys[k].offset);
+                            let moved = [pivot[0] + offset[0], pivot[1] + offset[1], pivot[2] + offset[2]];
+                            reach = reach.add(moved, radius, from);
+                            k += 1;
+                        }
+                    }
+                }
+                i += 1;
+            }
+            s += 1;
+        }
+        reach
+    }
+
+    /// Reach taking in the sphere of `radius` around `center` as well
+    const fn add(mut self, center: [i32; 3], radius: u32, from: [i32; 3]) -> Self {
+        let mut k = 0;
+        while k < 3 {
+            if center[k] - (radius as i32) < self.min[k] {
+                self.min[k] = center[k] - radius as i32;
+            }
+            if center[k] + (radius as i32) > self.max[k] {
+                self.max[k] = center[k] + radius as i32;
+            }
+            k += 1;
+        }
+        let farthest = distance(center, from).saturating_add(radius);
+        if farthest > self.farthest {
+            self.farthest = farthest;
+        }
+        self
+    }
+}
+
+/// Coordinates of a vertex, widened so that moving it cannot overflow
+const fn coords(v: Vec3) -> [i32; 3] {
+    [v.x as i32, v.y as i32, v.z as i32]
+}
+
+/// Distance between two points, rounded up
+const fn distance(a: [i32; 3], b: [i32; 3]) -> u32 {
+    let mut squared = 0u32;
+    let mut k = 0;
+    while k < 3 {
+        let d = (a[k] - b[k]).unsigned_abs();
+        squared = squared.saturating_add(d.saturating_mul(d));
+        k += 1;
+    }
+
+    // Round the root up so the farthest vertex is still inside
+    let root = sqrt(squared) as u32;
+    if root*root < squared {
+        root + 1
+    } else {
+        root
+    }
+}
+
+/// Index of the part vertex `i` belongs to, if any, and of its first
+/// keyframe
+const fn part_of(parts: &[Part], i: usize) -> Option<(usize, usize)> {
+    let mut first = 0;
+    let mut p = 0;
+    while p < parts.len() {
+        if parts[p].first as usize <= i && i < parts[p].end as usize {
+            return Some((p, first));
+        }
+        first += parts[p].keys as usize;
+        p += 1;
+    }
+    None
+}
+
+/// Sphere holding every vertex of an object, in model space
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: IFixed,
+}
+
+impl Sphere {
+
+    /// Sphere that is never culled, for objects reaching past the range of
+    /// [`IFixed`]
+    pub const EVERYWHERE: Self = Self::new(vec3!(0, 0, 0), IFixed::MAX);
+
+    pub const fn new(center: Vec3, radius: IFixed) -> Self {
+        Self { center, radius }
+    }
+
+    /// Sphere about the middle of the bounding box of `verts` and of
+    /// `morph`, the vertices an object morphs towards, holding all of them
+    /// in every pose of the `parts` following `keys`
+    /// 
+    /// A vertex of a part stays as far from the pivot as it is in the mesh
+    /// however the part turns, and the part moves between the offsets of its
+    /// keyframes in straight lines, so the vertex is held by the spheres of
+    /// that distance around the pivot moved by each offset.
+    /// 
+    /// Not the smallest sphere, which the mesh pipeline computes for
+    /// imported models, but cheap enough to evaluate at compile time.
+    pub const fn around(verts: &[Vec3], morph: &[Vec3], parts: &[Part], keys: &[Keyframe]) -> Self {
+        if verts.is_empty() && morph.is_empty() {
+            return Self::new(vec3!(0, 0, 0), 0);
+        }
+
+        let reach = Reach::of(verts, morph, parts, keys, [0; 3]);
+        let mut center = [0; 3];
+        let mut k = 0;
+        while k < 3 {
+            center[k] = (reach.min[k] + reach.max[k]) >> 1;
+            if center[k] < IFixed::MIN as i32 || center[k] > IFixed::MAX as i32 {
+                return Self::EVERYWHERE;
+            }
+            k += 1;
+        }
+        let radius = Reach::of(verts, morph, parts, keys, center).farthest;
+        if radius >= IFixed::MAX as u32 {
+            return Self::EVERYWHERE;
+        }
+        Self::new(vec3!(center[0] as IFixed, center[1] as IFixed, center[2] as IFixed), radius as IFixed)
+    }
+
+    /// Whether any part of the sphere may show within `screen`
+    /// 
+    /// `to_view` moves a point from model space into view space without
+    /// scaling it, as [`view_transform`](crate::transform::view_transform)
+    /// does, and `to_screen` projects a point in view space at or beyond the
+    /// near plane onto the screen. Only the center is transformed, and the
+    /// corners of the box around the sphere nearest to and furthest from the
+    /// camera are projected.
+    /// 
+    /// A sphere entirely in front of the near plane is never on screen, and
+    /// one crossing it always is, as its segments are clipped against the
+    /// near plane later.
+    pub fn on_screen<T, S>(&self, to_view: T, to_screen: S, screen: &Rect) -> bool
+    where
+        T: Fn(Vec3) -> Vec3,
+        S: Fn(Vec3) -> Vec2,
+    {
+        if *self == Self::EVERYWHERE {
+            return true;
+        }
+        let center = to_view(self.center);
+        let r = self.radius;
+        let (near, far) = (center.z.saturating_sub(r), center.z.saturating_add(r));
+        if far < NEAR {
+            return false;
+        }
+        if near < NEAR {
+            return true;
+        }
+
+        // Each side of the box projects furthest out at one depth or the
+        // other, depending on the side of the camera it is on
+        let (x0, x1) = (center.x.saturating_sub(r), center.x.saturating_add(r));
+        let (y0, y1) = (center.y.saturating_sub(r), center.y.saturating_add(r));
+        let corners = [
+            to_screen(vec3!(x0, y0, near)),
+            to_screen(vec3!(x1, y1, near)),
+            to_screen(vec3!(x0, y0, far)),
+            to_screen(vec3!(x1, y1, far)),
+        ];
+
+        let mut min = corners[0];
+        let mut max = corners[0];
+        for corner in &corners[1..] {
+            min = vec2!(min.x.min(corner.x), min.y.min(corner.y));
+            max = vec2!(max.x.max(corner.x), max.y.max(corner.y));
+        }
+        max.x >= screen.min.x - MARGIN && min.x < screen.max.x + MARGIN
+            && max.y >= screen.min.y - MARGIN && min.y < screen.max.y + MARGIN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::AnimationState;
+    use crate::rotor::Axis;
+
+    avr_progmem::progmem! {
+        static progmem PARTS: [Part; 1] = [Part::new(2..4, Axis::Y, vec3!(0x400, 0, 0), 3)];
+        static progmem KEYS: [Keyframe; 3] = [
+            Keyframe::new(0, vec3!(0, 0, 0), 0),
+            Keyframe::new(20, vec3!(0, 0x800, 0), 0x4000),
+            Keyframe::new(40, vec3!(0, 0x800, 0), 0x8000),
+        ];
+    }
+
+    const VERTS: [Vec3; 4] = [
+        vec3!(-0x400, 0, 0),
+        vec3!(0x400, 0, 0),
+        vec3!(0x400, 0, 0x400),
+        vec3!(0xc00, 0, 0),
+    ];
+
+    fn holds(sphere: &Sphere, v: Vec3) -> bool {
+        let d = coords(v - sphere.center);
+        (d[0]*d[0] + d[1]*d[1] + d[2]*d[2]) as u32 <= (sphere.radius as u32)*(sphere.radius as u32)
+    }
+
+    #[test]
+    fn sphere_holds_every_vertex() {
+        let morph = [vec3!(0, 0x600, 0); 4];
+        let sphere = Sphere::around(&VERTS, &morph, &[], &[]);
+        assert!(VERTS.iter().chain(&morph).all(|&v| holds(&sphere, v)));
+    }
+
+    #[test]
+    fn sphere_holds_every_pose() {
+        let sphere = Sphere::around(&VERTS, &[], &PARTS.load(), &KEYS.load());
+        assert!(sphere != Sphere::EVERYWHERE);
+        let mut state = AnimationState::new();
+        for _ in 0..48 {
+            let poses = state.poses(&PARTS, &KEYS);
+            for (i, &v) in VERTS.iter().enumerate() {
+                let posed = crate::animation::pose_vertex(&poses, i, v);
+                assert!(holds(&sphere, posed), "vertex {} at frame {} is outside", i, state.frame());
+            }
+            state.advance(48);
+        }
+    }
+}
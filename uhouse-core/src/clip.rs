@@ -0,0 +1,269 @@
+//! Line clipping
+//! 
+//! Segments are clipped with the Cohen–Sutherland algorithm. Intersections
+//! are computed in `i32`, so even segments spanning the whole projection
+//! range clip without overflow.
+//! 
+//! Each endpoint moves onto at most one vertical and one horizontal edge, so
+//! a segment takes at most [`MAX_CLIP_STEPS`] steps to clip. With the
+//! `bounded` feature the loop stops there whatever rounding does, dropping
+//! the segment, so its cost is fixed.
+
+use crate::units::{Screen, SubPixel};
+use crate::vec::*;
+
+/// Most endpoint moves a segment takes to clip, two for each endpoint
+pub const MAX_CLIP_STEPS: u8 = 4;
+
+/// Screen rectangle with an exclusive lower-right corner
+/// 
+/// In whole pixels by default, or in sixteenths of a pixel for clipping
+/// lines with the `subpixel` feature.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Rect<U = Screen> {
+    pub min: Vec2<U>,
+    pub max: Vec2<U>,
+}
+
+impl<U> Rect<U> {
+
+    /// Rectangle from the origin to `width` by `height`, such as the whole
+    /// screen
+    pub const fn sized(width: IFixed, height: IFixed) -> Self {
+        Self {
+            min: vec2!(0, 0),
+            max: vec2!(width, height),
+        }
+    }
+
+    pub fn contains(&self, v: Vec2<U>) -> bool {
+        v.x >= self.min.x && v.x < self.max.x && v.y >= self.min.y && v.y < self.max.y
+    }
+}
+
+/// Depth of the near plane in view space
+/// 
+/// Geometry closer to the camera than this is clipped away before the
+/// perspective divide, which would otherwise blow it up or mirror it.
+pub const NEAR: IFixed = 0x400;
+
+/// Screen position standing in for a vertex in front of the near plane
+/// 
+/// Projected vertices never land here, so segments with such an endpoint are
+/// recognized and clipped against the near plane with [`clip_near`].
+pub const BEHIND_NEAR: Vec2 = vec2!(IFixed::MIN, IFixed::MIN);
+
+/// [`BEHIND_NEAR`] in sixteenths of a pixel
+pub const SUBPIXEL_BEHIND_NEAR: Vec2<SubPixel> = vec2!(IFixed::MIN, IFixed::MIN);
+
+/// Width of the guard band around the screen, in pixels
+const GUARD: IFixed = 256;
+
+/// Screen extended by the guard band on every side
+/// 
+/// The limit on the corners of filled faces, see
+/// [`fill_triangle`](crate::fill::fill_triangle), which are stepped along
+/// their edges without being clipped. Lines are clipped to the screen
+/// exactly and need no band.
+pub fn guard_band(screen: &Rect) -> Rect {
+    Rect {
+        min: vec2!(screen.min.x - GUARD, screen.min.y - GUARD),
+        max: vec2!(screen.max.x + GUARD, screen.max.y + GUARD),
+    }
+}
+
+/// Clip a segment in view space to the near plane
+/// 
+/// Returns the part of the segment at or beyond [`NEAR`], or `None` if
+/// there is none.
+pub fn clip_near(v0: Vec3, v1: Vec3) -> Option<(Vec3, Vec3)> {
+    match (v0.z < NEAR, v1.z < NEAR) {
+        (false, false) => Some((v0, v1)),
+        (true, true) => None,
+        (true, false) => Some((near_intersection(v1, v0), v1)),
+        (false, true) => Some((v0, near_intersection(v0, v1))),
+    }
+}
+
+/// Point where the segment from `inside` to `outside` crosses the near
+/// plane
+fn near_intersection(inside: Vec3, outside: Vec3) -> Vec3 {
+    // Fraction of the way from `inside` to `outside`, with 12 fractional bits
+    let t = ((inside.z as i32 - NEAR as i32) << 12) / (inside.z as i32 - outside.z as i32);
+    let at = inside.lerp(outside, t as IFixed);
+    vec3!(at.x, at.y, NEAR)
+}
+
+const LEFT: u8 = 1 << 0;
+const RIGHT: u8 = 1 << 1;
+const TOP: u8 = 1 << 2;
+const BOTTOM: u8 = 1 << 3;
+
+/// Which sides of `bounds` a point lies beyond
+fn outcode<U>(x: i32, y: i32, bounds: &Rect<U>) -> u8 {
+    let mut code = 0;
+    if x < bounds.min.x as i32 {
+        code |= LEFT;
+    } else if x >= bounds.max.x as i32 {
+        code |= RIGHT;
+    }
+    if y < bounds.min.y as i32 {
+        code |= TOP;
+    } else if y >= bounds.max.y as i32 {
+        code |= BOTTOM;
+    }
+    code
+}
+
+/// Clip a segment to a rectangle
+/// 
+/// Returns the part of the segment inside `bounds`, or `None` if there is
+/// none. Endpoints already inside are returned unchanged.
+pub fn clip_line<U>(v0: Vec2<U>, v1: Vec2<U>, bounds: &Rect<U>) -> Option<(Vec2<U>, Vec2<U>)> {
+    let (mut x0, mut y0) = (v0.x as i32, v0.y as i32);
+    let (mut x1, mut y1) = (v1.x as i32, v1.y as i32);
+    let mut code0 = outcode(x0, y0, bounds);
+    let mut code1 = outcode(x1, y1, bounds);
+
+    // The lower-right corner is exclusive
+    let (min_x, max_x) = (bounds.min.x as i32, bounds.max.x as i32 - 1);
+    let (min_y, max_y) = (bounds.min.y as i32, bounds.max.y as i32 - 1);
+
+    #[cfg(feature = "bounded")]
+    let mut steps = 0;
+
+    loop {
+        if code0 | code1 == 0 {
+            return Some((
+                vec2!(x0 as IFixed, y0 as IFixed),
+                vec2!(x1 as IFixed, y1 as IFixed),
+            ));
+        }
+        if code0 & code1 != 0 {
+            return None;
+        }
+
+        #[cfg(feature = "bounded")]
+        {
+            if steps == MAX_CLIP_STEPS {
+                return None;
+            }
+            steps += 1;
+        }
+
+        // Move the endpoint outside onto the edge it lies beyond
+        let code = if code0 != 0 { code0 } else { code1 };
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let (x, y) = if code & TOP != 0 {
+            (x0 + dx*(min_y - y0)/dy, min_y)
+        } else if code & BOTTOM != 0 {
+            (x0 + dx*(max_y - y0)/dy, max_y)
+        } else if code & LEFT != 0 {
+            (min_x, y0 + dy*(min_x - x0)/dx)
+        } else {
+            (max_x, y0 + dy*(max_x - x0)/dx)
+        };
+
+        if code == code0 {
+            (x0, y0) = (x, y);
+            code0 = outcode(x0, y0, bounds);
+        } else {
+            (x1, y1) = (x, y);
+            code1 = outcode(x1, y1, bounds);
+        }
+    }
+}
+
+/// Mark where a segment leaves the screen with a short tick across the
+/// screen edge
+/// 
+/// With the `clip-debug` feature every segment is passed through here, to
+/// show on the device where clipping takes place.
+#[cfg(feature = "clip-debug")]
+pub fn mark_clip_points<F: FnMut(u32, u32)>(put_pixel: &mut F, v0: Vec2, v1: Vec2, screen: &Rect) {
+    let (c0, c1) = match clip_line(v0, v1, screen) {
+        Some(segment) => segment,
+        None => return,
+    };
+    for (clipped, original) in [(c0, v0), (c1, v1)] {
+        if clipped == original {
+            continue;
+        }
+        let on_side = clipped.x == screen.min.x || clipped.x == screen.max.x - 1;
+        for d in -1..=1 {
+            let tick = if on_side {
+                vec2!(clipped.x, clipped.y + d)
+            } else {
+                vec2!(clipped.x + d, clipped.y)
+            };
+            if screen.contains(tick) {
+                put_pixel(tick.x as u32, tick.y as u32);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Rect = Rect::sized(128, 64);
+
+    #[test]
+    fn inside_segments_are_unchanged() {
+        let (v0, v1) = (vec2!(0, 0), vec2!(127, 63));
+        assert!(clip_line(v0, v1, &SCREEN) == Some((v0, v1)));
+    }
+
+    #[test]
+    fn outside_segments_are_dropped() {
+        assert!(clip_line(vec2!(-10, -10), vec2!(200, -1), &SCREEN).is_none());
+        assert!(clip_line(vec2!(128, 0), vec2!(128, 63), &SCREEN).is_none());
+        assert!(clip_line(vec2!(-50, 40), vec2!(10, 200), &SCREEN).is_none());
+    }
+
+    #[test]
+    fn crossing_segments_end_on_the_edges() {
+        let (v0, v1) = clip_line(vec2!(-100, 32), vec2!(300, 32), &SCREEN).unwrap();
+        assert!(v0 == vec2!(0, 32) && v1 == vec2!(127, 32));
+
+        let (v0, v1) = clip_line(vec2!(-64, -32), vec2!(192, 96), &SCREEN).unwrap();
+        assert!(SCREEN.contains(v0) && SCREEN.contains(v1));
+        assert!(v0.x == 0 || v0.y == 0);
+        assert!(v1.x == 127 || v1.y == 63);
+    }
+
+    #[test]
+    fn segments_across_the_whole_range_clip_without_overflow() {
+        let corners = [
+            vec2!(IFixed::MIN, IFixed::MIN),
+            vec2!(IFixed::MIN, IFixed::MAX),
+            vec2!(IFixed::MAX, IFixed::MIN),
+            vec2!(IFixed::MAX, IFixed::MAX),
+        ];
+        for v0 in corners {
+            for v1 in corners {
+                if let Some((c0, c1)) = clip_line(v0, v1, &SCREEN) {
+                    assert!(SCREEN.contains(c0) && SCREEN.contains(c1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn near_plane_cuts_segments_at_its_depth() {
+        let far = vec3!(0x100, 0, 0x1000);
+        assert!(clip_near(far, far) == Some((far, far)));
+        assert!(clip_near(vec3!(0, 0, 0), vec3!(0, 0, NEAR - 1)).is_none());
+
+        let (v0, v1) = clip_near(vec3!(0x100, 0, 0), far).unwrap();
+        assert_eq!(v0.z, NEAR);
+        assert!(v1 == far);
+        assert_eq!(v0.x, 0x100);
+
+        let (v0, v1) = clip_near(far, vec3!(-0x100, 0, -0x1000)).unwrap();
+        assert!(v0 == far);
+        assert_eq!(v1.z, NEAR);
+        assert!(v1.x > 0);
+    }
+}
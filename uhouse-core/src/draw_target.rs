@@ -0,0 +1,64 @@
+//! Drawing into any `embedded-graphics` display
+//! 
+//! The renderer sets pixels through a `put_pixel` sink, which on the device
+//! writes straight into the SSD1306 framebuffer. [`PixelSink`] adapts any
+//! [`DrawTarget`] of [`BinaryColor`] into such a sink instead, so the same
+//! meshes can be drawn on other displays or in the simulator, at the cost of
+//! going through the target for every pixel.
+//! 
+//! ```ignore
+//! let mut sink = PixelSink::new(&mut display);
+//! let screen = sink.screen();
+//! HOUSE.draw(&screen_verts, &screen, mirror, clip_near, portion, |x, y| sink.put_pixel(x, y));
+//! sink.finish()?;
+//! ```
+
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::Point;
+use embedded_graphics_core::pixelcolor::BinaryColor;
+use embedded_graphics_core::Pixel;
+
+use crate::clip::Rect;
+use crate::vec::*;
+
+/// Sink setting pixels on a [`DrawTarget`]
+pub struct PixelSink<'a, D: DrawTarget<Color = BinaryColor>> {
+    target: &'a mut D,
+
+    /// First error returned by the target
+    error: Option<D::Error>,
+}
+
+impl<'a, D: DrawTarget<Color = BinaryColor>> PixelSink<'a, D> {
+    pub fn new(target: &'a mut D) -> Self {
+        Self { target, error: None }
+    }
+
+    /// The area of the target to clip lines to
+    pub fn screen(&self) -> Rect {
+        let area = self.target.bounding_box();
+        let min = vec2!(area.top_left.x as IFixed, area.top_left.y as IFixed);
+        Rect {
+            min,
+            max: min + vec2!(area.size.width as IFixed, area.size.height as IFixed),
+        }
+    }
+
+    /// Turn on the pixel at column `x` and row `y`
+    /// 
+    /// Pixels set after the target returned an error are dropped.
+    pub fn put_pixel(&mut self, x: u32, y: u32) {
+        if self.error.is_some() {
+            return;
+        }
+        let pixel = Pixel(Point::new(x as i32, y as i32), BinaryColor::On);
+        if let Err(error) = self.target.draw_iter(core::iter::once(pixel)) {
+            self.error = Some(error);
+        }
+    }
+
+    /// Return the first error the target returned while drawing, if any
+    pub fn finish(self) -> Result<(), D::Error> {
+        self.error.map_or(Ok(()), Err)
+    }
+}
@@ -0,0 +1,253 @@
+//! Filled faces
+//! 
+//! Besides its wireframe, an object may list faces to fill in, see the
+//! `fills` field of [`scene!`]. Faces are sorted by their average depth and
+//! drawn from the farthest to the nearest, the painter's algorithm, so nearer
+//! faces cover farther ones without a depth buffer. The display has no shades
+//! of gray, so every face is filled with a 4x4 ordered dither of its
+//! [`Fill::shade`], turning pixels off as well as on.
+//! 
+//! Faces are rasterized one scanline at a time, stepping both edges of each
+//! row in fixed point so that a triangle takes three divisions.
+
+use avr_progmem::wrapper::ProgMem;
+
+use crate::clip::{self, Rect};
+use crate::mesh::{winds_front, Mirror};
+use crate::vec::*;
+
+/// Shade of a face with every pixel off
+pub const BLACK: u8 = 0;
+
+/// Shade of a face with every pixel on
+pub const WHITE: u8 = 16;
+
+/// Largest number of faces an object can fill
+/// 
+/// Faces are sorted on the stack, taking five bytes each.
+pub const MAX_FILLS: usize = 16;
+
+/// Thresholds of the 4x4 Bayer matrix
+/// 
+/// A pixel is on if its threshold is below the shade, so each shade turns on
+/// one more pixel of every 4x4 block, spread out as evenly as possible.
+const BAYER: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+
+/// Fractional bits of the edge positions stepped along each row
+const EDGE_FRACTION: u32 = 8;
+
+/// Face to fill, as vertex indices winding clockwise on screen while the
+/// face is seen from the front
+#[derive(Copy, Clone)]
+pub struct Fill {
+    /// Corners of the face, with the third repeated for a triangle
+    pub verts: [u8; 4],
+
+    /// Pixels on out of every 16, from [`BLACK`] to [`WHITE`]
+    pub shade: u8,
+}
+
+impl Fill {
+
+    /// Fill the triangle `a`, `b`, `c`
+    pub const fn triangle(a: u8, b: u8, c: u8, shade: u8) -> Self {
+        Self { verts: [a, b, c, c], shade }
+    }
+
+    /// Fill the convex quad `a`, `b`, `c`, `d`
+    pub const fn quad(a: u8, b: u8, c: u8, d: u8, shade: u8) -> Self {
+        Self { verts: [a, b, c, d], shade }
+    }
+
+    /// Whether every corner is below `num_verts`
+    const fn fits(&self, num_verts: usize) -> bool {
+        let mut k = 0;
+        while k < self.verts.len() {
+            if self.verts[k] as usize >= num_verts {
+                return false;
+            }
+            k += 1;
+        }
+        true
+    }
+
+    /// Vertex indices of the corners
+    fn corners(&self) -> &[u8] {
+        if self.verts[3] == self.verts[2] {
+            &self.verts[..3]
+        } else {
+            &self.verts
+        }
+    }
+}
+
+/// Whether every corner of `fills` is below `num_verts`, for checking fills
+/// at compile time
+pub const fn fills_fit(fills: &[Fill], num_verts: usize) -> bool {
+    let mut i = 0;
+    while i < fills.len() {
+        if !fills[i].fits(num_verts) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Whether the pixel at column `x` and row `y` of a face filled with `shade`
+/// is on
+pub fn dither(shade: u8, x: u32, y: u32) -> bool {
+    BAYER[(y & 3) as usize][(x & 3) as usize] < shade
+}
+
+/// Fill the faces seen from the front, from the farthest to the nearest
+/// 
+/// `position` is given the index of a vertex and returns its position on
+/// screen, and `depth` its depth in view space. Faces are clipped to
+/// `screen`, and `put_pixel` is given the column and row of every pixel
+/// covered and whether to turn it on or off.
+/// 
+/// A face with a corner in front of the near plane or outside the
+/// [guard band](clip::guard_band) is left out rather than clipped, leaving
+/// its outline to the wireframe. The faces of a mirror instance are sorted
+/// on their own and drawn over those of the mesh.
+pub fn draw_fills<const P: usize, S, Z, F>(
+    fills: &ProgMem<[Fill; P]>,
+    position: S,
+    depth: Z,
+    screen: &Rect,
+    mirror: Option<Mirror>,
+    mut put_pixel: F,
+)
+where
+    S: Fn(usize) -> Vec2,
+    Z: Fn(usize) -> IFixed,
+    F: FnMut(u32, u32, bool),
+{
+    let () = AssertFills::<P>::OK;
+
+    let guard = clip::guard_band(screen);
+    let on_screen = |fill: &Fill| {
+        let [a, b, c, d] = fill.verts.map(|i| position(i as usize));
+        [a, b, c, d].iter().all(|&v| guard.contains(v)) && winds_front(a, b, c, mirror)
+    };
+
+    // Depth and index of every face to fill, farthest first
+    let mut order = [(0i32, 0u8); P];
+    let mut count = 0;
+    for (i, fill) in fills.iter().enumerate() {
+        if !on_screen(&fill) {
+            continue;
+        }
+        let corners = fill.corners();
+        let sum: i32 = corners.iter().map(|&i| depth(i as usize) as i32).sum();
+        let z = sum / corners.len() as i32;
+
+        let mut k = count;
+        while k > 0 && order[k - 1].0 < z {
+            order[k] = order[k - 1];
+            k -= 1;
+        }
+        order[k] = (z, i as u8);
+        count += 1;
+    }
+
+    for &(_, i) in &order[..count] {
+        let fill = fills.load_at(i as usize);
+        let [a, b, c, d] = fill.verts.map(|i| position(i as usize));
+        fill_triangle(&mut put_pixel, a, b, c, fill.shade, screen);
+        if fill.verts[3] != fill.verts[2] {
+            fill_triangle(&mut put_pixel, a, c, d, fill.shade, screen);
+        }
+    }
+}
+
+/// Fill a triangle with a dither of `shade`, clipped to `screen`
+/// 
+/// Rows from the top corner down to, but not including, the bottom one are
+/// filled from edge to edge, rounded to the nearest column. Corners must lie
+/// within the [guard band](clip::guard_band) so that edges step along without
+/// overflow.
+pub fn fill_triangle<F: FnMut(u32, u32, bool)>(
+    put_pixel: &mut F,
+    a: Vec2,
+    b: Vec2,
+    c: Vec2,
+    shade: u8,
+    screen: &Rect,
+) {
+    let mut corners = [a, b, c];
+    corners.sort_unstable_by_key(|v| v.y);
+    let [top, middle, bottom] = corners;
+
+    let long = Edge::new(top, bottom);
+    fill_rows(put_pixel, &long, &Edge::new(top, middle), top.y..middle.y, shade, screen);
+    fill_rows(put_pixel, &long, &Edge::new(middle, bottom), middle.y..bottom.y, shade, screen);
+}
+
+/// Fill the rows of `rows` between two edges
+fn fill_rows<F: FnMut(u32, u32, bool)>(
+    put_pixel: &mut F,
+    e0: &Edge,
+    e1: &Edge,
+    rows: core::ops::Range<IFixed>,
+    shade: u8,
+    screen: &Rect,
+) {
+    let first = rows.start.max(screen.min.y);
+    let end = rows.end.min(screen.max.y);
+    if first >= end {
+        return;
+    }
+
+    let half = 1 << (EDGE_FRACTION - 1);
+    let (mut x0, mut x1) = (e0.x_at(first), e1.x_at(first));
+    for y in first..end {
+        let (left, right) = if x0 <= x1 { (x0, x1) } else { (x1, x0) };
+        let left = ((left + half) >> EDGE_FRACTION).max(screen.min.x as i32);
+        let right = ((right + half) >> EDGE_FRACTION).min(screen.max.x as i32 - 1);
+        for x in left..=right {
+            put_pixel(x as u32, y as u32, dither(shade, x as u32, y as u32));
+        }
+        x0 += e0.step;
+        x1 += e1.step;
+    }
+}
+
+/// Edge of a triangle, stepped along one row at a time
+struct Edge {
+    /// Upper end of the edge
+    start: Vec2,
+
+    /// Change in column per row, with [`EDGE_FRACTION`] fractional bits
+    step: i32,
+}
+
+impl Edge {
+    fn new(from: Vec2, to: Vec2) -> Self {
+        let rows = (to.y - from.y) as i32;
+        let step = if rows == 0 {
+            0
+        } else {
+            ((to.x - from.x) as i32) * (1 << EDGE_FRACTION) / rows
+        };
+        Self { start: from, step }
+    }
+
+    /// Column of the edge at row `y`, with [`EDGE_FRACTION`] fractional bits
+    fn x_at(&self, y: IFixed) -> i32 {
+        ((self.start.x as i32) << EDGE_FRACTION) + self.step*(y - self.start.y) as i32
+    }
+}
+
+/// Compile-time check that `P` faces can be sorted
+struct AssertFills<const P: usize>;
+
+impl<const P: usize> AssertFills<P> {
+    const OK: () = assert!(P <= MAX_FILLS, "object has more fills than MAX_FILLS");
+}
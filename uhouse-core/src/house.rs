@@ -0,0 +1,215 @@
+//! The built-in house scene
+//! 
+//! A house with a door and windows, the car parked behind it, a tree and a
+//! fence, spinning in place while orbiting the center of the screen. With
+//! keyframes, the car drives around the house and the door swings open as it
+//! passes.
+
+use crate::animation::{Keyframe, Part};
+use crate::fill::{self, Fill};
+use crate::mesh::{CULLED, DASHED, DOTTED};
+use crate::morph;
+use crate::rotor::Axis;
+use crate::sine::degrees;
+use crate::vec::*;
+
+/// How far into the screen to render the house
+pub const MESH_DEPTH: IFixed = 0x2a00;
+
+/// Spin of 3 degrees per frame
+pub const ROT0: u16 = degrees(3);
+
+/// Orbit of 1 degree per frame
+pub const LOC0: u16 = degrees(1);
+
+/// Placement of the car, parked behind the house
+const CAR_AT: Vec3 = vec3!(0x000, 0x800, 0xb00);
+
+/// Placement of the tree, beside the house
+const TREE_AT: Vec3 = vec3!(0x1000, 0x800, 0x000);
+
+/// Placement of the fence, against the left wall of the house
+const FENCE_AT: Vec3 = vec3!(-0x800, 0x800, 0x000);
+
+/// Frames for the car to drive once around the house
+const LAP: u16 = 360;
+
+/// The door, swinging on its hinge at the left of the doorway
+const DOOR: Part = Part::new(9..13, Axis::Y, vec3!(-0x600, 0x000, -0x800), 4);
+
+/// The car, driving around the middle of the house
+const CAR: Part = Part::new(21..35, Axis::Y, vec3!(0x000, 0x000, 0x000), 5);
+
+/// Parts staying where the mesh has them
+const STILL: Vec3 = vec3!(0x000, 0x000, 0x000);
+
+scene! {
+
+    /// The house scene
+    pub object HOUSE {
+        depth: MESH_DEPTH,
+        spin: ROT0,
+        orbit: LOC0,
+        verts: [
+            // Cube
+            vec3!( 0x800,  0x800,  0x800),
+            vec3!(-0x800,  0x800,  0x800),
+            vec3!(-0x800, -0x800,  0x800),
+            vec3!( 0x800, -0x800,  0x800),
+            vec3!( 0x800,  0x800, -0x800),
+            vec3!(-0x800,  0x800, -0x800),
+            vec3!(-0x800, -0x800, -0x800),
+            vec3!( 0x800, -0x800, -0x800),
+
+            // Roof
+            vec3!( 0x000, -0x1400, 0x000),
+
+            // Door
+            vec3!(-0x100,  0x800, -0x800),
+            vec3!(-0x600,  0x800, -0x800),
+            vec3!(-0x600,  0x200, -0x800),
+            vec3!(-0x100,  0x200, -0x800),
+
+            // Front window
+            vec3!( 0x500, -0x200, -0x800),
+            vec3!( 0x200, -0x200, -0x800),
+            vec3!( 0x200, -0x500, -0x800),
+            vec3!( 0x500, -0x500, -0x800),
+
+            // Left window
+            vec3!(-0x800,  0x500,  0x200),
+            vec3!(-0x800,  0x500,  0x500),
+            vec3!(-0x800,  0x200,  0x500),
+            vec3!(-0x800,  0x200,  0x200),
+
+            // Car
+            vec3!(-0x800,  0x000,  0x000).translate(CAR_AT),
+            vec3!( 0x800,  0x000,  0x000).translate(CAR_AT),
+            vec3!( 0x800, -0x300,  0x000).translate(CAR_AT),
+            vec3!( 0x400, -0x300,  0x000).translate(CAR_AT),
+            vec3!( 0x200, -0x600,  0x000).translate(CAR_AT),
+            vec3!(-0x600, -0x600,  0x000).translate(CAR_AT),
+            vec3!(-0x800, -0x300,  0x000).translate(CAR_AT),
+            vec3!(-0x800,  0x000,  0x700).translate(CAR_AT),
+            vec3!( 0x800,  0x000,  0x700).translate(CAR_AT),
+            vec3!( 0x800, -0x300,  0x700).translate(CAR_AT),
+            vec3!( 0x400, -0x300,  0x700).translate(CAR_AT),
+            vec3!( 0x200, -0x600,  0x700).translate(CAR_AT),
+            vec3!(-0x600, -0x600,  0x700).translate(CAR_AT),
+            vec3!(-0x800, -0x300,  0x700).translate(CAR_AT),
+
+            // Tree
+            vec3!( 0x000,  0x000,  0x000).translate(TREE_AT),
+            vec3!( 0x000, -0x1c00, 0x000).translate(TREE_AT),
+            vec3!( 0x000, -0x600,  0x000).translate(TREE_AT), // Branch base
+            vec3!( 0x400, -0x1800, 0x000).translate(TREE_AT),
+            vec3!(-0x400, -0x1800, 0x000).translate(TREE_AT),
+            vec3!( 0x000, -0x1800, 0x400).translate(TREE_AT),
+            vec3!( 0x000, -0x1800,-0x400).translate(TREE_AT),
+
+            // Fence
+            vec3!( 0x000,  0x000,  0x000).translate(FENCE_AT),
+            vec3!(-0xc00,  0x000,  0x000).translate(FENCE_AT),
+            vec3!(-0xc00, -0x600,  0x000).translate(FENCE_AT),
+            vec3!(-0xa00, -0x800,  0x000).translate(FENCE_AT),
+            vec3!(-0x800, -0x600,  0x000).translate(FENCE_AT),
+            vec3!(-0x600, -0x800,  0x000).translate(FENCE_AT),
+            vec3!(-0x400, -0x600,  0x000).translate(FENCE_AT),
+            vec3!(-0x200, -0x800,  0x000).translate(FENCE_AT),
+            vec3!( 0x000, -0x600,  0x000).translate(FENCE_AT),
+            vec3!(-0x800,  0x000,  0x000).translate(FENCE_AT),
+            vec3!(-0x400,  0x000,  0x000).translate(FENCE_AT),
+
+            // Welcome mat
+            vec3!(-0x100,  0x800, -0x900),
+            vec3!(-0x600,  0x800, -0x900),
+            vec3!(-0x600,  0x800, -0xc00),
+            vec3!(-0x100,  0x800, -0xc00),
+        ],
+
+        // Each strip is a vertex count followed by that many vertex indices,
+        // and draws a line between every pair of consecutive vertices. A lone
+        // edge is a strip of two. The edges of the house are culled, each
+        // with the two faces it borders as a little endian face mask, and the
+        // fence and the mat are set apart from the outlines as construction
+        // lines.
+        strips: [
+            CULLED | 2, 0x12, 0x00, 0, 1,               // Cube back, and floor
+            CULLED | 2, 0x0a, 0x00, 1, 2,               //            and left
+            CULLED | 2, 0x42, 0x00, 2, 3,               //            and roof back
+            CULLED | 2, 0x06, 0x00, 3, 0,               //            and right
+            CULLED | 2, 0x11, 0x00, 4, 5,               // Cube front, and floor
+            CULLED | 2, 0x09, 0x00, 5, 6,               //             and left
+            CULLED | 2, 0x21, 0x00, 6, 7,               //             and roof front
+            CULLED | 2, 0x05, 0x00, 7, 4,               //             and right
+            CULLED | 2, 0x14, 0x00, 0, 4,               // Cube right and floor
+            CULLED | 2, 0x18, 0x00, 1, 5,               // Cube left and floor
+            CULLED | 2, 0x84, 0x00, 3, 7,               // Roof, and cube right
+            CULLED | 2, 0xa0, 0x00, 7, 8,
+            CULLED | 2, 0x20, 0x01, 8, 6,
+            CULLED | 2, 0x08, 0x01, 6, 2,               //       and cube left
+            CULLED | 2, 0x40, 0x01, 2, 8,
+            CULLED | 2, 0xc0, 0x00, 8, 3,
+            CULLED | 4, 0x01, 0x00, 10, 11, 12, 9,      // Door
+            CULLED | 5, 0x01, 0x00, 13, 14, 15, 16, 13, // Front window
+            CULLED | 5, 0x08, 0x00, 17, 18, 19, 20, 17, // Left window
+            4, 22, 21, 28, 29,                          // Car, one body panel per strip
+            4, 23, 22, 29, 30,
+            4, 24, 23, 30, 31,
+            4, 25, 24, 31, 32,
+            4, 26, 25, 32, 33,
+            4, 27, 26, 33, 34,
+            4, 21, 27, 34, 28,
+            2, 35, 36,                                  // Tree
+            3, 38, 37, 39,
+            3, 40, 37, 41,
+            DASHED | 10, 51, 46, 45, 44, 43, 42, 50, 49, 48, 52, // Fence
+            DASHED | 3, 46, 47, 48,
+            DOTTED | 5, 53, 54, 55, 56, 53,             // Welcome mat
+        ],
+
+        // Faces of the house, clockwise from outside, in face mask bit order
+        faces: [
+            [4, 5, 6],                                  // Front
+            [2, 1, 0],                                  // Back
+            [7, 3, 0],                                  // Right
+            [1, 2, 6],                                  // Left
+            [0, 1, 5],                                  // Floor
+            [7, 6, 8],                                  // Roof front
+            [2, 3, 8],                                  // Roof back
+            [3, 7, 8],                                  // Roof right
+            [6, 2, 8],                                  // Roof left
+        ],
+
+        // Faces of the house to fill, clockwise from outside, shaded as if lit
+        // from above
+        fills: [
+            Fill::quad(4, 5, 6, 7, 6),                  // Front
+            Fill::quad(2, 1, 0, 3, 6),                  // Back
+            Fill::quad(7, 3, 0, 4, 4),                  // Right
+            Fill::quad(1, 2, 6, 5, 4),                  // Left
+            Fill::quad(0, 1, 5, 4, fill::BLACK),        // Floor
+            Fill::triangle(7, 6, 8, 10),                // Roof front
+            Fill::triangle(2, 3, 8, 10),                // Roof back
+            Fill::triangle(3, 7, 8, 12),                // Roof right
+            Fill::triangle(6, 2, 8, 12),                // Roof left
+        ],
+
+        // The door opens as the car comes around the front of the house, and
+        // the car turns a quarter of the way around every quarter lap
+        animate: (LAP, [DOOR, CAR], [
+            Keyframe::new(120, STILL, 0),              // Door
+            Keyframe::new(150, STILL, degrees(-80)),
+            Keyframe::new(220, STILL, degrees(-80)),
+            Keyframe::new(260, STILL, 0),
+            Keyframe::new(0, STILL, 0),                // Car
+            Keyframe::new(LAP/4, STILL, degrees(90)),
+            Keyframe::new(LAP/2, STILL, degrees(180)),
+            Keyframe::new(3*LAP/4, STILL, degrees(270)),
+            Keyframe::new(LAP, STILL, 0),
+        ]),
+
+        // Everything collapses onto the surface of the cube and back
+        morph: (morph::onto_cube(MODEL, 0x800), 480, InOut),
+    }
+}
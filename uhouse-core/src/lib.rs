@@ -0,0 +1,53 @@
+//! # μHouse-rs core
+//! 
+//! The renderer behind μHouse-rs, free of any particular board or display.
+//! Meshes are transformed into view space, projected onto the screen and
+//! rasterized one line at a time into a `put_pixel` sink taking the column
+//! and row of every pixel to set, so the same code drives the SSD1306 on an
+//! Atmega328P and the simulator on the host. With the `embedded-graphics`
+//! feature the sink can also be any `embedded-graphics` display, see
+//! [`draw_target`](crate::draw_target).
+//! 
+//! For performance, this crate uses a fixed point representation throughout.
+//! Rotations are accumulated as complex numbers and combined into one
+//! rotation matrix per frame, and lines are clipped to the screen before they
+//! are rasterized.
+
+#![no_std]
+
+#[cfg(all(feature = "saturating", feature = "overflow-checks"))]
+compile_error!("the saturating and overflow-checks features handle fixed-point overflow differently and cannot be combined");
+
+#[macro_use]
+pub mod vec;
+
+#[macro_use]
+pub mod scene;
+
+pub mod animation;
+pub mod background;
+pub mod bounds;
+pub mod clip;
+
+#[cfg(feature = "embedded-graphics")]
+pub mod draw_target;
+
+pub mod fill;
+pub mod house;
+pub mod mesh;
+pub mod morph;
+pub mod numfmt;
+pub mod projection;
+pub mod radar;
+pub mod raster;
+pub mod rle;
+pub mod rotor;
+pub mod sine;
+pub mod transform;
+pub mod units;
+pub mod validate;
+
+/// Used by [`scene!`] so that scenes can be declared without depending on
+/// `avr-progmem` directly
+#[doc(hidden)]
+pub use avr_progmem;
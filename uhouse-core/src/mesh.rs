@@ -0,0 +1,554 @@
+//! Wireframe meshes in program memory
+
+use core::mem::size_of;
+use avr_progmem::wrapper::ProgMem;
+
+use crate::clip::{Rect, BEHIND_NEAR};
+use crate::raster::{Endpoints, Style};
+use crate::vec::*;
+
+#[cfg(not(feature = "subpixel"))]
+use crate::raster::draw_line;
+
+#[cfg(feature = "subpixel")]
+use crate::clip::SUBPIXEL_BEHIND_NEAR;
+
+#[cfg(feature = "subpixel")]
+use crate::raster::draw_line_subpixel;
+
+#[cfg(feature = "subpixel")]
+use crate::units::SubPixel;
+
+/// Strip length flag of a strip drawn only while one of its faces is seen
+/// from the front
+/// 
+/// The length is followed by a [`FaceMask`] of those faces in little endian.
+pub const CULLED: u8 = 0x80;
+
+/// Screen position of a transformed vertex, in whole pixels
+#[cfg(not(feature = "subpixel"))]
+pub type ScreenVert = Vec2;
+
+/// Screen position of a transformed vertex, in sixteenths of a pixel with
+/// the `subpixel` feature
+#[cfg(feature = "subpixel")]
+pub type ScreenVert = Vec2<SubPixel>;
+
+/// [`BEHIND_NEAR`] as a [`ScreenVert`]
+#[cfg(not(feature = "subpixel"))]
+const BEHIND: ScreenVert = BEHIND_NEAR;
+
+#[cfg(feature = "subpixel")]
+const BEHIND: ScreenVert = SUBPIXEL_BEHIND_NEAR;
+
+/// Pixel a transformed vertex is in, for drawing other than lines
+/// 
+/// A vertex in front of the near plane stays at [`BEHIND_NEAR`].
+pub fn pixels(v: ScreenVert) -> Vec2 {
+    #[cfg(feature = "subpixel")]
+    {
+        if v == BEHIND { BEHIND_NEAR } else { v.to_pixels() }
+    }
+
+    #[cfg(not(feature = "subpixel"))]
+    {
+        v
+    }
+}
+
+/// Bits of a strip length holding the number of vertices
+pub const LEN: u8 = 0x1f;
+
+/// Position of the bits of a strip length picking the [`Style`] of its
+/// lines, solid while clear
+pub const STYLE_SHIFT: u8 = 5;
+
+/// Strip length flag of a strip drawn [dashed](Style::Dashed)
+pub const DASHED: u8 = 1 << STYLE_SHIFT;
+
+/// Strip length flag of a strip drawn [dotted](Style::Dotted)
+pub const DOTTED: u8 = 2 << STYLE_SHIFT;
+
+/// Strip length flag of a strip drawn [faint](Style::Faint)
+pub const FAINT: u8 = 3 << STYLE_SHIFT;
+
+/// Bit mask of the faces of a mesh, bit `i` standing for face `i`
+pub type FaceMask = u16;
+
+/// Largest number of faces a mesh can have, one per bit of a [`FaceMask`]
+pub const MAX_FACES: usize = FaceMask::BITS as usize;
+
+/// Portion of a segment drawn when whole, see [`Mesh::draw`]
+pub const WHOLE: u8 = u8::MAX;
+
+/// Wireframe mesh stored in program memory
+/// 
+/// `V` is the number of vertices, `S` the length of the line strip data and
+/// `F` the number of faces, so every mesh carries its own sizes and meshes of
+/// different sizes can coexist.
+pub struct Mesh<const V: usize, const S: usize, const F: usize> {
+    /// Vertices in model space
+    pub verts: &'static ProgMem<[Vec3; V]>,
+
+    /// Line strips as indices into [`Mesh::verts`]
+    /// 
+    /// Each strip is a vertex count followed by that many vertex indices, and
+    /// draws a line between every pair of consecutive vertices. A lone edge
+    /// is a strip of two. A count with the [`CULLED`] flag is followed by the
+    /// faces the strip borders, and the strip is hidden while all of them face
+    /// away. A count with the [`DASHED`], [`DOTTED`] or [`FAINT`] flag draws
+    /// the lines of the strip with that [`Style`], to set construction lines
+    /// apart from outlines. The count itself takes the bits of [`LEN`].
+    pub strips: &'static ProgMem<[u8; S]>,
+
+    /// Faces for backface culling, as three vertex indices each
+    /// 
+    /// The vertices of a face wind clockwise on screen while the face is seen
+    /// from the front.
+    pub faces: &'static ProgMem<[[u8; 3]; F]>,
+
+    /// Axis of a mirror instance drawn in addition to the mesh itself
+    /// 
+    /// A symmetric model only needs to store one half and is completed by its
+    /// mirror image, at the cost of one negation per transformed vertex.
+    pub mirror: Option<Mirror>,
+}
+
+/// Axis a mirror instance is flipped across
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Mirror {
+    /// Negate X, mirroring left and right
+    X,
+
+    /// Negate Z, mirroring front and back
+    Z,
+}
+
+impl Mirror {
+
+    /// Flip a vertex in model space
+    pub fn apply(self, v: Vec3) -> Vec3 {
+        match self {
+            Mirror::X => vec3!(-v.x, v.y, v.z),
+            Mirror::Z => vec3!(v.x, v.y, -v.z),
+        }
+    }
+}
+
+impl<const V: usize, const S: usize, const F: usize> Mesh<V, S, F> {
+
+    /// Create a mesh from its tables in program memory
+    /// 
+    /// Declare meshes with [`scene!`](crate::scene!) instead, which checks
+    /// the tables at compile time.
+    /// 
+    /// # Safety
+    /// 
+    /// Every strip must fit [`strips_fit`] and every face [`faces_fit`] with
+    /// `V` vertices. Unless the `checked-indices` feature is enabled, their
+    /// indices are read without bounds checks.
+    pub const unsafe fn new(
+        verts: &'static ProgMem<[Vec3; V]>,
+        strips: &'static ProgMem<[u8; S]>,
+        faces: &'static ProgMem<[[u8; 3]; F]>,
+    ) -> Self {
+        assert!(F <= MAX_FACES, "mesh has more faces than a FaceMask can hold");
+        Self { verts, strips, faces, mirror: None }
+    }
+
+    /// Also draw a mirror image of the mesh flipped across `axis`
+    pub const fn mirrored(self, axis: Mirror) -> Self {
+        Self { mirror: Some(axis), ..self }
+    }
+
+    /// Every instance of the mesh to transform and draw, as the mirror to
+    /// apply to its vertices
+    pub fn instances(&self) -> impl Iterator<Item = Option<Mirror>> {
+        core::iter::once(None).chain(self.mirror.map(Some))
+    }
+
+    /// Draw every line strip from transformed vertices
+    /// 
+    /// `screen_verts` may be a buffer of this mesh's own size or a larger
+    /// scratch buffer shared between meshes, which is checked at compile time,
+    /// and with the `subpixel` feature is in sixteenths of a pixel.
+    /// Lines are clipped to `screen`, and `put_pixel` is given the column and
+    /// row of every pixel to set.
+    /// 
+    /// Segments with an endpoint at [`BEHIND_NEAR`] are passed to
+    /// `clip_near` by the indices of both endpoints, which are always within
+    /// the mesh, and it returns the part of the segment to draw on screen, if
+    /// any. Vertices are looked up by index rather than read from
+    /// [`Mesh::verts`] so that the caller may move them, as a morph does.
+    /// 
+    /// `mirror` is the instance being drawn, which decides which side of each
+    /// face is the front.
+    /// 
+    /// `portion` is given the number of each segment counted through the
+    /// strips and its vertex indices, and returns how much of the segment to
+    /// draw from its first vertex out of [`WHOLE`]. Returning `None` draws
+    /// every segment whole and lets consecutive segments share endpoints.
+    /// 
+    /// Returns `false` if any strip indexed past the vertex buffer, which can
+    /// only happen with the `checked-indices` feature.
+    #[cfg(not(feature = "streaming"))]
+    pub fn draw<const N: usize, C, R, P>(
+        &self,
+        screen_verts: &[ScreenVert; N],
+        screen: &Rect,
+        mirror: Option<Mirror>,
+        clip_near: C,
+        mut portion: R,
+        mut put_pixel: P,
+    ) -> bool
+    where
+        C: Fn(usize, usize) -> Option<(ScreenVert, ScreenVert)>,
+        R: FnMut(u16, u8, u8) -> Option<u8>,
+        P: FnMut(u32, u32),
+    {
+        let () = AssertFits::<V, N>::OK;
+
+        let front = self.front_faces(
+            |i| fetch_vertex(screen_verts, i).unwrap_or(BEHIND),
+            mirror,
+        );
+
+        let mut valid = true;
+        self.for_each_segment(front, |n, i0, i1, endpoints, style| {
+            match (fetch_vertex(screen_verts, i0), fetch_vertex(screen_verts, i1)) {
+                (Some(v0), Some(v1)) => {
+                    let segment = ((i0 as usize, v0), (i1 as usize, v1));
+                    let shown = portion(n, i0, i1);
+                    self.draw_segment(segment, (endpoints, style), shown, &clip_near, screen, &mut put_pixel);
+                }
+                _ => valid = false,
+            }
+        });
+        valid
+    }
+
+    /// Transform and draw the mesh `C` vertices at a time
+    /// 
+    /// Instead of a buffer holding every transformed vertex, only a scratch
+    /// buffer of `C` vertices is kept. Each segment is drawn with the chunk
+    /// holding its higher-indexed endpoint, and an endpoint from an earlier
+    /// chunk is transformed again. This allows meshes far larger than would fit
+    /// in RAM at the cost of some recomputation.
+    /// 
+    /// `transform` is given the index of a vertex and returns its position on
+    /// screen. Indices past the end of the mesh are never resolved, so their
+    /// segments are skipped. The screen, vertices in front of the near plane,
+    /// faces and portions are handled as in [`Mesh::draw`], with the vertices
+    /// of every face transformed once more up front.
+    #[cfg(feature = "streaming")]
+    pub fn draw_streamed<const C: usize, T, N, R, P>(
+        &self,
+        transform: T,
+        screen: &Rect,
+        mirror: Option<Mirror>,
+        clip_near: N,
+        mut portion: R,
+        mut put_pixel: P,
+    )
+    where
+        T: Fn(usize) -> ScreenVert,
+        N: Fn(usize, usize) -> Option<(ScreenVert, ScreenVert)>,
+        R: FnMut(u16, u8, u8) -> Option<u8>,
+        P: FnMut(u32, u32),
+    {
+        let front = self.front_faces(
+            |i| if (i as usize) < V { transform(i as usize) } else { BEHIND },
+            mirror,
+        );
+
+        let mut chunk = [ScreenVert::default(); C];
+        let mut base = 0;
+        while base < V {
+            let end = (base + C).min(V);
+            for (i, screen) in (base..end).zip(&mut chunk) {
+                *screen = transform(i);
+            }
+
+            let resolve = |i: usize| {
+                if i >= base {
+                    chunk[i - base]
+                } else {
+                    transform(i)
+                }
+            };
+
+            self.for_each_segment(front, |n, i0, i1, endpoints, style| {
+                if (base..end).contains(&(i0.max(i1) as usize)) {
+                    let shown = portion(n, i0, i1);
+                    let (i0, i1) = (i0 as usize, i1 as usize);
+                    let segment = ((i0, resolve(i0)), (i1, resolve(i1)));
+                    self.draw_segment(segment, (endpoints, style), shown, &clip_near, screen, &mut put_pixel);
+                }
+            });
+
+            base = end;
+        }
+    }
+
+    /// Draw a segment given the index and screen position of both endpoints,
+    /// which of them to plot and its style
+    /// 
+    /// A segment with an endpoint in front of the near plane is redone by
+    /// `clip_near`. The endpoint moved onto the near plane is new, so both
+    /// endpoints are plotted, as they are for a segment drawn in part.
+    fn draw_segment<N, P>(
+        &self,
+        ((i0, v0), (i1, v1)): ((usize, ScreenVert), (usize, ScreenVert)),
+        (endpoints, style): (Endpoints, Style),
+        shown: Option<u8>,
+        clip_near: &N,
+        screen: &Rect,
+        put_pixel: &mut P,
+    )
+    where
+        N: Fn(usize, usize) -> Option<(ScreenVert, ScreenVert)>,
+        P: FnMut(u32, u32),
+    {
+        let (v0, v1, endpoints) = if v0 != BEHIND && v1 != BEHIND {
+            (v0, v1, endpoints)
+        } else if i0 < V && i1 < V {
+            match clip_near(i0, i1) {
+                Some((v0, v1)) => (v0, v1, Endpoints::Both),
+                None => return,
+            }
+        } else {
+            return;
+        };
+
+        let (v1, endpoints) = match shown {
+            None => (v1, endpoints),
+            Some(0) => return,
+            Some(shown) => (trim(v0, v1, shown), Endpoints::Both),
+        };
+        #[cfg(not(feature = "subpixel"))]
+        draw_line(&mut *put_pixel, v0, v1, endpoints, style, screen);
+
+        #[cfg(feature = "subpixel")]
+        draw_line_subpixel(&mut *put_pixel, v0, v1, endpoints, style, screen);
+    }
+
+    /// Faces seen from the front, given the screen position of each vertex
+    /// 
+    /// A mirror instance winds the other way. Faces seen edge-on or with a
+    /// vertex in front of the near plane count as seen from the front, so
+    /// culling never hides an edge that should show.
+    fn front_faces<P: Fn(u8) -> ScreenVert>(&self, position: P, mirror: Option<Mirror>) -> FaceMask {
+        let mut front = 0;
+        for (bit, [a, b, c]) in self.faces.iter().enumerate() {
+            let (a, b, c) = (position(a), position(b), position(c));
+            let seen = a == BEHIND || b == BEHIND || c == BEHIND
+                || winds_front(pixels(a), pixels(b), pixels(c), mirror);
+            if seen {
+                front |= 1 << bit;
+            }
+        }
+        front
+    }
+
+    /// Number of segments in all strips
+    pub fn num_segments(&self) -> u16 {
+        let mut count = 0;
+        self.for_each_segment(FaceMask::MAX, |_, _, _, _, _| count += 1);
+        count
+    }
+
+    /// Walk the line strips, calling `segment` with the number, counted from
+    /// the first strip, and vertex indices of every segment, which of its
+    /// endpoints to plot and its style
+    /// 
+    /// Culled strips are skipped unless one of their faces is in `front`, but
+    /// their segments are still counted.
+    fn for_each_segment<G>(&self, front: FaceMask, mut segment: G)
+    where
+        G: FnMut(u16, u8, u8, Endpoints, Style),
+    {
+        let mut n: u16 = 0;
+        let mut strips = self.strips.iter();
+        while let Some(header) = strips.next() {
+            let len = header & LEN;
+            let style = Style::from_bits(header >> STYLE_SHIFT);
+            if header & CULLED != 0 {
+                let faces = match (strips.next(), strips.next()) {
+                    (Some(low), Some(high)) => FaceMask::from_le_bytes([low, high]),
+                    _ => break,
+                };
+                if faces & front == 0 {
+                    strips.by_ref().take(len as usize).for_each(drop);
+                    n += (len as u16).saturating_sub(1);
+                    continue;
+                }
+            }
+
+            let first = match strips.next() {
+                Some(first) => first,
+                None => break,
+            };
+            let mut prev = first;
+            let mut endpoints = Endpoints::Both;
+            let segments = (len as usize).saturating_sub(1);
+            for (k, i) in strips.by_ref().take(segments).enumerate() {
+
+                // The segment closing a loop ends on the first vertex, which
+                // is already plotted
+                if k > 0 && k + 1 == segments && i == first {
+                    endpoints = Endpoints::SkipBoth;
+                }
+
+                segment(n, prev, i, endpoints, style);
+                n += 1;
+                prev = i;
+                endpoints = Endpoints::SkipStart;
+            }
+        }
+    }
+}
+
+/// Whether a face with corners at `a`, `b` and `c` on screen is seen from the
+/// front, winding clockwise or, for a mirror instance, counterclockwise
+/// 
+/// A face seen edge-on counts as seen from the front.
+pub(crate) fn winds_front(a: Vec2, b: Vec2, c: Vec2, mirror: Option<Mirror>) -> bool {
+    let cross = (b.x - a.x) as i32*(c.y - a.y) as i32
+        - (b.y - a.y) as i32*(c.x - a.x) as i32;
+    if mirror.is_some() { cross <= 0 } else { cross >= 0 }
+}
+
+/// The start of the segment from `v0` to `v1` reaching `shown` out of
+/// [`WHOLE`] of the way
+fn trim<U>(v0: Vec2<U>, v1: Vec2<U>, shown: u8) -> Vec2<U> {
+    let lerp = |a: IFixed, b: IFixed| (a as i32 + (b as i32 - a as i32)*shown as i32/WHOLE as i32) as IFixed;
+    vec2!(lerp(v0.x, v1.x), lerp(v0.y, v1.y))
+}
+
+/// Compile-time check that a buffer of `N` vertices can hold a mesh of `V`
+#[cfg(not(feature = "streaming"))]
+struct AssertFits<const V: usize, const N: usize>;
+
+#[cfg(not(feature = "streaming"))]
+impl<const V: usize, const N: usize> AssertFits<V, N> {
+    const OK: () = assert!(N >= V, "screen vertex buffer is smaller than the mesh");
+}
+
+/// RAM taken by a screen-space vertex buffer of `verts` vertices, in bytes
+pub const fn screen_verts_bytes(verts: usize) -> usize {
+    verts * size_of::<Vec2>()
+}
+
+/// Largest vertex count of several meshes, for sizing a shared scratch buffer
+#[cfg(not(feature = "streaming"))]
+pub const fn max_verts(counts: &[usize]) -> usize {
+    let mut max = 0;
+    let mut i = 0;
+    while i < counts.len() {
+        if counts[i] > max {
+            max = counts[i];
+        }
+        i += 1;
+    }
+    max
+}
+
+/// Whether every strip of `strips` has at least two vertices, all below
+/// `num_verts`, and the last one ends the data, for checking strips at
+/// compile time
+/// 
+/// A count of more vertices than [`LEN`] holds spills into the style and
+/// culling bits, so its strip is read as a shorter one and the strips after
+/// it from the wrong bytes, which this catches.
+pub const fn strips_fit(strips: &[u8], num_verts: usize) -> bool {
+    let mut offset = 0;
+    while offset < strips.len() {
+        let header = strips[offset];
+        let len = (header & LEN) as usize;
+        let start = if header & CULLED != 0 { offset + 1 + size_of::<FaceMask>() } else { offset + 1 };
+        if len < 2 || start + len > strips.len() {
+            return false;
+        }
+        let mut i = start;
+        while i < start + len {
+            if strips[i] as usize >= num_verts {
+                return false;
+            }
+            i += 1;
+        }
+        offset = start + len;
+    }
+    true
+}
+
+/// Whether every corner of `faces` is below `num_verts`, for checking faces
+/// at compile time
+/// 
+/// Faces are looked up through [`fetch_vertex`], which skips bounds checks
+/// by default.
+pub const fn faces_fit(faces: &[[u8; 3]], num_verts: usize) -> bool {
+    let mut i = 0;
+    while i < faces.len() {
+        let mut k = 0;
+        while k < 3 {
+            if faces[i][k] as usize >= num_verts {
+                return false;
+            }
+            k += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Fetch the screen-space position of a vertex
+/// 
+/// Meshes in program memory are hard-coded to index into their vertices, so by
+/// default this skips bounds checks. With the `checked-indices` feature an
+/// out-of-range index returns `None` instead, for meshes that do not come
+/// with that guarantee.
+#[cfg(not(feature = "streaming"))]
+#[inline(always)]
+fn fetch_vertex(screen_verts: &[ScreenVert], index: u8) -> Option<ScreenVert> {
+    #[cfg(feature = "checked-indices")]
+    {
+        screen_verts.get(index as usize).copied()
+    }
+
+    #[cfg(not(feature = "checked-indices"))]
+    unsafe {
+        // SAFETY: Array is hard-coded to index into vertices so there is no
+        // chance for an out-of-bounds access
+        Some(*screen_verts.get_unchecked(index as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_fit_their_counts() {
+        assert!(strips_fit(&[2, 0, 1, DASHED | 3, 1, 2, 0], 3));
+        assert!(strips_fit(&[CULLED | 2, 0x01, 0x00, 0, 1], 2));
+        assert!(!strips_fit(&[2, 0, 3], 3));
+        assert!(!strips_fit(&[3, 0, 1], 3));
+        assert!(!strips_fit(&[CULLED | 2, 0x01, 0x00, 0], 2));
+    }
+
+    #[test]
+    fn faces_fit_the_vertices() {
+        assert!(faces_fit(&[[0, 1, 2], [2, 1, 0]], 3));
+        assert!(!faces_fit(&[[0, 1, 2], [2, 3, 0]], 3));
+    }
+
+    #[test]
+    fn strips_too_long_for_len_are_caught() {
+        let mut strips = [0; 41];
+        strips[0] = 40;
+        for (i, index) in strips[1..].iter_mut().enumerate() {
+            *index = i as u8;
+        }
+        assert!(!strips_fit(&strips, 40));
+    }
+}
@@ -0,0 +1,130 @@
+//! Morphing between two vertex tables
+//! 
+//! A morph moves every vertex of a mesh towards the same vertex of a second
+//! table of equal length and back again, while the strips and faces stay
+//! those of the mesh. The [`scene!`](crate::scene!) macro checks that both
+//! tables have the same number of vertices at compile time.
+
+use crate::vec::*;
+
+/// Easing curve of a morph
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Ease {
+    /// Constant speed
+    Linear,
+
+    /// Start and end slowly, following a smoothstep
+    InOut,
+}
+
+impl Ease {
+
+    /// Ease a fraction `t` out of 255
+    pub fn apply(self, t: u8) -> u8 {
+        match self {
+            Ease::Linear => t,
+            Ease::InOut => {
+                let t = t as u32;
+                (t*t*(3*255 - 2*t) / (255*255)) as u8
+            }
+        }
+    }
+}
+
+/// Morph towards the target and back once every `period` frames
+#[derive(Copy, Clone)]
+pub struct MorphTrack {
+    /// Number of frames there and back
+    pub period: u16,
+
+    pub ease: Ease,
+}
+
+impl MorphTrack {
+
+    /// The track that never morphs
+    pub const NONE: Self = Self {
+        period: 0,
+        ease: Ease::Linear,
+    };
+}
+
+/// Progress along a [`MorphTrack`]
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct MorphState {
+    /// Frames since the start of the current period
+    frames: u16,
+}
+
+impl MorphState {
+
+    /// Start at the mesh itself
+    pub const fn new() -> Self {
+        Self { frames: 0 }
+    }
+
+    /// Advance by one frame along `track`
+    pub fn advance(&mut self, track: MorphTrack) {
+        self.frames += 1;
+        if self.frames >= track.period {
+            self.frames = 0;
+        }
+    }
+
+    /// How far the vertices are towards the target, from 0 for the mesh
+    /// itself to 255 for the target
+    pub fn blend(&self, track: MorphTrack) -> u8 {
+        let half = track.period / 2;
+        if half == 0 {
+            return 0;
+        }
+        let phase = self.frames.min(track.period - self.frames);
+        track.ease.apply((phase as u32 * 255 / half as u32).min(255) as u8)
+    }
+}
+
+impl Default for MorphState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The vertex `blend` out of 255 of the way from `from` to `to`
+/// 
+/// Shifts instead of dividing by 255, scaling the blend up by a fraction so
+/// that 255 lands exactly on `to`.
+pub fn lerp(from: Vec3, to: Vec3, blend: u8) -> Vec3 {
+    let w = blend as i32 + (blend >> 7) as i32;
+    let mix = |a: IFixed, b: IFixed| ((a as i32*(256 - w) + b as i32*w) >> 8) as IFixed;
+    vec3!(mix(from.x, to.x), mix(from.y, to.y), mix(from.z, to.z))
+}
+
+/// Move every vertex along its direction from the origin onto the surface
+/// of a cube `half` out from the origin on each axis
+/// 
+/// This is a `const fn`, so a morph target is derived from a model at
+/// compile time, such as the house collapsing into a cube.
+pub const fn onto_cube<const N: usize>(verts: [Vec3; N], half: IFixed) -> [Vec3; N] {
+    let mut out = verts;
+    let mut i = 0;
+    while i < N {
+        let v = verts[i];
+        let mut reach = v.x.unsigned_abs();
+        if v.y.unsigned_abs() > reach {
+            reach = v.y.unsigned_abs();
+        }
+        if v.z.unsigned_abs() > reach {
+            reach = v.z.unsigned_abs();
+        }
+        if reach != 0 {
+            let (half, reach) = (half as i32, reach as i32);
+            out[i] = vec3!(
+                (v.x as i32*half/reach) as IFixed,
+                (v.y as i32*half/reach) as IFixed,
+                (v.z as i32*half/reach) as IFixed
+            );
+        }
+        i += 1;
+    }
+    out
+}
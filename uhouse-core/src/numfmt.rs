@@ -0,0 +1,89 @@
+//! Allocation-free number formatting
+//! 
+//! `ufmt` can print integers but has no idea that an [`IFixed`] is really a
+//! fractional value. These helpers write decimal text into a stack buffer
+//! provided by the caller and return the written part as a `&str`, for use
+//! by anything that puts numbers on the serial port or the screen.
+
+use crate::vec::IFixed;
+
+/// Buffer length needed by [`fmt_u16`]
+pub const U16_STR_LEN: usize = 5;
+
+/// Buffer length needed by [`fmt_i16`]
+pub const I16_STR_LEN: usize = 6;
+
+/// Buffer length needed by [`fmt_fixed`]
+/// 
+/// Sign, one integer digit, decimal point and four fractional digits.
+pub const FIXED_STR_LEN: usize = 7;
+
+/// Number of fractional digits printed by [`fmt_fixed`]
+/// 
+/// Four digits is the fewest that keeps every step of 1/4096 distinct.
+const FIXED_FRAC_DIGITS: u8 = 4;
+
+/// Write `value` in decimal into the end of `buf`
+/// 
+/// At least `min_digits` digits are written, padding with leading zeros.
+/// Returns the index of the first digit written.
+fn write_digits(mut value: u16, min_digits: u8, buf: &mut [u8]) -> usize {
+    let mut i = buf.len();
+    let mut digits = 0;
+    while value != 0 || digits < min_digits.max(1) {
+        i -= 1;
+        buf[i] = b'0' + (value % 10) as u8;
+        value /= 10;
+        digits += 1;
+    }
+    i
+}
+
+/// View formatted ASCII bytes as a string slice
+fn as_str(bytes: &[u8]) -> &str {
+    // SAFETY: Every formatter here only writes ASCII digits, '-' and '.'
+    unsafe { core::str::from_utf8_unchecked(bytes) }
+}
+
+/// Format an unsigned integer in decimal
+pub fn fmt_u16(value: u16, buf: &mut [u8; U16_STR_LEN]) -> &str {
+    let start = write_digits(value, 1, buf);
+    as_str(&buf[start..])
+}
+
+/// Format a signed integer in decimal
+pub fn fmt_i16(value: i16, buf: &mut [u8; I16_STR_LEN]) -> &str {
+    let mut start = write_digits(value.unsigned_abs(), 1, buf);
+    if value < 0 {
+        start -= 1;
+        buf[start] = b'-';
+    }
+    as_str(&buf[start..])
+}
+
+/// Format a fixed-point value as a decimal fraction
+/// 
+/// The fraction is rounded to four digits and trailing zeros are dropped, so
+/// `-0x2200` prints as `-2.125` and `0x1000` prints as `1.0`.
+pub fn fmt_fixed(value: IFixed, buf: &mut [u8; FIXED_STR_LEN]) -> &str {
+    let magnitude = value.unsigned_abs();
+    let integer = magnitude >> 12;
+
+    // Scale the 12-bit fraction to decimal, rounding to nearest
+    let mut frac = (((magnitude & 0xfff) as u32 * 10000 + 0x800) >> 12) as u16;
+    let mut frac_digits = FIXED_FRAC_DIGITS;
+    while frac_digits > 1 && frac.is_multiple_of(10) {
+        frac /= 10;
+        frac_digits -= 1;
+    }
+
+    let mut start = write_digits(frac, frac_digits, buf);
+    start -= 1;
+    buf[start] = b'.';
+    start = write_digits(integer, 1, &mut buf[..start]);
+    if value < 0 {
+        start -= 1;
+        buf[start] = b'-';
+    }
+    as_str(&buf[start..])
+}
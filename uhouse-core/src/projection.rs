@@ -0,0 +1,190 @@
+//! Projections from view space onto the screen
+//! 
+//! The renderer only ever calls [`Project::project`], so an alternative
+//! projection is a matter of implementing the trait and changing the
+//! `Projection` type in `main`.
+
+use crate::house::MESH_DEPTH;
+use crate::units::{Screen, SubPixel, World, SUBPIXEL_BITS};
+use crate::vec::*;
+
+/// Map a point in view space to the screen
+/// 
+/// View space is model space after rotation and translation, with the camera
+/// at the origin looking down positive Z. The result is in pixels relative to
+/// the center of the screen, and projecting is where a point changes from
+/// [`World`] units to [`Screen`] pixels.
+pub trait Project {
+    fn project(v: Vec3<World>) -> Vec2<Screen>;
+
+    /// Map a point in view space to the screen in sixteenths of a pixel, for
+    /// the `subpixel` feature
+    /// 
+    /// Defaults to the whole pixels of [`Project::project`].
+    fn project_subpixel(v: Vec3<World>) -> Vec2<SubPixel> {
+        Self::project(v).to_subpixels()
+    }
+}
+
+/// Depth shift of the perspective divide below each depth, nearest first
+/// 
+/// Dividing by `z >> 6` throws away the low bits of every depth alike, which
+/// makes near geometry visibly pop from one step to the next. Near depths are
+/// shifted less and the numerator is scaled up to make up for it, and far
+/// depths are shifted more, where the lost precision does not show.
+const DEPTH_REMAP: [(IFixed, u8); 3] = [
+    (0x1000, 4),
+    (0x2000, 5),
+    (0x4000, 6),
+];
+
+/// Depth shift beyond the last entry of [`DEPTH_REMAP`]
+const FAR_SHIFT: u8 = 7;
+
+/// Unity depth shift, for which the numerator is not scaled
+const UNITY_SHIFT: u8 = 6;
+
+/// Depth shift to use for a depth
+fn depth_shift(z: IFixed) -> u8 {
+    DEPTH_REMAP
+        .iter()
+        .find(|&&(max, _)| z < max)
+        .map_or(FAR_SHIFT, |&(_, shift)| shift)
+}
+
+/// Largest distance of a projected point from the screen center, in pixels
+/// 
+/// Points projecting further out, or so far out that they would overflow an
+/// [`IFixed`] and wrap to the other side of the screen, are saturated to this
+/// range instead and left to the rasterizer to discard.
+pub const GUARD_RADIUS: IFixed = 0x400;
+
+/// Saturate a projected coordinate into the guard range
+fn saturate(value: i32) -> IFixed {
+    value.clamp(-GUARD_RADIUS as i32, GUARD_RADIUS as i32) as IFixed
+}
+
+/// Saturate a projected coordinate in sixteenths of a pixel into the guard
+/// range
+fn saturate_subpixel(value: i32) -> IFixed {
+    let radius = (GUARD_RADIUS as i32) << SUBPIXEL_BITS;
+    value.clamp(-radius, radius) as IFixed
+}
+
+/// Perspective projection, the default
+/// 
+/// Geometry at or behind the camera is projected as if it were just in
+/// front of it rather than dividing by zero.
+pub struct Perspective;
+
+impl Project for Perspective {
+    fn project(v: Vec3<World>) -> Vec2<Screen> {
+        let shift = depth_shift(v.z);
+        if shift >= UNITY_SHIFT {
+            let down = shift - UNITY_SHIFT;
+            let z_prime: IFixed = (v.z >> shift).max(1);
+            vec2!(
+                saturate(((v.x >> down)/z_prime) as i32),
+                saturate(((v.y >> down)/z_prime) as i32)
+            )
+        } else {
+            // Scaling up the numerator needs a wider intermediate
+            let up = UNITY_SHIFT - shift;
+            let z_prime = ((v.z >> shift) as i32).max(1);
+            vec2!(
+                saturate(((v.x as i32) << up)/z_prime),
+                saturate(((v.y as i32) << up)/z_prime)
+            )
+        }
+    }
+
+    /// Keeps the fractional bits the divide yields, with the numerator
+    /// scaled up by them as well as for the depth shift
+    fn project_subpixel(v: Vec3<World>) -> Vec2<SubPixel> {
+        let shift = depth_shift(v.z);
+
+        // Never negative, as FAR_SHIFT is below UNITY_SHIFT + SUBPIXEL_BITS
+        let up = UNITY_SHIFT + SUBPIXEL_BITS - shift;
+        let z_prime = ((v.z >> shift) as i32).max(1);
+        vec2!(
+            saturate_subpixel(((v.x as i32) << up)/z_prime),
+            saturate_subpixel(((v.y as i32) << up)/z_prime)
+        )
+    }
+}
+
+/// Perspective projection dividing by `z >> 6` at every depth
+/// 
+/// The projection before [`DEPTH_REMAP`] was introduced, kept as a reference
+/// to compare against.
+#[allow(unused)]
+pub struct FlatPerspective;
+
+impl Project for FlatPerspective {
+    fn project(v: Vec3<World>) -> Vec2<Screen> {
+        let z_prime: IFixed = (v.z >> UNITY_SHIFT).max(1);
+        vec2!(
+            saturate((v.x/z_prime) as i32),
+            saturate((v.y/z_prime) as i32)
+        )
+    }
+}
+
+/// Orthographic projection without any foreshortening
+/// 
+/// Scaled to match [`Perspective`] at [`MESH_DEPTH`].
+#[allow(unused)]
+pub struct Orthographic;
+
+impl Project for Orthographic {
+    fn project(v: Vec3<World>) -> Vec2<Screen> {
+        const SCALE: IFixed = MESH_DEPTH >> 6;
+        vec2!(v.x/SCALE, v.y/SCALE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_on_the_axis_project_to_the_center() {
+        for z in [0x400, 0x1000, 0x2fff, 0x4000, IFixed::MAX] {
+            assert!(Perspective::project(vec3!(0, 0, z)) == vec2!(0, 0));
+        }
+    }
+
+    #[test]
+    fn projection_shrinks_with_depth() {
+        // Across every step of DEPTH_REMAP
+        let mut last = IFixed::MAX;
+        for z in (0x400..0x7c00).step_by(0x100) {
+            let x = Perspective::project(vec3!(0x800, -0x800, z)).x;
+            assert!(x <= last, "x {} at depth {:#x} grew from {}", x, z, last);
+            last = x;
+        }
+    }
+
+    #[test]
+    fn unity_depth_matches_the_flat_projection() {
+        let v = vec3!(0x1234, -0x0567, 0x3000);
+        assert!(Perspective::project(v) == FlatPerspective::project(v));
+    }
+
+    #[test]
+    fn subpixels_agree_with_whole_pixels() {
+        for &(x, y, z) in &[(0x800, 0x300, 0x400), (-0x1234, 0x567, 0x1800), (0x7000, -0x7000, 0x5000)] {
+            let v = vec3!(x, y, z);
+            let whole = Perspective::project(v);
+            let sub = Perspective::project_subpixel(v);
+            let (sx, sy) = (sub.x >> SUBPIXEL_BITS, sub.y >> SUBPIXEL_BITS);
+            assert!((sx - whole.x).abs() <= 1 && (sy - whole.y).abs() <= 1, "{} {} {}", x, y, z);
+        }
+    }
+
+    #[test]
+    fn points_beside_the_camera_saturate() {
+        let v = Perspective::project(vec3!(IFixed::MAX, IFixed::MIN, 0));
+        assert!(v == vec2!(GUARD_RADIUS, -GUARD_RADIUS));
+    }
+}
@@ -0,0 +1,146 @@
+//! Radar sweep of the scene
+//! 
+//! A beam turns about the center of the scene on screen, and only the parts
+//! of the wireframe it has swept over lately are drawn, as on the display of
+//! a radar. The trail behind the beam fades out like the afterglow of a
+//! phosphor, drawn with fewer pixels of an ordered dither in each of
+//! [`BANDS`] sectors further behind the beam.
+//! 
+//! Segments missing the trail are skipped before they are rasterized, with
+//! [`Sector::hits`], and the pixels of the rest are kept only where they fall
+//! within the trail, with [`Sector::shows`].
+
+use crate::clip::{clip_line, Rect, BEHIND_NEAR};
+use crate::fill::{dither, WHITE};
+use crate::sine::{degrees, rotor_from_fine_angle};
+use crate::vec::*;
+
+/// Sectors the trail is divided into, each fainter than the one before
+pub const BANDS: usize = 4;
+
+/// Angle the trail spans behind the beam, in 65536ths of a turn
+pub const TRAIL: u16 = degrees(90);
+
+/// Shade of each band, from the one at the beam back, see
+/// [`fill::dither`](crate::fill::dither)
+const SHADES: [u8; BANDS] = [WHITE, 10, 5, 2];
+
+/// Bits the directions of the band edges are kept to, small enough that the
+/// cross product of a direction with a point anywhere on screen, and the
+/// product of two of those, fit in an `i32`
+const DIRECTION_BITS: u32 = 7;
+
+const _: () = assert!(TRAIL < degrees(180), "the trail must span less than half a turn");
+
+/// Angle of the beam, advanced every frame
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Sweep {
+    /// Angle of the beam in 65536ths of a turn, clockwise on screen from
+    /// pointing right
+    angle: u16,
+}
+
+impl Sweep {
+
+    /// Start with the beam pointing right
+    pub const fn new() -> Self {
+        Self { angle: 0 }
+    }
+
+    /// Turn the beam by `step`
+    pub fn advance(&mut self, step: u16) {
+        self.angle = self.angle.wrapping_add(step);
+    }
+
+    /// Trail of the beam turning about `center`, for drawing a frame
+    pub fn sector(&self, center: Vec2) -> Sector {
+        let band = TRAIL / BANDS as u16;
+        Sector {
+            center,
+            edges: core::array::from_fn(|k| {
+                let edge = rotor_from_fine_angle(self.angle.wrapping_sub(band*k as u16));
+                let shift = 12 - DIRECTION_BITS;
+                (edge.x as i32 >> shift, edge.y as i32 >> shift)
+            }),
+        }
+    }
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Area of the screen behind the beam, in bands
+pub struct Sector {
+    center: Vec2,
+
+    /// Direction of the beam, then of the far edge of each band behind it,
+    /// with [`DIRECTION_BITS`] fractional bits
+    edges: [(i32, i32); BANDS + 1],
+}
+
+impl Sector {
+
+    /// Whether any part of the segment from `v0` to `v1` within `screen`
+    /// falls in the trail
+    /// 
+    /// A segment with an endpoint at [`BEHIND_NEAR`] always hits, as where it
+    /// reaches on screen is only known once it is clipped to the near plane.
+    pub fn hits(&self, v0: Vec2, v1: Vec2, screen: &Rect) -> bool {
+        if v0 == BEHIND_NEAR || v1 == BEHIND_NEAR {
+            return true;
+        }
+        let (v0, v1) = match clip_line(v0, v1, screen) {
+            Some(segment) => segment,
+            None => return false,
+        };
+
+        // How far each endpoint is past the far edge of the trail, and short
+        // of the beam
+        let past = |v: Vec2| self.cross(self.edges[BANDS], v);
+        let short = |v: Vec2| -self.cross(self.edges[0], v);
+        let (past0, short0) = (past(v0), short(v0));
+        let (past1, short1) = (past(v1), short(v1));
+
+        if past0 >= 0 && short0 >= 0 || past1 >= 0 && short1 >= 0 {
+            return true;
+        }
+        if past0 < 0 && past1 < 0 || short0 < 0 && short1 < 0 {
+            return false;
+        }
+
+        // One endpoint is behind the trail and the other ahead of the beam,
+        // so the segment crosses the line of the far edge. It passes
+        // through the trail if it crosses on the side of the beam rather
+        // than on the opposite side of the center.
+        let (behind, ahead) = if past0 < 0 {
+            ((past0, short0), (past1, short1))
+        } else {
+            ((past1, short1), (past0, short0))
+        };
+        behind.0*ahead.1 <= behind.1*ahead.0
+    }
+
+    /// Whether the pixel at column `x` and row `y` is drawn, falling in the
+    /// trail and on in the dither of its band
+    pub fn shows(&self, x: u32, y: u32) -> bool {
+        let v = vec2!(x as IFixed, y as IFixed);
+        if self.cross(self.edges[BANDS], v) < 0 || self.cross(self.edges[0], v) > 0 {
+            return false;
+        }
+        let band = (1..BANDS)
+            .position(|k| self.cross(self.edges[k], v) > 0)
+            .unwrap_or(BANDS - 1);
+        dither(SHADES[band], x, y)
+    }
+
+    /// Cross product of an edge direction and the offset of `v` from the
+    /// center, positive where `v` lies clockwise on screen of the edge
+    fn cross(&self, (ex, ey): (i32, i32), v: Vec2) -> i32 {
+        let dx = v.x as i32 - self.center.x as i32;
+        let dy = v.y as i32 - self.center.y as i32;
+        ex*dy - ey*dx
+    }
+}
@@ -0,0 +1,471 @@
+//! Line rasterization
+//! 
+//! Lines are drawn through a `put_pixel` sink taking the column and row of
+//! every pixel to set, so the same code draws into the SSD1306 framebuffer on
+//! the device and into a window on the host. Every line has a [`Style`]
+//! masking which of its pixels are set, so that construction lines can be
+//! told apart from outlines on a display without shades of gray.
+
+use core::mem::swap;
+
+use crate::clip::{self, Rect};
+use crate::units::{SubPixel, SUBPIXEL_BITS};
+use crate::vec::*;
+
+/// Which endpoints of a line [`draw_line`] should plot
+/// 
+/// Consecutive segments of a line strip share an endpoint, so plotting both
+/// ends of every segment sets each shared pixel twice. For
+/// [`draw_line_subpixel`] the endpoint is the pixel the vertex rounds to.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Endpoints {
+    /// Plot both endpoints
+    Both,
+
+    /// Skip the start point, which was plotted by the previous segment
+    SkipStart,
+
+    /// Skip both endpoints, as when closing a loop
+    SkipBoth,
+}
+
+impl Endpoints {
+
+    /// Whether the start and the end are skipped
+    fn skipped(self) -> (bool, bool) {
+        match self {
+            Endpoints::Both => (false, false),
+            Endpoints::SkipStart => (true, false),
+            Endpoints::SkipBoth => (true, true),
+        }
+    }
+}
+
+/// Pattern a line is drawn with, from the most emphasis to the least
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Style {
+    /// Every pixel set
+    Solid,
+
+    /// Four pixels set and four left out
+    Dashed,
+
+    /// Every other pixel set
+    Dotted,
+
+    /// Every fourth pixel set
+    Faint,
+}
+
+impl Style {
+
+    /// Style picked by the two lowest bits of `bits`, solid being 0
+    pub const fn from_bits(bits: u8) -> Self {
+        match bits & 3 {
+            0 => Style::Solid,
+            1 => Style::Dashed,
+            2 => Style::Dotted,
+            _ => Style::Faint,
+        }
+    }
+
+    /// Which of every eight pixels along a line are set, the first in the
+    /// lowest bit
+    const fn pattern(self) -> u8 {
+        match self {
+            Style::Solid => 0xff,
+            Style::Dashed => 0x0f,
+            Style::Dotted => 0x55,
+            Style::Faint => 0x11,
+        }
+    }
+}
+
+/// Bresenham's line algorithm
+/// 
+/// The segment is first clipped to `screen`, so every pixel walked is on
+/// screen. An endpoint moved by clipping is a new point on the screen edge
+/// and is always plotted, unless `style` leaves it out.
+/// 
+/// The pattern of `style` starts at `v0` and is counted along the longer
+/// axis of the line, so clipping does not shift it.
+pub fn draw_line<F: FnMut(u32, u32)>(
+    mut put_pixel: F,
+    v0: Vec2,
+    v1: Vec2,
+    endpoints: Endpoints,
+    style: Style,
+    screen: &Rect,
+) {
+    #[cfg(feature = "clip-debug")]
+    clip::mark_clip_points(&mut put_pixel, v0, v1, screen);
+
+    let (c0, c1) = match clip::clip_line(v0, v1, screen) {
+        Some(segment) => segment,
+        None => return,
+    };
+
+    // Endpoints moved by clipping were never plotted by another segment
+    let (skip_start, skip_end) = endpoints.skipped();
+    let skip = (skip_start && c0 == v0, skip_end && c1 == v1);
+    let start = v0;
+    let (mut v0, mut v1) = (c0, c1);
+
+    let should_swap = {
+        let d = (v1 - v0).component_abs();
+        d.y > d.x
+    };
+
+    if should_swap {
+        swap(&mut v0.x, &mut v0.y);
+        swap(&mut v1.x, &mut v1.y);
+    }
+
+    let reversed = v0.x > v1.x;
+    if reversed {
+        swap(&mut v0, &mut v1);
+    }
+
+    // The start point ends up on the right if the line was reversed
+    let (skip_left, skip_right) = if reversed { (skip.1, skip.0) } else { skip };
+    let last_x = if skip_right { v1.x - 1 } else { v1.x };
+    let mut plot = !skip_left;
+
+    // Line the pattern up with the leftmost pixel, counting from the
+    // unclipped start, and turn it by a pixel per step, backwards if the line
+    // was reversed
+    let major = |v: Vec2| if should_swap { v.y } else { v.x };
+    let left = if reversed { c1 } else { c0 };
+    let offset = (major(left) as i32 - major(start) as i32).unsigned_abs();
+    let mut pattern = style.pattern().rotate_right(offset % 8);
+
+    let dx = v1.x - v0.x;
+    let dy = (v1.y - v0.y).abs();
+
+    let y_step = if v0.y < v1.y { 1 } else { -1 };
+    let mut half_diff = -(dx >> 1);
+
+    while v0.x <= last_x {
+        if !plot {
+            plot = true;
+        } else if pattern & 1 != 0 {
+            if should_swap {
+                put_pixel(v0.y as u32, v0.x as u32);
+            } else {
+                put_pixel(v0.x as u32, v0.y as u32);
+            }
+        }
+        pattern = if reversed { pattern.rotate_left(1) } else { pattern.rotate_right(1) };
+
+        half_diff += dy;
+        if half_diff > 0 {
+            half_diff -= dx;
+            v0.y += y_step;
+        }
+        v0.x += 1;
+    }
+}
+
+/// Bresenham's line algorithm between points in sixteenths of a pixel
+/// 
+/// As [`draw_line`], but every pixel is set where the line crosses the
+/// center of its column, or its row for a steep line, so an edge turning
+/// slowly slides across rather than jumping whenever an endpoint rounds to
+/// the next pixel. The fraction of the endpoints is carried in the error
+/// term, and otherwise a step costs as much as in [`draw_line`].
+/// 
+/// The line is clipped to the centers of the pixels of `screen`, so it is
+/// never stepped onto a pixel outside of it.
+/// 
+/// Two segments of a strip each end where they cross the column or row
+/// nearest their shared vertex, and those pixels need not touch. Every
+/// pixel the line steps onto is plotted, and each end `endpoints` does not
+/// skip also plots the pixel its vertex rounds to, which touches the last
+/// pixel of both segments, so the strip stays joined. An end moved by
+/// clipping only plots the pixel the line steps onto.
+pub fn draw_line_subpixel<F: FnMut(u32, u32)>(
+    mut put_pixel: F,
+    v0: Vec2<SubPixel>,
+    v1: Vec2<SubPixel>,
+    endpoints: Endpoints,
+    style: Style,
+    screen: &Rect,
+) {
+    #[cfg(feature = "clip-debug")]
+    clip::mark_clip_points(&mut put_pixel, v0.to_pixels(), v1.to_pixels(), screen);
+
+    let (c0, c1) = match clip::clip_line(v0, v1, &pixel_centers(screen)) {
+        Some(segment) => segment,
+        None => return,
+    };
+
+    // Endpoints moved by clipping are not vertices of the strip
+    let (skip_start, skip_end) = endpoints.skipped();
+    let vertices = (!skip_start && c0 == v0, !skip_end && c1 == v1);
+    let start = v0;
+    let (mut v0, mut v1) = (c0, c1);
+
+    let should_swap = {
+        let d = (v1 - v0).component_abs();
+        d.y > d.x
+    };
+
+    if should_swap {
+        swap(&mut v0.x, &mut v0.y);
+        swap(&mut v1.x, &mut v1.y);
+    }
+
+    let reversed = v0.x > v1.x;
+    if reversed {
+        swap(&mut v0, &mut v1);
+    }
+
+    // Columns from the one nearest the left end to the one nearest the right
+    let round = |c: IFixed| (c as i32 + HALF_PIXEL) >> SUBPIXEL_BITS;
+    let mut x = round(v0.x);
+    let (first_x, last_x) = (x, round(v1.x));
+
+    // Rows of the vertex pixels to plot at either end, the start ending up
+    // on the right if the line was reversed
+    let (left, right) = if reversed { (vertices.1, vertices.0) } else { vertices };
+    let left_row = left.then(|| round(v0.y));
+    let right_row = right.then(|| round(v1.y)).filter(|&row| first_x != last_x || left_row != Some(row));
+
+    let mut put = |x: i32, y: i32| {
+        if should_swap {
+            put_pixel(y as u32, x as u32);
+        } else {
+            put_pixel(x as u32, y as u32);
+        }
+    };
+
+    // Line the pattern up with the leftmost pixel, as in draw_line
+    let major = |v: Vec2<SubPixel>| if should_swap { v.y } else { v.x };
+    let offset = (x - round(major(start))).unsigned_abs();
+    let mut pattern = style.pattern().rotate_right(offset % 8);
+
+    // Row of the line at the center of column x, as y and an error out of a
+    // whole row of `scale`, which stepping a column adds `step` to. Halfway
+    // rows round toward the left end as in draw_line, so whole pixel
+    // endpoints draw the same line. A line of a single point has no slope
+    // and is taken as flat.
+    let dx = (v1.x as i32 - v0.x as i32).max(1);
+    let dy = v1.y as i32 - v0.y as i32;
+    let scale = dx << SUBPIXEL_BITS;
+    let step = dy << SUBPIXEL_BITS;
+    let at = (v0.y as i32 + HALF_PIXEL)*dx + ((x << SUBPIXEL_BITS) - v0.x as i32)*dy - (dy >= 0) as i32;
+    let mut y = at.div_euclid(scale);
+    let mut error = at.rem_euclid(scale);
+
+    while x <= last_x {
+        if pattern & 1 != 0 {
+            put(x, y);
+            let rows = [left_row.filter(|_| x == first_x), right_row.filter(|_| x == last_x)];
+            for row in rows.into_iter().flatten().filter(|&row| row != y) {
+                put(x, row);
+            }
+        }
+        pattern = if reversed { pattern.rotate_left(1) } else { pattern.rotate_right(1) };
+
+        error += step;
+        if error >= scale {
+            error -= scale;
+            y += 1;
+        } else if error < 0 {
+            error += scale;
+            y -= 1;
+        }
+        x += 1;
+    }
+}
+
+/// Half a pixel in sixteenths of a pixel
+const HALF_PIXEL: i32 = 1 << (SUBPIXEL_BITS - 1);
+
+/// Rectangle from the center of the top left pixel of `rect` to the center
+/// of its bottom right pixel, in sixteenths of a pixel
+/// 
+/// The column or row a line is stepped onto is the one nearest to a point
+/// of the line at most half a pixel beyond its ends. Ends clipped to this
+/// rectangle keep that within `rect`.
+fn pixel_centers(rect: &Rect) -> Rect<SubPixel> {
+    Rect {
+        min: rect.min.to_subpixels(),
+        max: (rect.max - vec2!(1, 1)).to_subpixels() + vec2!(1, 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+
+    /// Display sizes of the supported panels, as in the simulator
+    const PANEL_SIZES: [(IFixed, IFixed); 6] = [(128, 64), (128, 32), (96, 16), (72, 40), (64, 48), (64, 32)];
+
+    /// Coordinates at the limits of the range and around the screen edges
+    const ADVERSARIAL: [IFixed; 12] = [IFixed::MIN, IFixed::MIN + 1, -32000, -257, -1, 0, 1, 63, 64, 127, 128, IFixed::MAX];
+
+    /// Segments drawn by each randomized test
+    const SEGMENTS: u32 = 200_000;
+
+    /// Small xorshift generator, so every run tests the same segments
+    struct Rng(u32);
+
+    impl Rng {
+        fn new() -> Self {
+            Self(0x2545_f491)
+        }
+
+        fn next(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            self.0
+        }
+
+        /// Number from `min` up to but not including `max`
+        fn range(&mut self, min: i32, max: i32) -> IFixed {
+            (min + (self.next() % (max - min) as u32) as i32) as IFixed
+        }
+
+        /// Coordinate around a screen `size` long, anywhere at all, or one of
+        /// [`ADVERSARIAL`]
+        fn coordinate(&mut self, size: IFixed) -> IFixed {
+            match self.next() % 4 {
+                0 => ADVERSARIAL[self.next() as usize % ADVERSARIAL.len()],
+                1 => self.next() as IFixed,
+                _ => self.range(-2*size as i32, 3*size as i32),
+            }
+        }
+
+        fn endpoints(&mut self) -> Endpoints {
+            [Endpoints::Both, Endpoints::SkipStart, Endpoints::SkipBoth][self.next() as usize % 3]
+        }
+    }
+
+    /// Draw random and adversarial segments on every panel size, with
+    /// coordinates in `unit`s of a pixel, checking that every pixel is on
+    /// screen and that no line plots more pixels than fit along the screen,
+    /// and `extra` more
+    fn fuzz<U>(unit: IFixed, extra: IFixed, mut draw: impl FnMut(&mut dyn FnMut(u32, u32), Vec2<U>, Vec2<U>, Endpoints, Style, &Rect)) {
+        // The ticks marking either clipped end, three pixels each
+        #[cfg(feature = "clip-debug")]
+        let extra = extra + 6;
+
+        let mut rng = Rng::new();
+        for i in 0..SEGMENTS {
+            let (width, height) = PANEL_SIZES[i as usize % PANEL_SIZES.len()];
+            let screen = Rect::sized(width, height);
+            let (x0, y0) = (rng.coordinate(width*unit), rng.coordinate(height*unit));
+            let (x1, y1) = if rng.next().is_multiple_of(8) {
+                (x0, y0)
+            } else {
+                (rng.coordinate(width*unit), rng.coordinate(height*unit))
+            };
+            let mut plotted = 0;
+            let mut put_pixel = |x: u32, y: u32| {
+                assert!(x < width as u32 && y < height as u32, "({}, {}) to ({}, {}) plotted ({}, {})", x0, y0, x1, y1, x, y);
+                plotted += 1;
+                assert!(plotted <= width.max(height) + extra, "({}, {}) to ({}, {}) plotted too many pixels", x0, y0, x1, y1);
+            };
+            draw(&mut put_pixel, vec2!(x0, y0), vec2!(x1, y1), rng.endpoints(), Style::from_bits(rng.next() as u8), &screen);
+        }
+    }
+
+    #[test]
+    fn draw_line_stays_on_screen() {
+        fuzz(1, 0, |put_pixel, v0, v1, endpoints, style, screen| draw_line(put_pixel, v0, v1, endpoints, style, screen));
+    }
+
+    #[test]
+    fn draw_line_subpixel_stays_on_screen() {
+        // Both vertex pixels may be plotted besides those along the line
+        fuzz(1 << SUBPIXEL_BITS, 2, |put_pixel, v0, v1, endpoints, style, screen| {
+            draw_line_subpixel(put_pixel, v0, v1, endpoints, style, screen)
+        });
+    }
+
+    /// Strips of the corners of a square, a triangle and a hexagon, each
+    /// closed into a loop, and of an open zigzag
+    const STRIPS: [&[(IFixed, IFixed)]; 4] = [
+        &[(10, 10), (40, 10), (40, 40), (10, 40), (10, 10)],
+        &[(60, 5), (100, 30), (55, 50), (60, 5)],
+        &[(20, 50), (30, 45), (40, 50), (40, 58), (30, 62), (20, 58), (20, 50)],
+        &[(70, 55), (80, 40), (90, 60), (100, 35), (120, 50)],
+    ];
+
+    /// Draw a strip through `corners` as a mesh does, counting the plots of
+    /// every pixel of a 128 by 64 screen
+    fn draw_strip(corners: &[(IFixed, IFixed)], plots: &mut [[u8; 128]; 64]) {
+        let screen = Rect::sized(128, 64);
+        let segments = corners.len() - 1;
+        let closed = corners[0] == corners[segments];
+        for (k, pair) in corners.windows(2).enumerate() {
+            let endpoints = match k {
+                0 => Endpoints::Both,
+                _ if closed && k + 1 == segments => Endpoints::SkipBoth,
+                _ => Endpoints::SkipStart,
+            };
+            let (v0, v1) = (vec2!(pair[0].0, pair[0].1), vec2!(pair[1].0, pair[1].1));
+            draw_line(|x, y| plots[y as usize][x as usize] += 1, v0, v1, endpoints, Style::Solid, &screen);
+        }
+    }
+
+    #[test]
+    fn strip_corners_are_plotted_once() {
+        for corners in STRIPS {
+            let mut plots = [[0; 128]; 64];
+            draw_strip(corners, &mut plots);
+            for &(x, y) in corners {
+                assert_eq!(plots[y as usize][x as usize], 1, "corner ({}, {}) of {:?}", x, y, corners);
+            }
+            assert!(plots.iter().flatten().all(|&n| n <= 1), "{:?} plots a pixel twice", corners);
+        }
+    }
+
+    #[test]
+    fn subpixel_strips_are_connected() {
+        let mut rng = Rng::new();
+        let screen = Rect::sized(128, 64);
+        let centers = pixel_centers(&screen);
+        for _ in 0..20_000 {
+            // Corners within the centers of the screen's edge pixels, so
+            // that no segment is clipped
+            let corners: Vec<Vec2<SubPixel>> = (0..2 + rng.next() % 4)
+                .map(|_| Vec2::new(rng.range(0, centers.max.x as i32), rng.range(0, centers.max.y as i32)))
+                .collect();
+            let mut plotted = [[false; 128]; 64];
+            for (k, pair) in corners.windows(2).enumerate() {
+                let endpoints = if k == 0 { Endpoints::Both } else { Endpoints::SkipStart };
+                draw_line_subpixel(|x, y| plotted[y as usize][x as usize] = true, pair[0], pair[1], endpoints, Style::Solid, &screen);
+            }
+            assert!(connected(&mut plotted), "strip through {:?} has a gap", corners.iter().map(|v| (v.x, v.y)).collect::<Vec<_>>());
+        }
+    }
+
+    /// Whether the plotted pixels are 8-connected, clearing those reached
+    /// from the first one
+    fn connected(plotted: &mut [[bool; 128]; 64]) -> bool {
+        let first = (0..64)
+            .flat_map(|y| (0..128).map(move |x| (x, y)))
+            .find(|&(x, y)| plotted[y as usize][x as usize]);
+        let mut pending: Vec<(i32, i32)> = first.into_iter().collect();
+        while let Some((x, y)) = pending.pop() {
+            plotted[y as usize][x as usize] = false;
+            for ny in y - 1..=y + 1 {
+                for nx in x - 1..=x + 1 {
+                    let pixel = plotted.get_mut(ny as usize).and_then(|row| row.get_mut(nx as usize));
+                    if let Some(pixel @ true) = pixel {
+                        *pixel = false;
+                        pending.push((nx, ny));
+                    }
+                }
+            }
+        }
+        plotted.iter().flatten().all(|&p| !p)
+    }
+}
@@ -0,0 +1,134 @@
+//! Run-length encoding of framebuffers
+//! 
+//! Shrinks the mostly empty framebuffer of a wireframe from 1 KiB to a few
+//! hundred bytes, for streaming it over serial. The encoding is a series of
+//! runs, each a control byte `c` followed by:
+//! 
+//! - for `c < 0x80`, `c + 1` literal bytes
+//! - for `c >= 0x80`, one byte repeated `c - 0x7f` times
+//! 
+//! The decoder knows the framebuffer size and stops once it is full.
+
+/// Longest literal or repeat a control byte can describe
+pub const MAX_RUN: usize = 128;
+
+/// Control byte flag of a repeat
+const REPEAT: u8 = 0x80;
+
+/// Run-length encode `data`, passing every encoded byte to `out`
+pub fn encode<F: FnMut(u8)>(data: &[u8], mut out: F) {
+    let repeat_len = |at: usize| {
+        data[at..].iter().take(MAX_RUN).take_while(|&&byte| byte == data[at]).count()
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let repeat = repeat_len(i);
+        if repeat >= 2 {
+            out(REPEAT | (repeat - 1) as u8);
+            out(data[i]);
+            i += repeat;
+            continue;
+        }
+
+        // Extend the literal up to where a repeat begins
+        let start = i;
+        i += 1;
+        while i < data.len() && i - start < MAX_RUN && repeat_len(i) < 2 {
+            i += 1;
+        }
+        out((i - start - 1) as u8);
+        data[start..i].iter().for_each(|&byte| out(byte));
+    }
+}
+
+/// Decode runs from `encoded` until `out` is full
+/// 
+/// Returns the number of encoded bytes read, or `None` if they run out
+/// before `out` is full or a run reaches past its end.
+pub fn decode(encoded: &[u8], out: &mut [u8]) -> Option<usize> {
+    let mut read = 0;
+    let mut written = 0;
+    while written < out.len() {
+        let control = *encoded.get(read)?;
+        read += 1;
+        if control & REPEAT == 0 {
+            let len = control as usize + 1;
+            let literal = encoded.get(read..read + len)?;
+            out.get_mut(written..written + len)?.copy_from_slice(literal);
+            read += len;
+            written += len;
+        } else {
+            let len = (control & !REPEAT) as usize + 1;
+            let byte = *encoded.get(read)?;
+            out.get_mut(written..written + len)?.fill(byte);
+            read += 1;
+            written += len;
+        }
+    }
+    Some(read)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+
+    /// Size of a 128 by 64 framebuffer
+    const FRAME_BYTES: usize = 1024;
+
+    fn round_trip(frame: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        encode(frame, |byte| encoded.push(byte));
+        let mut decoded = [0x5a; FRAME_BYTES];
+        assert_eq!(decode(&encoded, &mut decoded[..frame.len()]), Some(encoded.len()));
+        assert_eq!(&decoded[..frame.len()], frame);
+        encoded
+    }
+
+    #[test]
+    fn empty_frame_is_a_few_repeats() {
+        let encoded = round_trip(&[0; FRAME_BYTES]);
+        assert_eq!(encoded.len(), 2*FRAME_BYTES/MAX_RUN);
+    }
+
+    #[test]
+    fn runs_at_their_longest() {
+        for len in [1, 2, MAX_RUN - 1, MAX_RUN, MAX_RUN + 1, 2*MAX_RUN + 1] {
+            round_trip(&std::vec![0xff; len]);
+            let literal: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            round_trip(&literal);
+        }
+    }
+
+    #[test]
+    fn random_frames_round_trip() {
+        // Small xorshift generator, so every run tests the same frames
+        let mut state = 0x2545_f491u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        for density in [1, 4, 16, 64, 256] {
+            let frame: Vec<u8> = (0..FRAME_BYTES)
+                .map(|_| if next() % 256 < density { next() as u8 } else { 0 })
+                .collect();
+            round_trip(&frame);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_truncated_and_overlong_runs() {
+        let mut out = [0; 4];
+        assert_eq!(decode(&[0x03, 1, 2], &mut out), None);
+        assert_eq!(decode(&[0x81], &mut out), None);
+        assert_eq!(decode(&[0x84, 7], &mut out), None);
+        assert_eq!(decode(&[0x01, 1, 2, 0x81, 3, 0xff], &mut out), Some(5));
+        assert_eq!(out, [1, 2, 3, 3]);
+    }
+}
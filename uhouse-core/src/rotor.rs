@@ -0,0 +1,109 @@
+//! Rotations about one axis
+//! 
+//! A [`Rotor`] is a unit complex number, the cosine and sine of its angle,
+//! so composing two rotations is one complex multiplication and reversing
+//! one is a conjugation. Rotors are looked up from an angle in the
+//! [`sine`](crate::sine) table, such as one accumulated along a
+//! [`Track`](crate::scene::Track), or composed step by step and kept at unit
+//! length with [`Rotor::renormalize`].
+
+use crate::sine::rotor_from_fine_angle;
+use crate::units::Raw;
+use crate::vec::*;
+
+/// Rotation about one axis, as a unit complex number
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Rotor(Vec2<Raw>);
+
+/// Axis of view space to rotate about
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Axis {
+    /// Side to side, pitching the top towards the camera
+    X,
+
+    /// Up and down, spinning in place
+    Y,
+
+    /// Front to back, rolling sideways
+    Z,
+}
+
+impl Rotor {
+
+    /// The rotor that does not rotate
+    pub const IDENTITY: Self = Self(vec2!(0x1000, 0));
+
+    /// Rotor turning by `angle` 65536ths of a turn
+    pub fn from_angle(angle: u16) -> Self {
+        Self(rotor_from_fine_angle(angle))
+    }
+
+    /// Rotor of a unit complex number, such as one taken by
+    /// [`Vec2::rotate`]
+    pub const fn from_vec2(v: Vec2<Raw>) -> Self {
+        Self(v)
+    }
+
+    /// Rotor as a unit complex number, the cosine of its angle in `x` and
+    /// the sine in `y`
+    pub const fn as_vec2(self) -> Vec2<Raw> {
+        self.0
+    }
+
+    /// Rotate by `self`, then by `other`
+    #[must_use]
+    pub fn compose(self, other: Self) -> Self {
+        Self(self.0.rotate(other.0))
+    }
+
+    /// Rotation by the same angle the other way
+    #[must_use]
+    pub fn inverse(self) -> Self {
+        Self(vec2!(self.0.x, -self.0.y))
+    }
+
+    /// Pull the rotor back to unit length
+    /// 
+    /// Composing rotors one step after another loses a little length to
+    /// rounding every step, which one Newton step towards unit length
+    /// corrects without a square root.
+    #[must_use]
+    pub fn renormalize(self) -> Self {
+        let (x, y) = (self.0.x as i32, self.0.y as i32);
+        let length_squared = (x*x + y*y) >> 12;
+        let scale = (3*0x1000 - length_squared) >> 1;
+        Self(vec2!(((x*scale) >> 12) as IFixed, ((y*scale) >> 12) as IFixed))
+    }
+
+    /// Rotate a point in a plane
+    #[must_use]
+    pub fn apply<U>(self, v: Vec2<U>) -> Vec2<U> {
+        v.rotate(self.0)
+    }
+
+    /// Rotate a point about `axis`, the same way as a [`Mat3`] built from
+    /// this rotor for that axis
+    #[must_use]
+    pub fn apply_about<U>(self, axis: Axis, v: Vec3<U>) -> Vec3<U> {
+        match axis {
+            Axis::X => {
+                let yz: Vec2<U> = self.apply(vec2!(v.y, v.z));
+                vec3!(v.x, yz.x, yz.y)
+            }
+            Axis::Y => {
+                let xz: Vec2<U> = self.apply(vec2!(v.x, v.z));
+                vec3!(xz.x, v.y, xz.y)
+            }
+            Axis::Z => {
+                let xy: Vec2<U> = self.apply(vec2!(v.x, v.y));
+                vec3!(xy.x, xy.y, v.z)
+            }
+        }
+    }
+}
+
+impl Default for Rotor {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
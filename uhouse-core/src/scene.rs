@@ -0,0 +1,322 @@
+//! Declarative scene description
+//! 
+//! The [`scene!`] macro turns a readable list of objects into the program
+//! memory tables, [`Mesh`](crate::mesh::Mesh) descriptors and animation
+//! tracks the renderer needs, so table lengths and cross references no longer
+//! have to be kept in sync by hand.
+
+use crate::rotor::Rotor;
+use crate::vec::*;
+
+/// Distance from the camera to an object without a depth of its own
+pub const DEFAULT_DEPTH: IFixed = 0x2a00;
+
+/// Rotation applied once per frame
+#[derive(Copy, Clone)]
+pub struct Track {
+    /// Angle turned each frame, in 65536ths of a turn, see
+    /// [`sine`](crate::sine)
+    pub step: u16,
+}
+
+impl Track {
+
+    /// The track that never rotates
+    pub const NONE: Self = Self { step: 0 };
+}
+
+/// Rotation accumulated along a [`Track`]
+/// 
+/// The angle is accumulated rather than the rotor, which is looked up from
+/// it every frame, so no rounding error builds up however long it runs.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct TrackState {
+    /// Rotation turned so far
+    pub rotor: Rotor,
+
+    /// Angle turned so far, wrapping around after a full turn
+    angle: u16,
+}
+
+impl TrackState {
+
+    /// Start without any rotation
+    pub const fn new() -> Self {
+        Self {
+            rotor: Rotor::IDENTITY,
+            angle: 0,
+        }
+    }
+
+    /// Advance by one frame along `track`
+    pub fn advance(&mut self, track: Track) {
+        self.angle = self.angle.wrapping_add(track.step);
+        self.rotor = Rotor::from_angle(self.angle);
+    }
+}
+
+impl Default for TrackState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Declare the objects of a scene
+/// 
+/// ```ignore
+/// scene! {
+///     /// The house scene
+///     object HOUSE {
+///         depth: 0x2a00,
+///         spin: ROT0,
+///         orbit: LOC0,
+///         pitch: degrees(-2),
+///         verts: [vec3!(0x800, 0x800, 0x800), ...],
+///         strips: [5, 0, 1, 2, 3, 0, ...],
+///     }
+/// }
+/// ```
+/// 
+/// Each object expands to a `static` [`Mesh`](crate::mesh::Mesh) and a module
+/// of the same name holding its tables, their sizes `NUM_VERTS` and
+/// `NUM_STRIP_BYTES`, its `SPIN`, `ORBIT`, `PITCH` and `ROLL` [`Track`]s
+/// and its `DEPTH` from the camera. Every track is given as its angle per
+/// frame, see [`degrees`](crate::sine::degrees), and is optional, defaulting
+/// to [`Track::NONE`]. The depth defaults to [`DEFAULT_DEPTH`]. The strips,
+/// faces and fills are checked against the vertices at compile time, see
+/// [`strips_fit`](crate::mesh::strips_fit).
+/// Items of the enclosing module are visible to the object fields, and an
+/// object declared `pub object` is visible outside of it.
+/// 
+/// A symmetric object can store only one half and add `mirror: X,` or
+/// `mirror: Z,` after its tracks to be completed by a mirror instance, see
+/// [`Mesh::mirror`](crate::mesh::Mesh::mirror).
+/// 
+/// Objects with culled strips add `faces: [...]` after their strips, giving
+/// `FACES` and `NUM_FACES`. Without it an object has no faces and all of its
+/// strips are always drawn, see [`Mesh::faces`](crate::mesh::Mesh::faces).
+/// Strips are drawn dashed, dotted or faint by adding
+/// [`DASHED`](crate::mesh::DASHED), [`DOTTED`](crate::mesh::DOTTED) or
+/// [`FAINT`](crate::mesh::FAINT) to their count.
+/// 
+/// Objects drawn with filled faces add `fills: [...]` after their faces,
+/// giving `FILLS` and `NUM_FILLS`, see [`fill`](crate::fill). Without it
+/// nothing is filled.
+/// 
+/// Objects with parts moving on their own add
+/// `animate: (period, [parts], [keyframes])` after their fills, giving
+/// `PERIOD`, `PARTS`, `NUM_PARTS`, `KEYS` and `NUM_KEYS`, see
+/// [`animation`](crate::animation). The keyframes of every part follow those
+/// of the part before it, and their number is checked against the parts at
+/// compile time. Without it nothing moves on its own.
+/// 
+/// An object morphing into another shape and back adds
+/// `morph: (target, period, ease)` after its animation, giving `MORPH_VERTS`,
+/// `NUM_MORPH_VERTS` and the `MORPH` [`MorphTrack`](crate::morph::MorphTrack).
+/// Without it `MORPH_VERTS` is empty and `MORPH` is
+/// [`MorphTrack::NONE`](crate::morph::MorphTrack::NONE), so that every object
+/// can be drawn by the same morphing code without storing its vertices
+/// twice. The target must have as many vertices as `verts`, and may be
+/// derived from them through `MODEL`, the vertices as a constant. `ease`
+/// names an [`Ease`](crate::morph::Ease).
+/// 
+/// Every object has a `BOUNDS` [`Sphere`](crate::bounds::Sphere) holding
+/// its vertices, for skipping it while it is off screen. It is worked out at
+/// compile time around `verts`, any morph target and every pose of the
+/// animated parts, or given as `bounds: (center, radius)` after everything
+/// else, which the mesh pipeline does with the smallest sphere. Given bounds
+/// must hold every pose.
+#[macro_export]
+macro_rules! scene {
+    (@track) => {
+        $crate::scene::Track::NONE
+    };
+    (@track $step:expr) => {
+        $crate::scene::Track { step: $step }
+    };
+    (@depth) => {
+        $crate::scene::DEFAULT_DEPTH
+    };
+    (@depth $depth:expr) => {
+        $depth
+    };
+    (@faces) => {
+        []
+    };
+    (@faces $faces:expr) => {
+        $faces
+    };
+    (@fills) => {
+        [$crate::fill::Fill::triangle(0, 0, 0, 0); 0]
+    };
+    (@fills $fills:expr) => {
+        $fills
+    };
+    (@period) => {
+        0
+    };
+    (@period $period:expr) => {
+        $period
+    };
+    (@parts) => {
+        [$crate::animation::Part::new(0..0, $crate::rotor::Axis::Y, $crate::vec3!(0, 0, 0), 0); 0]
+    };
+    (@parts $parts:expr) => {
+        $parts
+    };
+    (@keys) => {
+        [$crate::animation::Keyframe::new(0, $crate::vec3!(0, 0, 0), 0); 0]
+    };
+    (@keys $keys:expr) => {
+        $keys
+    };
+    (@morph) => {
+        $crate::morph::MorphTrack::NONE
+    };
+    (@morph_verts) => {
+        [] as [$crate::vec::Vec3; 0]
+    };
+    (@morph_verts $morph:expr) => {
+        $morph
+    };
+    (@bounds [$($parts:expr, $keys:expr)?] [$($morph:expr)?]) => {
+        $crate::bounds::Sphere::around(
+            &MODEL,
+            &$crate::scene!(@morph_verts $($morph)?),
+            &$crate::scene!(@parts $($parts)?),
+            &$crate::scene!(@keys $($keys)?),
+        )
+    };
+    (@bounds [$($parts:expr, $keys:expr)?] [$($morph:expr)?] $center:expr, $radius:expr) => {
+        $crate::bounds::Sphere::new($center, $radius)
+    };
+    (@morph $period:expr, $ease:ident) => {
+        $crate::morph::MorphTrack {
+            period: $period,
+            ease: $crate::morph::Ease::$ease,
+        }
+    };
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis object $name:ident {
+            $(depth: $depth:expr,)?
+            $(spin: $spin:expr,)?
+            $(orbit: $orbit:expr,)?
+            $(pitch: $pitch:expr,)?
+            $(roll: $roll:expr,)?
+            $(mirror: $mirror:ident,)?
+            verts: $verts:expr,
+            strips: $strips:expr
+            $(, faces: $faces:expr)?
+            $(, fills: $fills:expr)?
+            $(, animate: ($period:expr, $parts:expr, $keys:expr))?
+            $(, morph: ($morph:expr, $morph_period:expr, $ease:ident))?
+            $(, bounds: ($center:expr, $radius:expr))? $(,)?
+        }
+    )*) => {$(
+        #[allow(non_snake_case)]
+        $vis mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            /// Distance from the camera to the center of the object
+            #[allow(dead_code)]
+            pub const DEPTH: $crate::vec::IFixed = $crate::scene!(@depth $($depth)?);
+
+            /// Rotation of the object about its vertical axis
+            #[allow(dead_code)]
+            pub const SPIN: $crate::scene::Track = $crate::scene!(@track $($spin)?);
+
+            /// Movement of the object around the scene
+            #[allow(dead_code)]
+            pub const ORBIT: $crate::scene::Track = $crate::scene!(@track $($orbit)?);
+
+            /// Rotation of the object about its side to side axis
+            #[allow(dead_code)]
+            pub const PITCH: $crate::scene::Track = $crate::scene!(@track $($pitch)?);
+
+            /// Rotation of the object about its front to back axis
+            #[allow(dead_code)]
+            pub const ROLL: $crate::scene::Track = $crate::scene!(@track $($roll)?);
+
+            /// Frames before the keyframes of the parts loop back to the start
+            #[allow(dead_code)]
+            pub const PERIOD: u16 = $crate::scene!(@period $($period)?);
+
+            /// Morph of the object towards [`MORPH_VERTS`] and back
+            #[allow(dead_code)]
+            pub const MORPH: $crate::morph::MorphTrack = $crate::scene!(@morph $($morph_period, $ease)?);
+
+            /// Vertices in model space as a constant, for deriving other
+            /// tables from them at compile time
+            #[allow(dead_code)]
+            pub const MODEL: [$crate::vec::Vec3; NUM_VERTS] = $verts;
+
+            $crate::avr_progmem::progmem! {
+
+                /// Vertices in model space
+                pub static progmem<const NUM_VERTS: usize> VERTS: [$crate::vec::Vec3; NUM_VERTS] = $verts;
+
+                /// Line strips as indices into [`VERTS`]
+                pub static progmem<const NUM_STRIP_BYTES: usize> STRIPS: [u8; NUM_STRIP_BYTES] = $strips;
+
+                /// Faces for backface culling as indices into [`VERTS`]
+                pub static progmem<const NUM_FACES: usize> FACES: [[u8; 3]; NUM_FACES] = $crate::scene!(@faces $($faces)?);
+
+                /// Faces to fill in as indices into [`VERTS`]
+                pub static progmem<const NUM_FILLS: usize> FILLS: [$crate::fill::Fill; NUM_FILLS] = $crate::scene!(@fills $($fills)?);
+
+                /// Ranges of [`VERTS`] moved by keyframes
+                pub static progmem<const NUM_PARTS: usize> PARTS: [$crate::animation::Part; NUM_PARTS] = $crate::scene!(@parts $($parts)?);
+
+                /// Keyframes of every part of [`PARTS`], one part after
+                /// another
+                pub static progmem<const NUM_KEYS: usize> KEYS: [$crate::animation::Keyframe; NUM_KEYS] = $crate::scene!(@keys $($keys)?);
+            }
+
+            const _: () = assert!(
+                $crate::mesh::strips_fit(&$strips, NUM_VERTS),
+                "a strip is shorter than two vertices, longer than LEN or past the end of the mesh",
+            );
+            const _: () = assert!(
+                $crate::mesh::faces_fit(&$crate::scene!(@faces $($faces)?), NUM_VERTS),
+                "a face has a corner past the end of the mesh",
+            );
+            const _: () = assert!(
+                $crate::fill::fills_fit(&$crate::scene!(@fills $($fills)?), NUM_VERTS),
+                "a fill has a corner past the end of the mesh",
+            );
+            const _: () = assert!(
+                $crate::animation::key_count(&$crate::scene!(@parts $($parts)?)) == NUM_KEYS,
+                "the parts do not follow as many keyframes as there are",
+            );
+            const _: () = assert!(
+                $crate::animation::parts_fit(&$crate::scene!(@parts $($parts)?), NUM_VERTS),
+                "a part has no keyframes or vertices past the end of the mesh",
+            );
+
+            $crate::avr_progmem::progmem! {
+
+                /// Vertices to morph towards, one for each of [`VERTS`], or
+                /// none for an object that does not morph
+                pub static progmem<const NUM_MORPH_VERTS: usize> MORPH_VERTS: [$crate::vec::Vec3; NUM_MORPH_VERTS] = $crate::scene!(@morph_verts $($morph)?);
+
+                /// Sphere holding every vertex, for skipping the object while it
+                /// is off screen
+                pub static progmem BOUNDS: $crate::bounds::Sphere = $crate::scene!(@bounds [$($parts, $keys)?] [$($morph)?] $($center, $radius)?);
+            }
+
+            const _: () = assert!(
+                NUM_MORPH_VERTS == 0 || NUM_MORPH_VERTS == NUM_VERTS,
+                "the morph target does not have as many vertices as the mesh",
+            );
+        }
+
+        $(#[$attr])*
+        $vis static $name: $crate::mesh::Mesh<{ $name::NUM_VERTS }, { $name::NUM_STRIP_BYTES }, { $name::NUM_FACES }> =
+            // SAFETY: The strips and faces are checked against the vertices
+            // at compile time above
+            unsafe { $crate::mesh::Mesh::new(&$name::VERTS, &$name::STRIPS, &$name::FACES) }
+                $(.mirrored($crate::mesh::Mirror::$mirror))?;
+    )*};
+}
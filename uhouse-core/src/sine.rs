@@ -0,0 +1,84 @@
+//! Sine and cosine from a lookup table
+//! 
+//! Angles are binary, with a full turn of 256 brads in a `u8`, or of 65536
+//! steps in a `u16` where finer steps are needed. An angle accumulated one
+//! step at a time wraps around after exactly one turn, so it never drifts
+//! and never needs resetting, unlike a rotor rotated by a fixed step every
+//! frame.
+//! 
+//! Only a quarter of a wave is stored, in program memory, at every brad.
+//! Angles in between are interpolated, and every sine is within 1.2 of the
+//! last bit of an [`IFixed`].
+
+use avr_progmem::progmem;
+
+use crate::units::Raw;
+use crate::vec::*;
+
+progmem! {
+
+    /// Sine of every brad from 0 to a quarter turn, both included
+    /// 
+    /// From the equation `round(4096*sin(i*pi/128))`
+    static progmem QUARTER_WAVE: [IFixed; 65] = [
+        0x000, 0x065, 0x0c9, 0x12d, 0x191, 0x1f5, 0x259, 0x2bc,
+        0x31f, 0x381, 0x3e3, 0x444, 0x4a5, 0x505, 0x564, 0x5c2,
+        0x61f, 0x67c, 0x6d7, 0x732, 0x78b, 0x7e3, 0x83a, 0x88f,
+        0x8e4, 0x937, 0x988, 0x9d8, 0xa26, 0xa73, 0xabf, 0xb08,
+        0xb50, 0xb97, 0xbdb, 0xc1e, 0xc5e, 0xc9d, 0xcda, 0xd15,
+        0xd4e, 0xd85, 0xdb9, 0xdec, 0xe1c, 0xe4b, 0xe77, 0xea1,
+        0xec8, 0xeee, 0xf11, 0xf31, 0xf50, 0xf6c, 0xf85, 0xf9c,
+        0xfb1, 0xfc4, 0xfd4, 0xfe1, 0xfec, 0xff5, 0xffb, 0xfff,
+        0x1000,
+    ];
+}
+
+/// A quarter turn as a fine angle
+const QUARTER_TURN: u16 = 0x4000;
+
+/// Fine angle of a whole number of degrees, for declaring steps at compile
+/// time
+/// 
+/// Negative angles turn the other way.
+pub const fn degrees(degrees: i16) -> u16 {
+    (degrees as i32*0x10000 / 360) as u16
+}
+
+/// Sine of a fine angle
+pub fn sin(angle: u16) -> IFixed {
+    let within = angle % QUARTER_TURN;
+    match angle / QUARTER_TURN {
+        0 => quarter_wave(within),
+        1 => quarter_wave(QUARTER_TURN - within),
+        2 => -quarter_wave(within),
+        _ => -quarter_wave(QUARTER_TURN - within),
+    }
+}
+
+/// Cosine of a fine angle
+pub fn cos(angle: u16) -> IFixed {
+    sin(angle.wrapping_add(QUARTER_TURN))
+}
+
+/// Unit rotor turning by `brad` 256ths of a turn, as taken by
+/// [`Vec2::rotate`]
+pub fn rotor_from_angle(brad: u8) -> Vec2<Raw> {
+    rotor_from_fine_angle((brad as u16) << 8)
+}
+
+/// Unit rotor turning by `angle` 65536ths of a turn, as taken by
+/// [`Vec2::rotate`]
+pub fn rotor_from_fine_angle(angle: u16) -> Vec2<Raw> {
+    vec2!(cos(angle), sin(angle))
+}
+
+/// Sine of a fine angle from 0 to a quarter turn, both included
+fn quarter_wave(angle: u16) -> IFixed {
+    let (brad, fraction) = ((angle >> 8) as usize, (angle & 0xff) as i32);
+    let low = QUARTER_WAVE.load_at(brad) as i32;
+    if fraction == 0 {
+        return low as IFixed;
+    }
+    let high = QUARTER_WAVE.load_at(brad + 1) as i32;
+    (low + (((high - low)*fraction + 0x80) >> 8)) as IFixed
+}
@@ -0,0 +1,65 @@
+//! Transforms from model space onto the screen
+
+use crate::clip;
+use crate::mesh::ScreenVert;
+use crate::projection::Project;
+use crate::rotor::Rotor;
+use crate::units::{SubPixel, World};
+use crate::vec::*;
+
+/// Transform a vertex from model space into view space
+/// 
+/// Rotates the mesh by `rotation`, moves it around and up and down by
+/// `location`, and pushes it `depth` into the screen.
+pub fn view_transform(v: Vec3, rotation: &Mat3, location: Rotor, depth: IFixed) -> Vec3 {
+    let rotated = rotation.apply(v);
+
+    // The rotor doubles as a point on the unit circle in world units
+    let location = location.as_vec2();
+    let moved: Vec2<World> = vec2!(rotated.x, rotated.z) + vec2!(location.y, location.x);
+    vec3!(
+        moved.x,
+        rotated.y + (location.x >> 2),
+        moved.y + depth
+    )
+}
+
+/// Project a vertex from view space onto the screen
+/// 
+/// Applies the projection `P` and centers the result on `center`. Vertices
+/// in front of the near plane are not projected and end up at
+/// [`clip::BEHIND_NEAR`] instead.
+pub fn project<P: Project>(view: Vec3, center: Vec2) -> Vec2 {
+    if view.z < clip::NEAR {
+        clip::BEHIND_NEAR
+    } else {
+        P::project(view) + center
+    }
+}
+
+/// Project a vertex from view space onto the screen in sixteenths of a pixel
+/// 
+/// As [`project`], with vertices in front of the near plane at
+/// [`clip::SUBPIXEL_BEHIND_NEAR`].
+pub fn project_subpixel<P: Project>(view: Vec3, center: Vec2) -> Vec2<SubPixel> {
+    if view.z < clip::NEAR {
+        clip::SUBPIXEL_BEHIND_NEAR
+    } else {
+        P::project_subpixel(view) + center.to_subpixels()
+    }
+}
+
+/// Project a vertex from view space onto the screen for
+/// [`Mesh::draw`](crate::mesh::Mesh::draw), in sixteenths of a pixel with the
+/// `subpixel` feature and in whole pixels without
+pub fn project_vert<P: Project>(view: Vec3, center: Vec2) -> ScreenVert {
+    #[cfg(feature = "subpixel")]
+    {
+        project_subpixel::<P>(view, center)
+    }
+
+    #[cfg(not(feature = "subpixel"))]
+    {
+        project::<P>(view, center)
+    }
+}
@@ -0,0 +1,39 @@
+//! Units of the vector types
+//! 
+//! A [`Vec2`](crate::vec::Vec2) or [`Vec3`](crate::vec::Vec3) carries its
+//! unit as a type parameter, so that a vertex in world units cannot be passed
+//! where a point in screen pixels is expected, or the other way around. The
+//! unit is only a marker and costs nothing at runtime.
+//! 
+//! Vectors change units only where one space is mapped onto another, such as
+//! [`Project::project`](crate::projection::Project::project) taking a point
+//! in [`World`] units to [`Screen`] pixels, or to [`SubPixel`]s for the
+//! `subpixel` feature. Vectors left without a unit in a type are in world
+//! units in 3D and in screen pixels in 2D, the spaces most of them live in.
+
+/// Model and view space, in [`IFixed`](crate::vec::IFixed) fixed point with
+/// 12 fractional bits
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct World;
+
+/// Screen space, in whole pixels from the top left corner, or from the
+/// center of the screen before centering
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct Screen;
+
+/// Screen space in sixteenths of a pixel, as kept by the `subpixel` feature
+/// for the endpoints of lines
+/// 
+/// A pixel's center is at a multiple of sixteen, so a point belongs to the
+/// pixel it is nearest to, see [`Vec2::to_pixels`](crate::vec::Vec2::to_pixels).
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct SubPixel;
+
+/// Fractional bits of a [`SubPixel`] coordinate
+pub const SUBPIXEL_BITS: u8 = 4;
+
+/// Raw fixed-point values of no space, such as the cosine and sine of a
+/// [`Rotor`](crate::rotor::Rotor) or the rows of a
+/// [`Mat3`](crate::vec::Mat3)
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct Raw;
@@ -0,0 +1,193 @@
+//! Validation of meshes loaded at runtime
+//! 
+//! The built-in mesh is hard-coded and trusted, but a mesh uploaded over
+//! serial or read back from EEPROM could hold anything. Run it through
+//! [`validate_mesh`] before activating it, and keep rendering the built-in
+//! house if validation fails.
+
+use core::mem::size_of;
+
+use crate::mesh::{FaceMask, CULLED, LEN};
+use crate::vec::*;
+
+/// Largest number of vertices an edge index can address
+pub const MAX_VERTS: usize = u8::MAX as usize + 1;
+
+/// Largest distance of a vertex from the Y axis
+/// 
+/// Rotated vertices are moved by up to `0x1000` and must stay in front of the
+/// camera at [`MESH_DEPTH`](crate::house::MESH_DEPTH).
+pub const MAX_RADIUS: IFixed = 0x1800;
+
+/// Largest magnitude of a vertex Y coordinate
+pub const MAX_HEIGHT: IFixed = 0x1800;
+
+/// Reason a mesh was rejected
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum MeshError {
+    /// Mesh has no vertices or more than [`MAX_VERTS`]
+    VertexCount,
+
+    /// Strip at this offset is shorter than two vertices or runs past the end
+    /// of the strip data
+    MalformedStrip(u16),
+
+    /// Strip at this offset references a vertex that does not exist
+    IndexOutOfRange(u16),
+
+    /// Vertex at this position lies outside the renderable range
+    VertexOutOfRange(u16),
+
+    /// Checksum did not match the mesh data
+    Crc,
+}
+
+/// Check that a vertex stays renderable under every transform
+fn vertex_in_range(v: Vec3) -> bool {
+    let x = v.x as i32;
+    let z = v.z as i32;
+    let r = MAX_RADIUS as i32;
+    x*x + z*z <= r*r && v.y.unsigned_abs() <= MAX_HEIGHT as u16
+}
+
+/// Update a CRC-16/CCITT-FALSE checksum with one byte
+pub fn crc16_update(mut crc: u16, byte: u8) -> u16 {
+    crc ^= (byte as u16) << 8;
+    for _ in 0..8 {
+        crc = if crc & 0x8000 != 0 {
+            (crc << 1) ^ 0x1021
+        } else {
+            crc << 1
+        };
+    }
+    crc
+}
+
+/// CRC-16/CCITT-FALSE checksum of a mesh
+/// 
+/// Vertices are hashed as little-endian `x, y, z` followed by the strip
+/// data, matching the order a mesh is sent in.
+pub fn mesh_crc(verts: &[Vec3], strips: &[u8]) -> u16 {
+    let mut crc = 0xffff;
+    for v in verts {
+        for c in [v.x, v.y, v.z] {
+            for byte in c.to_le_bytes() {
+                crc = crc16_update(crc, byte);
+            }
+        }
+    }
+    for &byte in strips {
+        crc = crc16_update(crc, byte);
+    }
+    crc
+}
+
+/// Validate a mesh before it is activated
+/// 
+/// Checks the checksum first so that corrupt data is reported as such rather
+/// than as whichever index or vertex it happened to garble.
+/// 
+/// `strips` uses the same line strip layout as the built-in
+/// [`Mesh::strips`](crate::mesh::Mesh::strips). A mesh checked here has no
+/// faces, so its culled strips are never drawn.
+pub fn validate_mesh(verts: &[Vec3], strips: &[u8], crc: u16) -> Result<(), MeshError> {
+    if verts.is_empty() || verts.len() > MAX_VERTS {
+        return Err(MeshError::VertexCount);
+    }
+
+    if mesh_crc(verts, strips) != crc {
+        return Err(MeshError::Crc);
+    }
+
+    let mut offset = 0;
+    while offset < strips.len() {
+        let header = strips[offset];
+        let len = (header & LEN) as usize;
+        let start = match header & CULLED {
+            0 => offset + 1,
+            _ => offset + 1 + size_of::<FaceMask>(),
+        };
+        let strip = match strips.get(start..start + len) {
+            Some(strip) if len >= 2 => strip,
+            _ => return Err(MeshError::MalformedStrip(offset as u16)),
+        };
+        if strip.iter().any(|&i| i as usize >= verts.len()) {
+            return Err(MeshError::IndexOutOfRange(offset as u16));
+        }
+        offset = start + len;
+    }
+
+    for (i, &v) in verts.iter().enumerate() {
+        if !vertex_in_range(v) {
+            return Err(MeshError::VertexOutOfRange(i as u16));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::mesh::DASHED;
+
+    /// Corners of a square, small enough for every transform
+    const VERTS: [Vec3; 4] = [
+        vec3!(-0x800, -0x800, 0),
+        vec3!(0x800, -0x800, 0),
+        vec3!(0x800, 0x800, 0),
+        vec3!(-0x800, 0x800, 0),
+    ];
+
+    /// Validate with the right checksum, so the other checks are reached
+    fn check(verts: &[Vec3], strips: &[u8]) -> Result<(), MeshError> {
+        validate_mesh(verts, strips, mesh_crc(verts, strips))
+    }
+
+    #[test]
+    fn valid_mesh_is_accepted() {
+        assert!(check(&VERTS, &[5, 0, 1, 2, 3, 0]) == Ok(()));
+        assert!(check(&VERTS, &[2, 0, 2, DASHED | 2, 1, 3]) == Ok(()));
+        assert!(check(&VERTS, &[CULLED | 2, 0x01, 0x00, 0, 1]) == Ok(()));
+    }
+
+    #[test]
+    fn vertex_counts_out_of_range_are_rejected() {
+        assert!(check(&[], &[]) == Err(MeshError::VertexCount));
+        let many: Vec<Vec3> = (0..MAX_VERTS + 1).map(|_| VERTS[0]).collect();
+        assert!(check(&many, &[]) == Err(MeshError::VertexCount));
+    }
+
+    #[test]
+    fn malformed_strips_are_rejected() {
+        assert!(check(&VERTS, &[5, 0, 1, 2, 3, 0, 1, 2]) == Err(MeshError::MalformedStrip(6)));
+        assert!(check(&VERTS, &[2, 0, 1, 3, 0, 1]) == Err(MeshError::MalformedStrip(3)));
+        assert!(check(&VERTS, &[CULLED | 2, 0x01, 0x00, 0]) == Err(MeshError::MalformedStrip(0)));
+    }
+
+    #[test]
+    fn indices_out_of_range_are_rejected() {
+        assert!(check(&VERTS, &[2, 0, 1, 2, 3, 4]) == Err(MeshError::IndexOutOfRange(3)));
+    }
+
+    #[test]
+    fn vertices_out_of_range_are_rejected() {
+        let mut verts = VERTS;
+        verts[2] = vec3!(MAX_RADIUS, 0, 1);
+        assert!(check(&verts, &[2, 0, 1]) == Err(MeshError::VertexOutOfRange(2)));
+        verts[2] = vec3!(0, MAX_HEIGHT + 1, 0);
+        assert!(check(&verts, &[2, 0, 1]) == Err(MeshError::VertexOutOfRange(2)));
+    }
+
+    #[test]
+    fn corrupt_data_fails_the_checksum() {
+        let strips = [5, 0, 1, 2, 3, 0];
+        let crc = mesh_crc(&VERTS, &strips);
+        assert!(validate_mesh(&VERTS, &[5, 0, 1, 2, 3, 1], crc) == Err(MeshError::Crc));
+        assert!(validate_mesh(&VERTS, &strips, crc ^ 1) == Err(MeshError::Crc));
+    }
+}
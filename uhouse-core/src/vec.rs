@@ -0,0 +1,731 @@
+use core::{
+    convert::From,
+    marker::PhantomData,
+    ops::{Add, AddAssign, Mul, Neg, Sub},
+};
+
+use crate::rotor::Rotor;
+use crate::units::{Raw, Screen, SubPixel, World, SUBPIXEL_BITS};
+
+/// Fixed-point type
+/// 
+/// This project uses a fixed point representation for mesh vertices and their
+/// transforms to improve performance. The Atmega328P has no floating point
+/// unit so all floating point math would be done in software suffering a
+/// performance hit.
+/// 
+/// The fixed point representation here uses 16-bit signed integers with a
+/// 12-bit fractional part. This allows a granularity of ~0.000244 with an
+/// integer part in the range [-8, 7].
+/// 
+/// Vector arithmetic whose result falls outside that range wraps around by
+/// default, as integers do in a release build, in debug builds as well. With
+/// the `saturating` feature it sticks at the nearest end of the range
+/// instead, and with the `overflow-checks` feature it panics, to catch
+/// out-of-range geometry while working on a model.
+pub type IFixed = i16;
+
+/// Private fixed-point intermediate type for multiplication
+/// 
+/// Use [`IFixed`] instead for general use.
+type IFixedMul = i32;
+
+/// Narrow a widened fixed-point result back to [`IFixed`]
+/// 
+/// A result out of range wraps by default, saturates with the `saturating`
+/// feature and panics with the `overflow-checks` feature.
+#[inline(always)]
+const fn narrow(value: IFixedMul) -> IFixed {
+    #[cfg(feature = "saturating")]
+    {
+        if value > IFixed::MAX as IFixedMul {
+            IFixed::MAX
+        } else if value < IFixed::MIN as IFixedMul {
+            IFixed::MIN
+        } else {
+            value as IFixed
+        }
+    }
+
+    #[cfg(feature = "overflow-checks")]
+    {
+        if value > IFixed::MAX as IFixedMul || value < IFixed::MIN as IFixedMul {
+            panic!("fixed-point overflow");
+        }
+        value as IFixed
+    }
+
+    #[cfg(not(any(feature = "saturating", feature = "overflow-checks")))]
+    {
+        value as IFixed
+    }
+}
+
+/// Add two fixed-point values, overflowing as [`narrow`] does
+#[inline(always)]
+const fn add(a: IFixed, b: IFixed) -> IFixed {
+    #[cfg(any(feature = "saturating", feature = "overflow-checks"))]
+    {
+        narrow(a as IFixedMul + b as IFixedMul)
+    }
+
+    #[cfg(not(any(feature = "saturating", feature = "overflow-checks")))]
+    {
+        a.wrapping_add(b)
+    }
+}
+
+/// Subtract two fixed-point values, overflowing as [`narrow`] does
+#[inline(always)]
+const fn sub(a: IFixed, b: IFixed) -> IFixed {
+    #[cfg(any(feature = "saturating", feature = "overflow-checks"))]
+    {
+        narrow(a as IFixedMul - b as IFixedMul)
+    }
+
+    #[cfg(not(any(feature = "saturating", feature = "overflow-checks")))]
+    {
+        a.wrapping_sub(b)
+    }
+}
+
+/// Add two widened products, overflowing as [`narrow`] does
+/// 
+/// Products of two [`IFixed`] fit in an [`IFixedMul`], but sums of them may
+/// not. A sum past its range is past that of [`IFixed`] once shifted back
+/// down, so saturating or panicking here gives the same result as [`narrow`]
+/// would, and wrapping keeps the bits that [`narrow`] keeps.
+#[inline(always)]
+const fn add_wide(a: IFixedMul, b: IFixedMul) -> IFixedMul {
+    #[cfg(feature = "saturating")]
+    {
+        a.saturating_add(b)
+    }
+
+    #[cfg(feature = "overflow-checks")]
+    {
+        match a.checked_add(b) {
+            Some(sum) => sum,
+            None => panic!("fixed-point overflow"),
+        }
+    }
+
+    #[cfg(not(any(feature = "saturating", feature = "overflow-checks")))]
+    {
+        a.wrapping_add(b)
+    }
+}
+
+/// Subtract two widened products, overflowing as [`add_wide`] does
+#[inline(always)]
+const fn sub_wide(a: IFixedMul, b: IFixedMul) -> IFixedMul {
+    #[cfg(feature = "saturating")]
+    {
+        a.saturating_sub(b)
+    }
+
+    #[cfg(feature = "overflow-checks")]
+    {
+        match a.checked_sub(b) {
+            Some(difference) => difference,
+            None => panic!("fixed-point overflow"),
+        }
+    }
+
+    #[cfg(not(any(feature = "saturating", feature = "overflow-checks")))]
+    {
+        a.wrapping_sub(b)
+    }
+}
+
+/// Fixed-point one, the scale of the fractional part
+const ONE: IFixedMul = 1 << 12;
+
+/// Multiply two fixed-point values, rounding to the nearest with ties
+/// toward positive infinity
+fn mul_round(a: IFixed, b: IFixed) -> IFixed {
+    narrow((a as IFixedMul*b as IFixedMul + (ONE >> 1)) >> 12)
+}
+
+/// Interpolate between two fixed-point values by `t`
+fn lerp(a: IFixed, b: IFixed, t: IFixed) -> IFixed {
+    narrow(a as IFixedMul + (((b as IFixedMul - a as IFixedMul)*t as IFixedMul) >> 12))
+}
+
+/// Square root of a fixed-point value squared, as taken by `length`,
+/// saturating at [`IFixed::MAX`]
+/// 
+/// Computed one bit at a time with shifts and subtractions, as the
+/// Atmega328P has no divider.
+pub(crate) const fn sqrt(mut value: u32) -> IFixed {
+    let mut root = 0u32;
+    let mut bit = 1u32 << 30;
+    while bit > value {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if value >= root + bit {
+            value -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    if root > IFixed::MAX as u32 {
+        IFixed::MAX
+    } else {
+        root as IFixed
+    }
+}
+
+/// 2D vector type of [`IFixed`]
+/// 
+/// Points on the screen by default, see [`units`](crate::units).
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct Vec2<U = Screen> {
+    pub x: IFixed,
+    pub y: IFixed,
+    unit: PhantomData<U>,
+}
+
+/// Private intermediate 2D vector type for multiplication
+/// 
+/// Use [`Vec2`] instead for general use.
+struct Vec2Mul {
+    x: IFixedMul,
+    y: IFixedMul,
+}
+
+impl<U> From<Vec2<U>> for Vec2Mul {
+    fn from(value: Vec2<U>) -> Self {
+        Self {
+            x: value.x as IFixedMul,
+            y: value.y as IFixedMul,
+        }
+    }
+}
+
+impl<U> From<Vec2Mul> for Vec2<U> {
+    fn from(value: Vec2Mul) -> Self {
+        Self::new(narrow(value.x), narrow(value.y))
+    }
+}
+
+/// Convenience macro for creating vectors via `vec2!(x, y)`
+/// 
+/// The unit of the vector is taken from where it is used.
+#[macro_export]
+macro_rules! vec2 {
+    ($x:expr, $y:expr) => {
+        $crate::vec::Vec2::new($x, $y)
+    }
+}
+
+impl<U> Vec2<U> {
+
+    /// Vector of two components, as [`vec2!`] creates
+    pub const fn new(x: IFixed, y: IFixed) -> Self {
+        Self {
+            x,
+            y,
+            unit: PhantomData,
+        }
+    }
+
+    /// Multiply by a rotor as a complex number
+    #[must_use]
+    pub fn rotate(self, other: Vec2<Raw>) -> Self {
+        let v1 = Vec2Mul::from(self);
+        let v2 = Vec2Mul::from(other);
+        Self::from(Vec2Mul {
+            x: (((v1.x*v2.x) - (v1.y*v2.y)) >> 12),
+            y: (((v1.x*v2.y) + (v1.y*v2.x)) >> 12),
+        })
+    }
+
+    /// Swap x and y
+    #[must_use]
+    pub fn swap(self) -> Self {
+        vec2!(self.y, self.x)
+    }
+
+    /// Component-wise absolute value
+    #[must_use]
+    pub fn component_abs(self) -> Self {
+        vec2!(self.x.abs(), self.y.abs())
+    }
+
+    /// Fixed-point dot product
+    /// 
+    /// Rounds toward negative infinity as [`Vec2::rotate`] does. The result
+    /// overflows if it falls outside the range of [`IFixed`], which it cannot
+    /// for two vectors of length 2 or less.
+    #[must_use]
+    pub fn dot(self, other: Self) -> IFixed {
+        let (a, b) = (Vec2Mul::from(self), Vec2Mul::from(other));
+        narrow(add_wide(a.x*b.x, a.y*b.y) >> 12)
+    }
+
+    /// Euclidean length, saturating at [`IFixed::MAX`]
+    #[must_use]
+    pub fn length(self) -> IFixed {
+        let v = Vec2Mul::from(self);
+        sqrt((v.x*v.x) as u32 + (v.y*v.y) as u32)
+    }
+
+    /// Interpolate toward `other` by `t`, from `self` at 0 to `other` at
+    /// `0x1000`
+    /// 
+    /// The result overflows if `t` is outside that range and takes it outside
+    /// the range of [`IFixed`].
+    #[must_use]
+    pub fn lerp(self, other: Self, t: IFixed) -> Self {
+        vec2!(lerp(self.x, other.x, t), lerp(self.y, other.y, t))
+    }
+}
+
+impl Vec2<Screen> {
+
+    /// The center of the pixel, in sixteenths of a pixel
+    #[must_use]
+    pub fn to_subpixels(self) -> Vec2<SubPixel> {
+        vec2!(self.x << SUBPIXEL_BITS, self.y << SUBPIXEL_BITS)
+    }
+}
+
+impl Vec2<SubPixel> {
+
+    /// The pixel the point is nearest to the center of
+    #[must_use]
+    pub fn to_pixels(self) -> Vec2 {
+        let round = |c: IFixed| ((c as i32 + (1 << (SUBPIXEL_BITS - 1))) >> SUBPIXEL_BITS) as IFixed;
+        vec2!(round(self.x), round(self.y))
+    }
+}
+
+impl<U> Add for Vec2<U> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        vec2!(add(self.x, other.x), add(self.y, other.y))
+    }
+}
+
+impl<U> Sub for Vec2<U> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        vec2!(sub(self.x, rhs.x), sub(self.y, rhs.y))
+    }
+}
+
+impl<U: Copy> AddAssign for Vec2<U> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<U> Neg for Vec2<U> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        vec2!(sub(0, self.x), sub(0, self.y))
+    }
+}
+
+/// Scale by a fixed-point factor, rounding to the nearest
+impl<U> Mul<IFixed> for Vec2<U> {
+    type Output = Self;
+
+    fn mul(self, rhs: IFixed) -> Self {
+        vec2!(mul_round(self.x, rhs), mul_round(self.y, rhs))
+    }
+}
+
+/// 3D vector type of [`IFixed`]
+/// 
+/// Points in world units by default, see [`units`](crate::units).
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Vec3<U = World> {
+    pub x: IFixed,
+    pub y: IFixed,
+    pub z: IFixed,
+    unit: PhantomData<U>,
+}
+
+/// Convenience macro for creating vectors via `vec3!(x, y, z)`
+/// 
+/// The unit of the vector is taken from where it is used.
+#[macro_export]
+macro_rules! vec3 {
+    ($x:expr, $y:expr, $z:expr) => {
+        $crate::vec::Vec3::new($x, $y, $z)
+    }
+}
+
+impl<U> Vec3<U> {
+
+    /// Vector of three components, as [`vec3!`] creates
+    pub const fn new(x: IFixed, y: IFixed, z: IFixed) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            unit: PhantomData,
+        }
+    }
+
+    /// Move by an offset
+    /// 
+    /// This is a `const fn` so that the placement of a static part of a mesh
+    /// is baked into its vertices at compile time, while the mesh source keeps
+    /// each part in its own local coordinates.
+    #[must_use]
+    pub const fn translate(self, by: Self) -> Self {
+        vec3!(add(self.x, by.x), add(self.y, by.y), add(self.z, by.z))
+    }
+
+    /// Components widened for multiplication
+    fn widen(self) -> [IFixedMul; 3] {
+        [self.x, self.y, self.z].map(IFixedMul::from)
+    }
+
+    /// Fixed-point dot product
+    /// 
+    /// Rounds toward negative infinity as [`Mat3::apply`] does. The result
+    /// overflows if it falls outside the range of [`IFixed`], which it cannot
+    /// for two vectors of length 2 or less.
+    #[must_use]
+    pub fn dot(self, other: Self) -> IFixed {
+        dot(self.widen(), other.widen())
+    }
+
+    /// Fixed-point cross product
+    /// 
+    /// Rounds toward negative infinity like [`Vec3::dot`], and overflows the
+    /// same way.
+    #[must_use]
+    pub fn cross(self, other: Self) -> Self {
+        let (a, b) = (self.widen(), other.widen());
+        vec3!(
+            narrow(sub_wide(a[1]*b[2], a[2]*b[1]) >> 12),
+            narrow(sub_wide(a[2]*b[0], a[0]*b[2]) >> 12),
+            narrow(sub_wide(a[0]*b[1], a[1]*b[0]) >> 12)
+        )
+    }
+
+    /// Euclidean length, saturating at [`IFixed::MAX`]
+    /// 
+    /// A vector can be longer than any [`IFixed`], up to about 13.9 with
+    /// every component at -8.
+    #[must_use]
+    pub fn length(self) -> IFixed {
+        let squares = self.widen().map(|c| (c*c) as u32);
+        sqrt(squares[0] + squares[1] + squares[2])
+    }
+
+    /// Interpolate toward `other` by `t`, from `self` at 0 to `other` at
+    /// `0x1000`
+    /// 
+    /// The result overflows if `t` is outside that range and takes it outside
+    /// the range of [`IFixed`].
+    #[must_use]
+    pub fn lerp(self, other: Self, t: IFixed) -> Self {
+        vec3!(
+            lerp(self.x, other.x, t),
+            lerp(self.y, other.y, t),
+            lerp(self.z, other.z, t)
+        )
+    }
+}
+
+/// Dot product of two widened vectors of any units
+fn dot(a: [IFixedMul; 3], b: [IFixedMul; 3]) -> IFixed {
+    narrow(add_wide(add_wide(a[0]*b[0], a[1]*b[1]), a[2]*b[2]) >> 12)
+}
+
+impl<U> Add for Vec3<U> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.translate(other)
+    }
+}
+
+impl<U> Sub for Vec3<U> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        vec3!(sub(self.x, rhs.x), sub(self.y, rhs.y), sub(self.z, rhs.z))
+    }
+}
+
+impl<U: Copy> AddAssign for Vec3<U> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<U> Neg for Vec3<U> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        vec3!(sub(0, self.x), sub(0, self.y), sub(0, self.z))
+    }
+}
+
+/// Scale by a fixed-point factor, rounding to the nearest
+impl<U> Mul<IFixed> for Vec3<U> {
+    type Output = Self;
+
+    fn mul(self, rhs: IFixed) -> Self {
+        vec3!(
+            mul_round(self.x, rhs),
+            mul_round(self.y, rhs),
+            mul_round(self.z, rhs)
+        )
+    }
+}
+
+/// 3x3 rotation matrix of [`IFixed`]
+/// 
+/// Built once per frame from three [`Rotor`]s, after which rotating a
+/// vertex about all three axes takes nine multiplications, fewer than
+/// applying the three rotors one after another.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Mat3 {
+    rows: [Vec3<Raw>; 3],
+}
+
+impl Mat3 {
+
+    /// Rotation by yaw about the Y axis, then pitch about the X axis, then
+    /// roll about the Z axis
+    pub fn from_rotors(yaw: Rotor, pitch: Rotor, roll: Rotor) -> Self {
+        let (yaw, pitch, roll) = (yaw.as_vec2(), pitch.as_vec2(), roll.as_vec2());
+        let yaw = Self {
+            rows: [
+                vec3!(yaw.x, 0, -yaw.y),
+                vec3!(0, 0x1000, 0),
+                vec3!(yaw.y, 0, yaw.x),
+            ],
+        };
+        let pitch = Self {
+            rows: [
+                vec3!(0x1000, 0, 0),
+                vec3!(0, pitch.x, -pitch.y),
+                vec3!(0, pitch.y, pitch.x),
+            ],
+        };
+        let roll = Self {
+            rows: [
+                vec3!(roll.x, -roll.y, 0),
+                vec3!(roll.y, roll.x, 0),
+                vec3!(0, 0, 0x1000),
+            ],
+        };
+        roll * pitch * yaw
+    }
+
+    /// Rotate a vector
+    #[must_use]
+    pub fn apply<U>(&self, v: Vec3<U>) -> Vec3<U> {
+        let v = v.widen();
+        vec3!(
+            dot(self.rows[0].widen(), v),
+            dot(self.rows[1].widen(), v),
+            dot(self.rows[2].widen(), v)
+        )
+    }
+
+    /// Column of the matrix as a vector
+    fn column(&self, i: usize) -> Vec3<Raw> {
+        let [x, y, z] = self.rows.map(|row| [row.x, row.y, row.z][i]);
+        vec3!(x, y, z)
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let columns = [rhs.column(0), rhs.column(1), rhs.column(2)];
+        Self {
+            rows: self.rows.map(|row| {
+                vec3!(row.dot(columns[0]), row.dot(columns[1]), row.dot(columns[2]))
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: IFixed = IFixed::MIN;
+    const MAX: IFixed = IFixed::MAX;
+
+    /// What an overflowing result `exact`, as if computed in 64 bits, becomes
+    /// with the features enabled
+    /// 
+    /// With `overflow-checks` the operation panics before this is reached.
+    fn overflowed(exact: i64) -> IFixed {
+        if cfg!(feature = "saturating") {
+            exact.clamp(MIN as i64, MAX as i64) as IFixed
+        } else {
+            exact as IFixed
+        }
+    }
+
+    /// Fixed-point product of `a` and `b`, as if computed in 64 bits
+    fn product(a: IFixed, b: IFixed) -> i64 {
+        a as i64*b as i64
+    }
+
+    #[test]
+    fn in_range_results_are_exact() {
+        let v: Vec3 = vec3!(0x1000, -0x2000, 0x0800);
+        assert_eq!((v*0x0800).x, 0x0800);
+        assert_eq!(v.dot(vec3!(0x1000, 0x1000, 0x1000)), -0x0800);
+        assert!(v.cross(v) == vec3!(0, 0, 0));
+        assert_eq!(Vec2::<Screen>::new(0x3000, 0x4000).length(), 0x5000);
+        assert!(-v == vec3!(-0x1000, 0x2000, -0x0800));
+
+        // Ties round toward positive infinity
+        assert_eq!(mul_round(1, 0x0800), 1);
+        assert_eq!(mul_round(-1, 0x0800), 0);
+    }
+
+    #[test]
+    fn lengths_saturate_in_every_mode() {
+        let corner: Vec3 = vec3!(MIN, MIN, MIN);
+        assert_eq!(corner.length(), MAX);
+        assert_eq!(Vec2::<Screen>::new(MIN, MIN).length(), MAX);
+        assert_eq!(sqrt(u32::MAX), MAX);
+        assert_eq!(sqrt((MAX as u32)*(MAX as u32)), MAX);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "overflow-checks", should_panic(expected = "fixed-point overflow"))]
+    fn mul_round_overflows() {
+        assert_eq!(mul_round(MIN, MIN), overflowed(product(MIN, MIN) >> 12));
+        assert_eq!(mul_round(MAX, MIN), overflowed((product(MAX, MIN) + 0x800) >> 12));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "overflow-checks", should_panic(expected = "fixed-point overflow"))]
+    fn negating_min_overflows() {
+        let v: Vec2 = -vec2!(MIN, 0);
+        assert_eq!(v.x, overflowed(-(MIN as i64)));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "overflow-checks", should_panic(expected = "fixed-point overflow"))]
+    fn vec2_dot_overflows_past_the_widened_range() {
+        let v: Vec2 = vec2!(MIN, MIN);
+        assert_eq!(v.dot(v), overflowed((2*product(MIN, MIN)) >> 12));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "overflow-checks", should_panic(expected = "fixed-point overflow"))]
+    fn vec3_dot_overflows_past_the_widened_range() {
+        let (a, b): (Vec3, Vec3) = (vec3!(MIN, MIN, MAX), vec3!(MIN, MIN, MIN));
+        let exact = 2*product(MIN, MIN) + product(MAX, MIN);
+        assert_eq!(a.dot(b), overflowed(exact >> 12));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "overflow-checks", should_panic(expected = "fixed-point overflow"))]
+    fn cross_overflows_past_the_widened_range() {
+        let (a, b): (Vec3, Vec3) = (vec3!(MIN, MIN, 0), vec3!(MAX, MIN, 0));
+        let exact = product(MIN, MIN) - product(MIN, MAX);
+        assert_eq!(a.cross(b).z, overflowed(exact >> 12));
+    }
+}
+
+#[cfg(feature = "ufmt")]
+mod serial {
+    use ufmt::{uDebug, uDisplay, uWrite, uwrite, Formatter};
+    use crate::numfmt::{fmt_fixed, FIXED_STR_LEN};
+    use super::*;
+
+    /// Write a fixed-point value as a decimal fraction
+    fn write_fixed<W>(f: &mut Formatter<'_, W>, value: IFixed) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        let mut buf = [0; FIXED_STR_LEN];
+        f.write_str(fmt_fixed(value, &mut buf))
+    }
+
+    /// Write a fixed-point value in both raw and decimal form, e.g. `0x1000 (1.0)`
+    fn write_fixed_raw<W>(f: &mut Formatter<'_, W>, value: IFixed) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        uwrite!(f, "{:#x} (", value)?;
+        write_fixed(f, value)?;
+        f.write_str(")")
+    }
+
+    /// Prints as `(1.0, -2.125)`
+    impl<U> uDisplay for Vec2<U> {
+        fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+        where
+            W: uWrite + ?Sized,
+        {
+            f.write_str("(")?;
+            write_fixed(f, self.x)?;
+            f.write_str(", ")?;
+            write_fixed(f, self.y)?;
+            f.write_str(")")
+        }
+    }
+
+    /// Prints as `Vec2 { x: 0x1000 (1.0), y: 0xde00 (-2.125) }`
+    impl<U> uDebug for Vec2<U> {
+        fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+        where
+            W: uWrite + ?Sized,
+        {
+            f.write_str("Vec2 { x: ")?;
+            write_fixed_raw(f, self.x)?;
+            f.write_str(", y: ")?;
+            write_fixed_raw(f, self.y)?;
+            f.write_str(" }")
+        }
+    }
+
+    /// Prints as `(1.0, -2.125, 0.5)`
+    impl<U> uDisplay for Vec3<U> {
+        fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+        where
+            W: uWrite + ?Sized,
+        {
+            f.write_str("(")?;
+            write_fixed(f, self.x)?;
+            f.write_str(", ")?;
+            write_fixed(f, self.y)?;
+            f.write_str(", ")?;
+            write_fixed(f, self.z)?;
+            f.write_str(")")
+        }
+    }
+
+    /// Prints as `Vec3 { x: 0x1000 (1.0), y: 0xde00 (-2.125), z: 0x800 (0.5) }`
+    impl<U> uDebug for Vec3<U> {
+        fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+        where
+            W: uWrite + ?Sized,
+        {
+            f.write_str("Vec3 { x: ")?;
+            write_fixed_raw(f, self.x)?;
+            f.write_str(", y: ")?;
+            write_fixed_raw(f, self.y)?;
+            f.write_str(", z: ")?;
+            write_fixed_raw(f, self.z)?;
+            f.write_str(" }")
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Lock-step comparison of the board against the simulator
+//! 
+//! With `--diverge`, the simulator runs headless alongside the board and
+//! prints the checksum of every frame it draws. Each checksum the board
+//! reports with the `frame-crc` feature is compared against the simulator's
+//! frame of the same number, so the first frame the two draw differently,
+//! from platform-specific math or a timing bug, is found without looking.
+//! 
+//! The simulator draws the house alone, stepping its animation once per
+//! frame, so the firmware must be built without `frame-pacing`, whose
+//! captures are replayed with the simulator's `--replay` instead, and without
+//! features that change the picture, such as `reveal`, `morph`, `keyframes`,
+//! `radar`, `filled`, `background`, `pixel-shift` or `fps-overlay`, and with
+//! the same projection. A firmware built with `subpixel` needs a simulator built with
+//! it too.
+
+use std::io::{BufRead, BufReader, Lines};
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+use crate::fail;
+use crate::telemetry::Line;
+
+/// Simulator drawing frames for comparison
+pub struct Divergence {
+    sim: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+
+    /// Display size given to the simulator, if not its default
+    size: Option<String>,
+
+    /// Number of the next frame the simulator will report
+    next: u32,
+}
+
+impl Divergence {
+
+    /// Build and start the simulator, with the display size of the board
+    pub fn spawn(size: Option<String>) -> Self {
+        let mut command = Command::new("cargo");
+        command
+            .current_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(".."))
+            .args(["run", "--release", "-q", "-p", "uhouse-sim", "--"])
+            .args(["--headless", "--crc", "--fps", "0"]);
+        if let Some(size) = &size {
+            command.args(["--size", size]);
+        }
+        let mut sim = command
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|e| fail(&format!("cannot run the simulator: {}", e)));
+        let lines = BufReader::new(sim.stdout.take().unwrap()).lines();
+        Self { sim, lines, size, next: 0 }
+    }
+
+    /// Compare the checksum the board reported for a frame against the
+    /// simulator's, returning the simulator's if they differ
+    /// 
+    /// The board numbers frames from power on, so a number lower than the
+    /// last means it was reset, and the simulator is started over with it.
+    pub fn check(&mut self, frame: u32, crc: u16) -> Result<(), u16> {
+        if frame < self.next {
+            *self = Self::spawn(self.size.take());
+        }
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => fail(&format!("cannot read the simulator: {}", e)),
+                None => fail("the simulator stopped"),
+            };
+            if let Line::FrameCrc { frame: sim_frame, crc: sim_crc, .. } = Line::parse(&line) {
+                self.next = sim_frame + 1;
+                if sim_frame == frame {
+                    return if sim_crc == crc { Ok(()) } else { Err(sim_crc) };
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Divergence {
+    fn drop(&mut self) {
+        let _ = self.sim.kill();
+        let _ = self.sim.wait();
+    }
+}
@@ -0,0 +1,213 @@
+//! # μHouse-rs runner
+//! 
+//! Builds the firmware, flashes it and then follows its serial output, so
+//! measuring a change on the board is one command:
+//! 
+//! ```text
+//! cargo run -p uhouse-run -- [OPTIONS] [CARGO BUILD ARGUMENTS...]
+//! ```
+//! 
+//! Arguments not listed below are passed on to `cargo build --release` in
+//! `uhouse/`, such as `--features profile`. Frame rates from the `fps`
+//! feature, stage timings from the `profile` feature and interrupt handler
+//! timings from the `isr-budget` feature are printed as tables, benchmarks
+//! of the `remote` feature as frame times, and every other line as it is.
+//! 
+//! | Option          | Effect                                               |
+//! |-----------------|------------------------------------------------------|
+//! | `--port PORT`   | Serial port, or a file of output captured earlier    |
+//! | `--avrdude`     | Flash with `avrdude` instead of `ravedude`           |
+//! | `--no-flash`    | Only follow the serial output of the running board   |
+//! | `--raw`         | Print serial output without decoding it              |
+//! | `--diverge`     | Compare frame checksums against the simulator        |
+//! | `--size WxH`    | Display size for the simulator, as in the firmware   |
+//! 
+//! With `--diverge`, build the firmware with `--features frame-crc`. Every
+//! frame checksum it reports is checked against the simulator, see
+//! [`diverge`], and the run stops at the first frame that differs.
+//! 
+//! A firmware also built with `frame-pacing` steps the animation by how long
+//! each frame took, which the simulator cannot follow as it runs. Capture its
+//! output with `--raw` into a file instead, such as with `--raw > capture`,
+//! and replay it with the simulator's `--replay capture`.
+//! 
+//! The port defaults to `$RAVEDUDE_PORT`, as for `ravedude`. It is set up
+//! with `stty`, so this runs on Linux and macOS.
+
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{exit, Command};
+
+mod diverge;
+mod telemetry;
+
+use diverge::Divergence;
+use telemetry::{Line, Printer};
+
+/// Pick the serial port used when neither `--port` nor `$RAVEDUDE_PORT` is
+/// given here
+const DEFAULT_PORT: &str = "/dev/ttyACM0";
+
+/// Pick the baud rate here, matching the firmware's `default_serial!`
+const BAUD: u32 = 57600;
+
+/// Pick the board clock frequency here, matching `CLOCK_FREQ` of the
+/// firmware
+const CLOCK_FREQ: u32 = 16_000_000;
+
+/// Pick how often `--diverge` reports progress here, in frames
+const DIVERGE_REPORT_FRAMES: u32 = 100;
+
+/// Firmware image built by `cargo build --release` in the firmware directory
+const ELF: &str = "target/avr-atmega328p/release/uhouse.elf";
+
+/// How to flash the board
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Flasher {
+    Ravedude,
+    Avrdude,
+    None,
+}
+
+struct Options {
+    port: String,
+    flasher: Flasher,
+    raw: bool,
+    diverge: bool,
+
+    /// Display size passed on to the simulator
+    size: Option<String>,
+
+    /// Arguments passed on to `cargo build`
+    build_args: Vec<String>,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let mut options = Options {
+            port: env::var("RAVEDUDE_PORT").unwrap_or_else(|_| DEFAULT_PORT.to_string()),
+            flasher: Flasher::Ravedude,
+            raw: false,
+            diverge: false,
+            size: None,
+            build_args: Vec::new(),
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--port" => match args.next() {
+                    Some(port) => options.port = port,
+                    None => fail("--port needs a serial port"),
+                },
+                "--avrdude" => options.flasher = Flasher::Avrdude,
+                "--no-flash" => options.flasher = Flasher::None,
+                "--raw" => options.raw = true,
+                "--diverge" => options.diverge = true,
+                "--size" => match args.next() {
+                    Some(size) => options.size = Some(size),
+                    None => fail("--size needs a display size such as 128x64"),
+                },
+                _ => options.build_args.push(arg),
+            }
+        }
+        if options.raw && options.diverge {
+            fail("--diverge decodes serial output and cannot be combined with --raw");
+        }
+        options
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("uhouse-run: {}", message);
+    exit(1)
+}
+
+/// Run a command to completion, failing if it does not succeed
+fn run(command: &mut Command) {
+    let name = command.get_program().to_string_lossy().into_owned();
+    match command.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => fail(&format!("{} failed with {}", name, status)),
+        Err(e) => fail(&format!("cannot run {}: {}", name, e)),
+    }
+}
+
+/// Directory of the firmware crate
+fn firmware_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../uhouse")
+}
+
+/// Build the firmware and write it to the board
+fn flash(options: &Options) {
+    let dir = firmware_dir();
+    run(Command::new("cargo").current_dir(&dir).args(["build", "--release"]).args(&options.build_args));
+
+    let elf = dir.join(ELF);
+    match options.flasher {
+        Flasher::Ravedude => run(Command::new("ravedude").args(["uno", "-P", &options.port]).arg(&elf)),
+        Flasher::Avrdude => {
+            let image = format!("flash:w:{}:e", elf.display());
+            run(Command::new("avrdude").args(["-p", "atmega328p", "-c", "arduino", "-P", &options.port, "-D", "-U", &image]));
+        }
+        Flasher::None => {}
+    }
+}
+
+/// Open the serial port at [`BAUD`] without any line processing
+/// 
+/// A regular file is opened as it is, to decode output captured earlier.
+fn open_port(port: &str) -> File {
+    if !Path::new(port).is_file() {
+        let device_flag = if cfg!(target_os = "macos") { "-f" } else { "-F" };
+        run(Command::new("stty").args([device_flag, port, &BAUD.to_string(), "raw", "-echo"]));
+    }
+    File::open(port).unwrap_or_else(|e| fail(&format!("cannot open {}: {}", port, e)))
+}
+
+fn main() {
+    let options = Options::from_args();
+    if options.flasher != Flasher::None {
+        flash(&options);
+    }
+
+    let mut divergence = options.diverge.then(|| Divergence::spawn(options.size.clone()));
+    let mut serial = BufReader::new(open_port(&options.port));
+    let mut printer = Printer::default();
+    let mut matched = 0;
+    let mut bytes = Vec::new();
+    loop {
+        bytes.clear();
+        match serial.read_until(b'\n', &mut bytes) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => fail(&format!("cannot read {}: {}", options.port, e)),
+        }
+        let text = String::from_utf8_lossy(&bytes);
+        let line = text.trim_end();
+        if options.raw {
+            println!("{}", line);
+            continue;
+        }
+        match (Line::parse(line), &mut divergence) {
+            (Line::FrameCrc { frame, steps, .. }, Some(_)) if steps != 1 => fail(&format!(
+                "frame {} advanced {} animation steps; capture with --raw and replay it with the simulator's --replay",
+                frame, steps
+            )),
+            (Line::FrameCrc { frame, crc, .. }, Some(divergence)) => match divergence.check(frame, crc) {
+                Ok(()) => {
+                    matched += 1;
+                    if matched % DIVERGE_REPORT_FRAMES == 0 {
+                        println!("{} frames match, up to frame {}", matched, frame);
+                    }
+                }
+                Err(sim_crc) => {
+                    println!("frame {} differs: board {:#06x}, simulator {:#06x}", frame, crc, sim_crc);
+                    exit(1);
+                }
+            },
+            (line, _) => printer.print(line),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Parsing and printing of the firmware's serial output
+//! 
+//! The firmware prints one report per line, see `uhouse/src/profile.rs`,
+//! `uhouse/src/isr.rs`, `uhouse/src/remote.rs` and `uhouse/src/lib.rs`. Lines are recognized by
+//! their English text, so with the `lang-de` feature, or for anything else,
+//! they are passed through as they are.
+
+use crate::CLOCK_FREQ;
+
+/// Stages of the frame in the order the profiler reports them
+const SECTIONS: [&str; 3] = ["transform", "draw", "flush"];
+
+/// One line of serial output
+#[derive(Debug, PartialEq, Eq)]
+pub enum Line {
+    /// Frames counted over the last second, from the `fps` feature, or
+    /// starting a report of the `profile` feature
+    Fps(u16),
+
+    /// Average cycles a stage of the frame took per frame over the last
+    /// report, and the frames it went over its budget in, from the `profile`
+    /// feature
+    Section { name: String, cycles: u32, overruns: u8 },
+
+    /// Frames that went over the whole frame budget, ending a profiler report
+    FrameOverruns(u8),
+
+    /// Longest run of an interrupt handler over the last report, and the
+    /// runs that went over its budget, from the `isr-budget` feature
+    Isr { name: String, cycles: u16, overruns: u16 },
+
+    /// Checksum of a frame and the animation steps it advanced by, from the
+    /// `frame-crc` feature or the simulator's `--crc` option
+    FrameCrc { frame: u32, crc: u16, steps: u8 },
+
+    /// Cycles a number of frames took together, from the `t` command of the
+    /// `remote` feature
+    Bench { frames: u16, cycles: u32 },
+
+    /// Anything else
+    Other(String),
+}
+
+impl Line {
+    pub fn parse(line: &str) -> Self {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [fps] => match fps.parse() {
+                Ok(fps) => Line::Fps(fps),
+                Err(_) => Line::Other(line.to_string()),
+            },
+            ["frame", "over", overruns] => match overruns.parse() {
+                Ok(overruns) => Line::FrameOverruns(overruns),
+                Err(_) => Line::Other(line.to_string()),
+            },
+            ["crc", frame, crc, steps] => {
+                let crc = crc.strip_prefix("0x").and_then(|crc| u16::from_str_radix(crc, 16).ok());
+                match (frame.parse(), crc, steps.parse()) {
+                    (Ok(frame), Some(crc), Ok(steps)) => Line::FrameCrc { frame, crc, steps },
+                    _ => Line::Other(line.to_string()),
+                }
+            }
+            ["bench", frames, cycles] => match (frames.parse(), cycles.parse()) {
+                (Ok(frames), Ok(cycles)) => Line::Bench { frames, cycles },
+                _ => Line::Other(line.to_string()),
+            },
+            ["isr", name, cycles, "over", overruns] => match (cycles.parse(), overruns.parse()) {
+                (Ok(cycles), Ok(overruns)) => Line::Isr {
+                    name: name.to_string(),
+                    cycles,
+                    overruns,
+                },
+                _ => Line::Other(line.to_string()),
+            },
+            [name, cycles, "over", overruns] if SECTIONS.contains(&name) => {
+                match (cycles.parse(), overruns.parse()) {
+                    (Ok(cycles), Ok(overruns)) => Line::Section {
+                        name: name.to_string(),
+                        cycles,
+                        overruns,
+                    },
+                    _ => Line::Other(line.to_string()),
+                }
+            }
+            _ => Line::Other(line.to_string()),
+        }
+    }
+}
+
+/// Collects profiler lines into one table per report
+#[derive(Default)]
+pub struct Printer {
+    /// Stages reported so far in the current report
+    sections: Vec<(String, u32, u8)>,
+
+    /// Frames counted over the last second
+    fps: u16,
+}
+
+impl Printer {
+    /// Print a line, or hold it back until its report is complete
+    pub fn print(&mut self, line: Line) {
+        match line {
+            Line::Fps(fps) => {
+                self.fps = fps;
+                let frame_ms = if fps == 0 { f32::INFINITY } else { 1000.0 / fps as f32 };
+                println!("fps {:>4}  frame {:>6.1} ms", fps, frame_ms);
+            }
+            Line::Section { name, cycles, overruns } => {
+                self.sections.push((name, cycles, overruns));
+            }
+            Line::FrameOverruns(overruns) => self.print_report(overruns),
+            Line::Isr { name, cycles, overruns } => {
+                let us = cycles as f64 * 1e6 / CLOCK_FREQ as f64;
+                let flag = if overruns > 0 { "  over budget" } else { "" };
+                println!("isr {:<8} {:>6} cycles {:>7.1} us {:>5} over{}", name, cycles, us, overruns, flag);
+            }
+            Line::FrameCrc { frame, crc, steps } => {
+                println!("frame {:>6}  crc {:#06x}  steps {}", frame, crc, steps)
+            }
+            Line::Bench { frames, cycles } => {
+                let ms = cycles as f64 * 1000.0 / CLOCK_FREQ as f64 / frames.max(1) as f64;
+                println!("bench {:>4} frames {:>8.1} ms/frame {:>6.1} fps", frames, ms, 1000.0 / ms);
+            }
+            Line::Other(line) => println!("{}", line),
+        }
+    }
+
+    /// Print the stages of a report as a table of time spent per frame,
+    /// and their share of the second at the frame rate counted with it
+    fn print_report(&mut self, frame_overruns: u8) {
+        let total: u32 = self.sections.iter().map(|&(_, cycles, _)| cycles).sum();
+        println!("{:<10} {:>12} {:>9} {:>7} {:>5}", "stage", "cycles/frame", "ms/frame", "cpu", "over");
+        for (name, cycles, overruns) in self.sections.drain(..) {
+            print_row(&name, cycles, self.fps, overruns);
+        }
+        print_row("total", total, self.fps, frame_overruns);
+        println!();
+    }
+}
+
+fn print_row(name: &str, cycles: u32, fps: u16, overruns: u8) {
+    let ms = cycles as f64 * 1000.0 / CLOCK_FREQ as f64;
+    let cpu = cycles as f64 * fps as f64 * 100.0 / CLOCK_FREQ as f64;
+    println!("{:<10} {:>12} {:>9.1} {:>6.1}% {:>5}", name, cycles, ms, cpu, overruns);
+}
@@ -0,0 +1,94 @@
+//! Display contents in memory
+//! 
+//! Every frame is drawn into a [`Canvas`] first, which is then shown in the
+//! window, written out or both, so a headless run draws exactly what the
+//! window would show.
+
+use std::convert::Infallible;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use uhouse_core::validate::crc16_update;
+
+/// One bit per pixel of the simulated display, row after row from the top
+#[derive(Clone)]
+pub struct Canvas {
+    size: Size,
+    pixels: Vec<bool>,
+}
+
+impl Canvas {
+    pub fn new(size: Size) -> Self {
+        Self {
+            size,
+            pixels: vec![false; (size.width*size.height) as usize],
+        }
+    }
+
+    /// Turn every pixel off
+    pub fn clear_all(&mut self) {
+        self.pixels.fill(false);
+    }
+
+    /// Rows of pixels from the top, each from the left, `true` being on
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> {
+        self.pixels.chunks(self.size.width as usize)
+    }
+
+    /// Whether the pixel at column `x` and row `y` is on, with every pixel
+    /// off the canvas off
+    pub fn is_on(&self, x: i32, y: i32) -> bool {
+        let (width, height) = (self.size.width as i32, self.size.height as i32);
+        (0..width).contains(&x) && (0..height).contains(&y) && self.pixels[(y*width + x) as usize]
+    }
+
+    /// CRC-16/CCITT-FALSE checksum of the pixels as the firmware's
+    /// framebuffer holds them
+    /// 
+    /// Bytes are hashed in SSD1306 display order, each a column of eight
+    /// pixels with the top one in the least significant bit, page by page
+    /// from the top, so this matches the checksums of the `frame-crc`
+    /// feature.
+    pub fn crc(&self) -> u16 {
+        let mut crc = 0xffff;
+        for page in (0..self.size.height as i32).step_by(8) {
+            for x in 0..self.size.width as i32 {
+                let byte = (0..8).fold(0, |byte, bit| byte | (self.is_on(x, page + bit) as u8) << bit);
+                crc = crc16_update(crc, byte);
+            }
+        }
+        crc
+    }
+
+    /// Every pixel, to be drawn on another target
+    pub fn pixels(&self) -> impl Iterator<Item = Pixel<BinaryColor>> + '_ {
+        let width = self.size.width as usize;
+        self.pixels.iter().enumerate().map(move |(i, &on)| {
+            let at = Point::new((i % width) as i32, (i / width) as i32);
+            Pixel(at, if on { BinaryColor::On } else { BinaryColor::Off })
+        })
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(at, color) in pixels {
+            let (width, height) = (self.size.width as i32, self.size.height as i32);
+            if (0..width).contains(&at.x) && (0..height).contains(&at.y) {
+                self.pixels[(at.y*width + at.x) as usize] = color.is_on();
+            }
+        }
+        Ok(())
+    }
+}
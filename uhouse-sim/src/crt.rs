@@ -0,0 +1,79 @@
+//! CRT filter for the window
+//! 
+//! The window normally scales every display pixel to a hard square. With
+//! `--crt`, the simulator draws each one itself instead, closer to how the
+//! panel looks in person: lit pixels glow onto their neighbours, a dark gap
+//! is left between columns and a darker scanline between rows.
+//! 
+//! Only the window is filtered. Frames written with `--output` are the
+//! display contents as they are.
+
+use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+
+use crate::canvas::Canvas;
+
+/// Smallest zoom the filter needs, leaving room for a gap after each pixel
+pub const MIN_ZOOM: u32 = 2;
+
+/// Color of a fully lit pixel
+const PHOSPHOR: Rgb888 = Rgb888::new(0x8c, 0xd4, 0xff);
+
+/// Color of an unlit pixel
+const BLACK_LEVEL: Rgb888 = Rgb888::new(0x04, 0x08, 0x10);
+
+/// Brightness of the last column of a lit pixel, its gap to the next one
+const PIXEL_GAP: f32 = 0.3;
+
+/// Brightness of the last row of a lit pixel, its scanline
+const SCANLINE: f32 = 0.1;
+
+/// Brightness a pixel gets from each lit neighbour beside, above or below it
+/// 
+/// Diagonal neighbours give half as much.
+const GLOW: f32 = 0.15;
+
+/// Draw the canvas at `zoom` window pixels per display pixel through the
+/// filter
+pub fn draw<D>(canvas: &Canvas, zoom: u32, target: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb888>,
+{
+    let size = canvas.size();
+    let last = zoom as i32 - 1;
+    let pixels = (0..size.height as i32).flat_map(|y| (0..size.width as i32).map(move |x| (x, y)));
+    target.draw_iter(pixels.flat_map(|(x, y)| {
+        let on = canvas.is_on(x, y) as u8 as f32;
+        let glow = glow(canvas, x, y);
+        (0..zoom as i32).flat_map(move |dy| {
+            (0..zoom as i32).map(move |dx| {
+                let weight = match (dx == last, dy == last) {
+                    (_, true) => SCANLINE,
+                    (true, false) => PIXEL_GAP,
+                    (false, false) => 1.0,
+                };
+                let at = Point::new(x*zoom as i32 + dx, y*zoom as i32 + dy);
+                Pixel(at, shade(on*weight + glow))
+            })
+        })
+    }))
+}
+
+/// Light a pixel gets from its lit neighbours
+fn glow(canvas: &Canvas, x: i32, y: i32) -> f32 {
+    let lit = |dx: i32, dy: i32| canvas.is_on(x + dx, y + dy) as u8 as f32;
+    let beside = lit(-1, 0) + lit(1, 0) + lit(0, -1) + lit(0, 1);
+    let diagonal = lit(-1, -1) + lit(1, -1) + lit(-1, 1) + lit(1, 1);
+    GLOW*(beside + diagonal/2.0)
+}
+
+/// Color of a pixel at `brightness`, from [`BLACK_LEVEL`] at 0 to
+/// [`PHOSPHOR`] at 1 or more
+fn shade(brightness: f32) -> Rgb888 {
+    let t = brightness.min(1.0);
+    let mix = |black: u8, lit: u8| (black as f32 + (lit as f32 - black as f32)*t).round() as u8;
+    Rgb888::new(
+        mix(BLACK_LEVEL.r(), PHOSPHOR.r()),
+        mix(BLACK_LEVEL.g(), PHOSPHOR.g()),
+        mix(BLACK_LEVEL.b(), PHOSPHOR.b()),
+    )
+}
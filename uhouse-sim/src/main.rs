@@ -0,0 +1,269 @@
+//! # μHouse-rs simulator
+//! 
+//! Renders the house scene on the host with the same `uhouse-core` code the
+//! firmware runs, into an SDL window made to look like the SSD1306. Meshes,
+//! projections and clipping changes can be previewed here without flashing
+//! the board.
+//! 
+//! ```text
+//! cargo run -p uhouse-sim -- [OPTIONS]
+//! ```
+//! 
+//! | Option          | Effect                                                 |
+//! |-----------------|--------------------------------------------------------|
+//! | `--size WxH`    | Display size of a supported panel, such as `128x32`    |
+//! | `--zoom N`      | Window pixels per display pixel                        |
+//! | `--crt`         | Show the window through a filter that looks like the panel |
+//! | `--fps N`       | Frame rate, or 0 to run as fast as possible            |
+//! | `--frames N`    | Quit after this many frames                            |
+//! | `--headless`    | Run without a window, which needs `--frames`, `--crc` or `--replay` |
+//! | `--crc`         | Print the checksum of every frame                      |
+//! | `--output FILE` | Write the last frame to a `.pbm` file, or every frame to a `.gif` file |
+//! | `--replay FILE` | Step the animation as in serial output captured from the board |
+//! 
+//! The animation steps once per frame, however long the frame took, so a run
+//! with the same options draws the same frames every time. Nothing else
+//! varies from run to run, so there is no seed to fix. A headless run with
+//! an output file makes golden images for scripts to compare against.
+//! 
+//! `--crc` prints a line `crc <frame> <crc> <steps>` for every frame, in the
+//! same format as the firmware's `frame-crc` feature, for `uhouse-run
+//! --diverge` to compare against the board. Run headless, it draws only as
+//! fast as the lines are read.
+//! 
+//! `--replay` reads those lines from a capture of the board's serial output
+//! instead, and draws its frames with the animation stepped as the board
+//! stepped it, see [`replay`]. The first frame that differs from the board's
+//! is reported and, with a window, stays on screen until it is closed.
+//! 
+//! Close the window or press Escape to quit.
+
+use std::io::{self, Write};
+use std::process::exit;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Rgb888},
+    prelude::*,
+};
+use embedded_graphics_simulator::{
+    sdl2::Keycode, BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, SimulatorEvent,
+    Window,
+};
+use uhouse_core::clip;
+use uhouse_core::draw_target::PixelSink;
+use uhouse_core::house::HOUSE;
+use uhouse_core::mesh::ScreenVert;
+use uhouse_core::projection::Perspective;
+use uhouse_core::scene::TrackState;
+use uhouse_core::transform::{project_vert, view_transform};
+use uhouse_core::vec::*;
+use uhouse_core::vec2;
+
+mod canvas;
+mod crt;
+mod options;
+mod output;
+mod replay;
+
+use canvas::Canvas;
+use options::{fail, Options};
+use output::Output;
+
+/// Pick your projection here
+type Projection = Perspective;
+
+/// How often the window is checked for closing while it holds the frame that
+/// differs from the board's
+const HOLD_POLL: Duration = Duration::from_millis(50);
+
+/// Simulated display and the window showing it
+struct Screen {
+    display: Display,
+    window: Window,
+}
+
+/// Simulated display, showing the canvas as it is or through the
+/// [CRT filter](crt) at a zoom
+enum Display {
+    Plain(SimulatorDisplay<BinaryColor>),
+    Crt(SimulatorDisplay<Rgb888>, u32),
+}
+
+impl Screen {
+    fn new(options: &Options) -> Self {
+        let size = options.size;
+        let (display, settings) = if options.crt {
+            let display = Display::Crt(SimulatorDisplay::new(size*options.zoom), options.zoom);
+            (display, OutputSettingsBuilder::new().build())
+        } else {
+            let settings = OutputSettingsBuilder::new()
+                .theme(BinaryColorTheme::OledBlue)
+                .scale(options.zoom)
+                .build();
+            (Display::Plain(SimulatorDisplay::new(size)), settings)
+        };
+        Self {
+            display,
+            window: Window::new("μHouse-rs", &settings),
+        }
+    }
+
+    /// Show the canvas in the window, returning whether to keep running
+    fn show(&mut self, canvas: &Canvas) -> bool {
+        match &mut self.display {
+            Display::Plain(display) => {
+                display.draw_iter(canvas.pixels()).unwrap();
+                self.window.update(display);
+            }
+            Display::Crt(display, zoom) => {
+                crt::draw(canvas, *zoom, display).unwrap();
+                self.window.update(display);
+            }
+        }
+        let mut running = true;
+        for event in self.window.events() {
+            match event {
+                SimulatorEvent::Quit => running = false,
+                SimulatorEvent::KeyDown { keycode: Keycode::Escape, .. } => running = false,
+                _ => {}
+            }
+        }
+        running
+    }
+}
+
+fn main() {
+    let options = Options::from_args();
+    let size = options.size;
+    let screen_center = vec2!(size.width as IFixed >> 1, size.height as IFixed >> 1);
+
+    let mut canvas = Canvas::new(size);
+    let mut screen = (!options.headless).then(|| Screen::new(&options));
+    let mut output = options.output.as_deref().map(|path| {
+        Output::create(path, size, options.fps)
+            .unwrap_or_else(|e| fail(&format!("cannot write {}: {}", path.display(), e)))
+    });
+
+    let mut screen_verts = [ScreenVert::default(); HOUSE::NUM_VERTS];
+    let mut tracks = [TrackState::new(); 4];
+    let frame_time = (options.fps > 0).then(|| Duration::from_secs(1) / options.fps);
+
+    let replay = options.replay.as_deref().map(|path| {
+        replay::load(path).unwrap_or_else(|e| fail(&format!("cannot replay {}: {}", path.display(), e)))
+    });
+    let mut captured = replay.iter().flatten();
+    let mut differs = false;
+
+    let mut frame = 0;
+    while options.frames.is_none_or(|frames| frame < frames) {
+        let start = Instant::now();
+
+        // Frame the board drew, when replaying, and the steps it took
+        let board = match &replay {
+            Some(_) => match captured.next() {
+                Some(&board) => Some(board),
+                None => break,
+            },
+            None => None,
+        };
+        if board.is_some_and(|board| board.frame == 0) {
+            tracks = [TrackState::new(); 4];
+        }
+        let steps = board.map_or(1, |board| board.steps);
+
+        let [spin, orbit, pitch, roll] = &mut tracks;
+        for _ in 0..steps {
+            spin.advance(HOUSE::SPIN);
+            orbit.advance(HOUSE::ORBIT);
+            pitch.advance(HOUSE::PITCH);
+            roll.advance(HOUSE::ROLL);
+        }
+        let rotation = Mat3::from_rotors(spin.rotor, pitch.rotor, roll.rotor);
+        let location = orbit.rotor;
+
+        canvas.clear_all();
+
+        for mirror in HOUSE.instances() {
+            let to_view = |i: usize| {
+                let v = HOUSE.verts.load_at(i);
+                view_transform(mirror.map_or(v, |axis| axis.apply(v)), &rotation, location, HOUSE::DEPTH)
+            };
+            let to_screen = |view: Vec3| project_vert::<Projection>(view, screen_center);
+            let clip_near = |i0: usize, i1: usize| {
+                clip::clip_near(to_view(i0), to_view(i1))
+                    .map(|(v0, v1)| (to_screen(v0), to_screen(v1)))
+            };
+
+            for (i, screen) in screen_verts.iter_mut().enumerate() {
+                *screen = to_screen(to_view(i));
+            }
+
+            let mut sink = PixelSink::new(&mut canvas);
+            let screen = sink.screen();
+            let valid = HOUSE.draw(
+                &screen_verts,
+                &screen,
+                mirror,
+                clip_near,
+                |_, _, _| None,
+                |x, y| sink.put_pixel(x, y),
+            );
+            sink.finish().unwrap();
+            if !valid {
+                eprintln!("mesh indexes past its vertices");
+            }
+        }
+
+        // Stop once whoever reads the checksums has gone away
+        let crc = canvas.crc();
+        let number = board.map_or(frame, |board| board.frame as u64);
+        if options.crc && writeln!(io::stdout(), "crc {} {:#x} {}", number, crc, steps).is_err() {
+            break;
+        }
+
+        if let Some(board) = board.filter(|board| board.crc != crc) {
+            println!("frame {} differs: board {:#06x}, simulator {:#06x}", board.frame, board.crc, crc);
+            differs = true;
+        }
+
+        if let Some(output) = &mut output {
+            if let Err(e) = output.frame(&canvas) {
+                fail(&format!("cannot write frame: {}", e));
+            }
+        }
+
+        if let Some(screen) = &mut screen {
+            if !screen.show(&canvas) {
+                break;
+            }
+        }
+        if differs {
+            break;
+        }
+
+        if let Some(rest) = frame_time.and_then(|time| time.checked_sub(start.elapsed())) {
+            thread::sleep(rest);
+        }
+        frame += 1;
+    }
+
+    if let Some(output) = output {
+        if let Err(e) = output.finish() {
+            fail(&format!("cannot write output: {}", e));
+        }
+    }
+
+    if differs {
+        if let Some(screen) = &mut screen {
+            while screen.show(&canvas) {
+                thread::sleep(HOLD_POLL);
+            }
+        }
+        exit(1);
+    }
+    if replay.is_some() {
+        println!("{} frames match", frame);
+    }
+}
@@ -0,0 +1,122 @@
+//! Command line options
+
+use std::env;
+use std::path::PathBuf;
+use std::process::exit;
+
+use embedded_graphics::prelude::*;
+
+use crate::crt;
+
+/// Display sizes of the SSD1306 panels the firmware supports
+const PANEL_SIZES: [(u32, u32); 6] = [(128, 64), (128, 32), (96, 16), (72, 40), (64, 48), (64, 32)];
+
+/// Pick the simulated display size used without `--size` here
+const DEFAULT_SIZE: Size = Size::new(128, 64);
+
+/// Pick the window scale used without `--zoom` here, in window pixels per
+/// display pixel
+const DEFAULT_ZOOM: u32 = 4;
+
+/// Pick the frame rate used without `--fps` here
+const DEFAULT_FPS: u32 = 30;
+
+pub struct Options {
+    pub size: Size,
+    pub zoom: u32,
+
+    /// Show the window through the [CRT filter](crt)
+    pub crt: bool,
+
+    /// Frames per second, or 0 to run as fast as possible
+    pub fps: u32,
+
+    /// Frames to run before quitting, or none to run until the window closes
+    pub frames: Option<u64>,
+
+    /// Run without opening a window
+    pub headless: bool,
+
+    /// Print the checksum of every frame
+    pub crc: bool,
+
+    /// File to write frames to
+    pub output: Option<PathBuf>,
+
+    /// Frame checksums captured from the board to replay
+    pub replay: Option<PathBuf>,
+}
+
+impl Options {
+    pub fn from_args() -> Self {
+        let mut options = Options {
+            size: DEFAULT_SIZE,
+            zoom: DEFAULT_ZOOM,
+            crt: false,
+            fps: DEFAULT_FPS,
+            frames: None,
+            headless: false,
+            crc: false,
+            output: None,
+            replay: None,
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--size" => options.size = parse_size(args.next()),
+                "--zoom" => match args.next().and_then(|zoom| zoom.parse().ok()) {
+                    Some(zoom) if zoom > 0 => options.zoom = zoom,
+                    _ => fail("--zoom needs a scale of at least 1"),
+                },
+                "--crt" => options.crt = true,
+                "--fps" => match args.next().and_then(|fps| fps.parse().ok()) {
+                    Some(fps) => options.fps = fps,
+                    None => fail("--fps needs a frame rate, or 0 to run unpaced"),
+                },
+                "--frames" => match args.next().and_then(|frames| frames.parse().ok()) {
+                    Some(frames) => options.frames = Some(frames),
+                    None => fail("--frames needs a number of frames"),
+                },
+                "--headless" => options.headless = true,
+                "--crc" => options.crc = true,
+                "--output" => match args.next() {
+                    Some(path) => options.output = Some(path.into()),
+                    None => fail("--output needs a .pbm or .gif file"),
+                },
+                "--replay" => match args.next() {
+                    Some(path) => options.replay = Some(path.into()),
+                    None => fail("--replay needs a file of serial output captured from the board"),
+                },
+                _ => fail(&format!("unknown option {}", arg)),
+            }
+        }
+        if options.headless && options.frames.is_none() && !options.crc && options.replay.is_none() {
+            fail("--headless needs --frames, --crc or --replay");
+        }
+        if options.crt && options.zoom < crt::MIN_ZOOM {
+            fail(&format!("--crt needs a --zoom of at least {}", crt::MIN_ZOOM));
+        }
+        options
+    }
+}
+
+/// Parse a display size given as `WIDTHxHEIGHT`, which must be that of a
+/// supported panel
+fn parse_size(arg: Option<String>) -> Size {
+    let parsed = arg.as_deref().and_then(|arg| {
+        let (width, height) = arg.split_once('x')?;
+        Some((width.parse().ok()?, height.parse().ok()?))
+    });
+    match parsed {
+        Some(size) if PANEL_SIZES.contains(&size) => Size::new(size.0, size.1),
+        _ => {
+            let sizes: Vec<String> = PANEL_SIZES.iter().map(|(w, h)| format!("{}x{}", w, h)).collect();
+            fail(&format!("--size needs one of {}", sizes.join(", ")))
+        }
+    }
+}
+
+pub fn fail(message: &str) -> ! {
+    eprintln!("uhouse-sim: {}", message);
+    exit(1)
+}
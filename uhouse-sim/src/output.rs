@@ -0,0 +1,210 @@
+//! Writing frames to image files
+//! 
+//! A `.pbm` file receives the last frame as a binary Netpbm bitmap, for
+//! comparing against a golden image. A `.gif` file receives every frame as
+//! an endlessly looping animation. Pixels that are on are black in a PBM file,
+//! as is usual for bitmaps, and white in a GIF file, as on the display.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use embedded_graphics::prelude::*;
+
+use crate::canvas::Canvas;
+
+/// Colors of pixels that are off and on in a GIF file
+const GIF_PALETTE: [[u8; 3]; 2] = [[0x00, 0x00, 0x00], [0xff, 0xff, 0xff]];
+
+/// Number of bits of the pixel values of a GIF file, the smallest allowed
+const GIF_MIN_CODE_SIZE: u8 = 2;
+
+/// Largest number of codes in the LZW table of a GIF file
+const GIF_MAX_CODES: u16 = 4096;
+
+/// Where frames are written
+pub enum Output {
+    /// Last frame drawn, written to the path when finished
+    Pbm(Box<Path>, Option<Canvas>),
+
+    /// Animation written as it goes, and the delay of every frame in
+    /// hundredths of a second
+    Gif(BufWriter<File>, u16),
+}
+
+impl Output {
+
+    /// Open an output file, picking the format by its extension
+    /// 
+    /// `fps` sets the frame delay of an animation, with 0 for as fast as the
+    /// viewer can play it.
+    pub fn create(path: &Path, size: Size, fps: u32) -> io::Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("pbm") => Ok(Output::Pbm(path.into(), None)),
+            Some("gif") => {
+                let mut out = BufWriter::new(File::create(path)?);
+                write_gif_header(&mut out, size)?;
+                let delay = 100u32.checked_div(fps).map_or(0, |delay| delay.max(1) as u16);
+                Ok(Output::Gif(out, delay))
+            }
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "output must be a .pbm or .gif file")),
+        }
+    }
+
+    /// Add a frame
+    pub fn frame(&mut self, canvas: &Canvas) -> io::Result<()> {
+        match self {
+            Output::Pbm(_, last) => {
+                *last = Some(canvas.clone());
+                Ok(())
+            }
+            Output::Gif(out, delay) => write_gif_frame(out, canvas, *delay),
+        }
+    }
+
+    /// Write out whatever is left and close the file
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Pbm(path, Some(canvas)) => {
+                let mut out = BufWriter::new(File::create(path)?);
+                write_pbm(&mut out, &canvas)?;
+                out.flush()
+            }
+            Output::Pbm(_, None) => Ok(()),
+            Output::Gif(mut out, _) => {
+                out.write_all(&[0x3b])?;
+                out.flush()
+            }
+        }
+    }
+}
+
+/// Write a frame as a binary PBM file, rows packed from the top bit
+fn write_pbm<W: Write>(out: &mut W, canvas: &Canvas) -> io::Result<()> {
+    let size = canvas.size();
+    write!(out, "P4\n{} {}\n", size.width, size.height)?;
+    for row in canvas.rows() {
+        let bytes: Vec<u8> = row
+            .chunks(8)
+            .map(|pixels| {
+                pixels.iter().enumerate().fold(0, |byte, (bit, &on)| byte | (on as u8) << (7 - bit))
+            })
+            .collect();
+        out.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+/// Write the header, palette and looping extension of a GIF animation
+fn write_gif_header<W: Write>(out: &mut W, size: Size) -> io::Result<()> {
+    out.write_all(b"GIF89a")?;
+    out.write_all(&(size.width as u16).to_le_bytes())?;
+    out.write_all(&(size.height as u16).to_le_bytes())?;
+
+    // Global palette of two colors, background color 0, square pixels
+    out.write_all(&[0x80, 0, 0])?;
+    for color in GIF_PALETTE {
+        out.write_all(&color)?;
+    }
+
+    // Loop forever
+    out.write_all(&[0x21, 0xff, 11])?;
+    out.write_all(b"NETSCAPE2.0")?;
+    out.write_all(&[3, 1, 0, 0, 0])
+}
+
+/// Write one frame of a GIF animation, shown for `delay` hundredths of a
+/// second
+fn write_gif_frame<W: Write>(out: &mut W, canvas: &Canvas, delay: u16) -> io::Result<()> {
+    out.write_all(&[0x21, 0xf9, 4, 0])?;
+    out.write_all(&delay.to_le_bytes())?;
+    out.write_all(&[0, 0])?;
+
+    let size = canvas.size();
+    out.write_all(&[0x2c, 0, 0, 0, 0])?;
+    out.write_all(&(size.width as u16).to_le_bytes())?;
+    out.write_all(&(size.height as u16).to_le_bytes())?;
+    out.write_all(&[0, GIF_MIN_CODE_SIZE])?;
+
+    let data = lzw(canvas.rows().flatten().map(|&on| on as u8));
+    for block in data.chunks(255) {
+        out.write_all(&[block.len() as u8])?;
+        out.write_all(block)?;
+    }
+    out.write_all(&[0])
+}
+
+/// Compress pixel values with the variable code width LZW of GIF
+/// 
+/// The table starts over with a clear code whenever it fills up.
+fn lzw<I: Iterator<Item = u8>>(mut pixels: I) -> Vec<u8> {
+    let clear = 1u16 << GIF_MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut codes = Codes::default();
+    let mut table = HashMap::new();
+    let mut next = end + 1;
+    let mut width = GIF_MIN_CODE_SIZE + 1;
+
+    codes.push(clear, width);
+    let mut prefix = match pixels.next() {
+        Some(pixel) => pixel as u16,
+        None => {
+            codes.push(end, width);
+            return codes.finish();
+        }
+    };
+    for pixel in pixels {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+        codes.push(prefix, width);
+        if next == GIF_MAX_CODES {
+            codes.push(clear, width);
+            table.clear();
+            next = end + 1;
+            width = GIF_MIN_CODE_SIZE + 1;
+        } else {
+            table.insert((prefix, pixel), next);
+            next += 1;
+
+            // The decoder adds each code one step behind, so it widens its
+            // codes once the code after this one is out of range
+            if next > 1 << width && width < 12 {
+                width += 1;
+            }
+        }
+        prefix = pixel as u16;
+    }
+    codes.push(prefix, width);
+    codes.push(end, width);
+    codes.finish()
+}
+
+/// Codes packed from the lowest bit of each byte up
+#[derive(Default)]
+struct Codes {
+    bytes: Vec<u8>,
+    bits: u32,
+    len: u8,
+}
+
+impl Codes {
+    fn push(&mut self, code: u16, width: u8) {
+        self.bits |= (code as u32) << self.len;
+        self.len += width;
+        while self.len >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        self.bytes
+    }
+}
@@ -0,0 +1,68 @@
+//! Replay of frame checksums captured from the board
+//! 
+//! A firmware built with `frame-crc` prints a line `crc <frame> <crc>
+//! <steps>` for every frame, with the animation steps the frame advanced by.
+//! With `frame-pacing` those vary with how long each frame took, so a glitch
+//! seen on the board may depend on its timing. Replaying a capture steps the
+//! simulator's animation exactly as the board did and checks every frame
+//! against the board's checksum, stopping at the first one that differs.
+//! 
+//! Other lines in the capture, such as from a reset, are skipped, so the raw
+//! serial output can be used as it is.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Frame reported by the board
+#[derive(Copy, Clone)]
+pub struct Captured {
+    /// Number of the frame, counting from 0 at power on
+    pub frame: u32,
+
+    /// Checksum of the frame as the board drew it
+    pub crc: u16,
+
+    /// Animation steps the frame advanced by
+    pub steps: u8,
+}
+
+impl Captured {
+    fn parse(line: &str) -> Option<Self> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let ["crc", frame, crc, steps] = words[..] else {
+            return None;
+        };
+        Some(Self {
+            frame: frame.parse().ok()?,
+            crc: u16::from_str_radix(crc.strip_prefix("0x")?, 16).ok()?,
+            steps: steps.parse().ok()?,
+        })
+    }
+}
+
+/// Load the frames of a capture in the order the board drew them
+/// 
+/// Fails unless the capture starts at power on, or if frames are missing in
+/// between, as their steps are unknown. Frame numbers starting over mean the
+/// board was reset, which is replayed as such by starting the animation over.
+pub fn load(path: &Path) -> io::Result<Vec<Captured>> {
+    let text = fs::read(path)?;
+    let frames: Vec<Captured> = String::from_utf8_lossy(&text).lines().filter_map(Captured::parse).collect();
+    match frames.first() {
+        Some(first) if first.frame == 0 => {}
+        Some(first) => return Err(invalid(format!("frames 0 to {} are missing", first.frame - 1))),
+        None => return Err(invalid("no frame checksums found".to_string())),
+    }
+    for pair in frames.windows(2) {
+        let (last, next) = (pair[0].frame, pair[1].frame);
+        if next != 0 && next != last.wrapping_add(1) {
+            return Err(invalid(format!("frames {} to {} are missing", last.wrapping_add(1), next.wrapping_sub(1))));
+        }
+    }
+    Ok(frames)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
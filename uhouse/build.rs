@@ -0,0 +1,1121 @@
+//! Build script for μHouse-rs
+//! 
+//! Tallies the flash and RAM cost of every enabled subsystem against the
+//! budget of the board being built for and refuses to build a feature
+//! combination that cannot fit.
+//! 
+//! Heavyweight subsystems are independent Cargo features. When adding a new
+//! one, add a row to [`SUBSYSTEMS`] with its estimated cost so the budget
+//! stays honest.
+//! 
+//! With the `bounded` feature it also works out the longest any frame can
+//! take and refuses to build if that exceeds [`FRAME_TIME_LIMIT_MS`], see
+//! [`check_frame_time`].
+//! 
+//! Also converts every Wavefront OBJ model in [`MODELS_DIR`] into a
+//! `scene!` object, see [`import_models`], and renders a thumbnail of every
+//! scene for the scene select screen, see [`render_thumbnails`].
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fmt::Write,
+    fs,
+    path::Path,
+    process,
+};
+
+use uhouse_core::clip::Rect;
+use uhouse_core::mesh::Mirror;
+use uhouse_core::projection::Perspective;
+use uhouse_core::raster::{draw_line, Endpoints, Style};
+use uhouse_core::rotor::Rotor;
+use uhouse_core::scene::DEFAULT_DEPTH;
+use uhouse_core::transform::{project, view_transform};
+use uhouse_core::vec::*;
+use uhouse_core::{vec2, vec3};
+
+/// Budget and interrupt vectors of a board, see `src/board.rs`
+struct Board {
+    /// Cargo feature picking the board
+    feature: &'static str,
+
+    /// Target spec in `avr-specs/` the board is built with
+    target: &'static str,
+
+    /// Clock frequency, in Hz
+    clock: u32,
+
+    /// Flash available to the application, in bytes
+    flash: u32,
+
+    /// SRAM available to static data, in bytes
+    ram: u32,
+
+    /// Vector number of every interrupt in [`INTERRUPTS`], by name, where a
+    /// lower number is served first when several interrupts are pending
+    vectors: &'static [(&'static str, u8)],
+}
+
+/// Every supported board
+/// 
+/// Flash is less the bootloader, 512 bytes of Optiboot on the UNO and 8 KiB
+/// on the Mega 2560. RAM is less 256 bytes of headroom for the stack.
+const BOARDS: &[Board] = &[
+    Board {
+        feature: "board-uno",
+        target: "avr-atmega328p",
+        clock: 16_000_000,
+        flash: 32 * 1024 - 512,
+        ram: 2 * 1024 - 256,
+        vectors: &[
+            ("TIMER2_COMPA", 7),
+            ("TIMER1_OVF", 13),
+            ("TIMER0_COMPA", 14),
+            ("TWI", 24),
+        ],
+    },
+    Board {
+        feature: "board-mega2560",
+        target: "avr-atmega2560",
+        clock: 16_000_000,
+        flash: 256 * 1024 - 8 * 1024,
+        ram: 8 * 1024 - 256,
+        vectors: &[
+            ("TIMER2_COMPA", 13),
+            ("TIMER1_OVF", 20),
+            ("TIMER0_COMPA", 21),
+            ("TWI", 39),
+        ],
+    },
+];
+
+/// Estimated cost of a subsystem
+struct Subsystem {
+    /// Name shown in the budget report
+    name: &'static str,
+
+    /// Cargo feature enabling this subsystem, or `None` if always built
+    feature: Option<&'static str>,
+
+    /// Estimated flash usage in bytes
+    flash: u32,
+
+    /// Estimated static RAM usage in bytes
+    ram: u32,
+}
+
+/// Budget matrix of every subsystem
+/// 
+/// Costs are rough estimates, not measurements, so the budget only catches
+/// combinations that are far over. Refresh a row from the difference in the
+/// `avr-size` totals of `scripts/size-report.sh` with and without its
+/// feature when a subsystem changes significantly.
+const SUBSYSTEMS: &[Subsystem] = &[
+    // Renderer, mesh tables, I2C and the SSD1306 driver with its framebuffer
+    Subsystem { name: "core", feature: None, flash: 9216, ram: 1024 + 256 },
+    Subsystem { name: "fps", feature: Some("fps"), flash: 1536, ram: 16 },
+    Subsystem { name: "checked-indices", feature: Some("checked-indices"), flash: 64, ram: 0 },
+    Subsystem { name: "streaming", feature: Some("streaming"), flash: 256, ram: 0 },
+    Subsystem { name: "erase-list", feature: Some("erase-list"), flash: 192, ram: 290 },
+    Subsystem { name: "profile", feature: Some("profile"), flash: 2048, ram: 32 },
+    Subsystem { name: "isr-budget", feature: Some("isr-budget"), flash: 448, ram: 16 },
+    Subsystem { name: "screenshot", feature: Some("screenshot"), flash: 512, ram: 16 },
+    Subsystem { name: "pixel-shift", feature: Some("pixel-shift"), flash: 128, ram: 4 },
+    Subsystem { name: "clip-debug", feature: Some("clip-debug"), flash: 256, ram: 0 },
+    Subsystem { name: "saturating", feature: Some("saturating"), flash: 384, ram: 0 },
+    Subsystem { name: "overflow-checks", feature: Some("overflow-checks"), flash: 512, ram: 0 },
+    Subsystem { name: "ab-compare", feature: Some("ab-compare"), flash: 768, ram: 24 },
+    Subsystem { name: "crash-log", feature: Some("crash-log"), flash: 832, ram: 52 },
+    Subsystem { name: "remote", feature: Some("remote"), flash: 640, ram: 8 },
+    Subsystem { name: "console", feature: Some("console"), flash: 640, ram: 5 },
+    Subsystem { name: "joystick", feature: Some("joystick"), flash: 448, ram: 8 },
+    Subsystem { name: "imu", feature: Some("imu"), flash: 576, ram: 12 },
+    Subsystem { name: "panic-reset", feature: Some("panic-reset"), flash: 32, ram: 0 },
+    Subsystem { name: "panic-show", feature: Some("panic-show"), flash: 320, ram: 0 },
+    Subsystem { name: "panic-display", feature: Some("panic-display"), flash: 1024, ram: 0 },
+    Subsystem { name: "spi", feature: Some("spi"), flash: 128, ram: 0 },
+    Subsystem { name: "frame-stream", feature: Some("frame-stream"), flash: 320, ram: 0 },
+    Subsystem { name: "frame-crc", feature: Some("frame-crc"), flash: 384, ram: 4 },
+    Subsystem { name: "fps-overlay", feature: Some("fps-overlay"), flash: 768, ram: 8 },
+    Subsystem { name: "vsync", feature: Some("vsync"), flash: 192, ram: 3 },
+    Subsystem { name: "frame-pacing", feature: Some("frame-pacing"), flash: 256, ram: 8 },
+    Subsystem { name: "reveal", feature: Some("reveal"), flash: 384, ram: 102 },
+    Subsystem { name: "morph", feature: Some("morph"), flash: 576, ram: 2 },
+    Subsystem { name: "keyframes", feature: Some("keyframes"), flash: 896, ram: 2 },
+    Subsystem { name: "radar", feature: Some("radar"), flash: 704, ram: 2 },
+    Subsystem { name: "text", feature: Some("serial"), flash: 320, ram: 0 },
+    Subsystem { name: "scene-cycle", feature: Some("scene-cycle"), flash: 1792, ram: 6 },
+    Subsystem { name: "brownout", feature: Some("brownout"), flash: 512, ram: 1 },
+    Subsystem { name: "filled", feature: Some("filled"), flash: 1024, ram: 0 },
+    Subsystem { name: "scene-select", feature: Some("scene-select"), flash: 768, ram: 2 },
+    Subsystem { name: "async-flush", feature: Some("async-flush"), flash: 640, ram: 384 + 7 },
+    Subsystem { name: "bounded", feature: Some("bounded"), flash: 32, ram: 0 },
+    Subsystem { name: "sphere-cull", feature: Some("sphere-cull"), flash: 448, ram: 0 },
+    Subsystem { name: "settings", feature: Some("settings"), flash: 768, ram: 73 },
+    Subsystem { name: "subpixel", feature: Some("subpixel"), flash: 512, ram: 0 },
+    Subsystem { name: "background", feature: Some("background"), flash: 1536, ram: 0 },
+];
+
+/// Interrupt handler installed by a subsystem
+struct Interrupt {
+    /// Name of the vector, as given to `#[avr_device::interrupt]`
+    name: &'static str,
+
+    /// Cargo features any of which install the handler
+    features: &'static [&'static str],
+}
+
+/// Interrupt handlers of every subsystem
+/// 
+/// The build lists the handlers its features install in order of priority.
+/// Subsystems sharing a vector share its handler, so each vector has one row.
+/// Two handlers for the same vector define the same symbol, which the linker
+/// already rejects. When adding a subsystem with an interrupt handler, add a
+/// row here and its vector number to every board in [`BOARDS`], set up its
+/// peripheral before interrupts are enabled in `src/lib.rs` and add its
+/// feature there, and time the handler with `isr_budget!`, see `src/isr.rs`.
+const INTERRUPTS: &[Interrupt] = &[
+    Interrupt { name: "TIMER2_COMPA", features: &["vsync"] },
+    Interrupt { name: "TIMER1_OVF", features: &["fps", "fps-overlay", "profile", "remote"] },
+    Interrupt { name: "TIMER0_COMPA", features: &["frame-pacing"] },
+    Interrupt { name: "TWI", features: &["async-flush"] },
+];
+
+/// Pick the longest a frame may take here, in milliseconds, with the
+/// `bounded` feature
+/// 
+/// Frame pacing and vsync waits come on top, as they only ever wait out the
+/// rest of a frame period.
+const FRAME_TIME_LIMIT_MS: u32 = 100;
+
+/// Longest line drawn in pixels, across the widest supported panel once
+/// clipped
+const MAX_LINE_PIXELS: u32 = 128;
+
+/// Worst-case cycles a subsystem adds to a frame
+struct FrameCost {
+    /// Cargo feature enabling this subsystem, or `None` if always built
+    feature: Option<&'static str>,
+
+    /// Cycles per frame
+    frame: u32,
+
+    /// Cycles per vertex transformed
+    vertex: u32,
+
+    /// Cycles per face tested for culling
+    face: u32,
+
+    /// Cycles per segment set up and clipped
+    segment: u32,
+
+    /// Cycles per pixel plotted
+    pixel: u32,
+}
+
+/// Frame cost of every subsystem allowed with the `bounded` feature
+/// 
+/// Like [`SUBSYSTEMS`], these are estimates, taken from the `profile`
+/// feature, and should be refreshed when a hot path changes. The renderer
+/// row counts clearing the framebuffer, the four clip steps of a segment and
+/// a Bresenham step per pixel. The bus rows count flushing every page.
+const FRAME_COSTS: &[FrameCost] = &[
+    FrameCost { feature: None, frame: 6000, vertex: 2200, face: 300, segment: 3000, pixel: 44 },
+    FrameCost { feature: Some("i2c"), frame: 380_000, vertex: 0, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("spi"), frame: 26_000, vertex: 0, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("async-flush"), frame: 110_000, vertex: 0, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("erase-list"), frame: 0, vertex: 0, face: 0, segment: 0, pixel: 30 },
+    FrameCost { feature: Some("checked-indices"), frame: 0, vertex: 40, face: 0, segment: 40, pixel: 0 },
+    FrameCost { feature: Some("saturating"), frame: 0, vertex: 600, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("morph"), frame: 0, vertex: 400, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("keyframes"), frame: 0, vertex: 300, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("reveal"), frame: 0, vertex: 0, face: 0, segment: 100, pixel: 0 },
+    FrameCost { feature: Some("radar"), frame: 1000, vertex: 0, face: 0, segment: 1500, pixel: 150 },
+    FrameCost { feature: Some("pixel-shift"), frame: 200, vertex: 0, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("fps-overlay"), frame: 30_000, vertex: 0, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("joystick"), frame: 3500, vertex: 0, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("scene-cycle"), frame: 500, vertex: 0, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("sphere-cull"), frame: 16_000, vertex: 0, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("settings"), frame: 2500, vertex: 0, face: 0, segment: 0, pixel: 0 },
+    FrameCost { feature: Some("subpixel"), frame: 0, vertex: 300, face: 0, segment: 1200, pixel: 10 },
+    FrameCost { feature: Some("background"), frame: 110_000, vertex: 0, face: 0, segment: 0, pixel: 0 },
+];
+
+/// Check whether a Cargo feature is enabled for this build
+fn feature_enabled(feature: &str) -> bool {
+    let var = format!(
+        "CARGO_FEATURE_{}",
+        feature.to_uppercase().replace('-', "_")
+    );
+    env::var_os(var).is_some()
+}
+
+/// Directory holding the models to import
+const MODELS_DIR: &str = "models";
+
+/// Largest number of vertices a strip index can address
+const MAX_VERTS: usize = 256;
+
+/// Largest number of vertices in one strip, whose length shares a byte with
+/// the culled flag and the style
+/// 
+/// Mirrors `LEN` in `uhouse_core::mesh`.
+const MAX_STRIP_LEN: usize = 0x1f;
+
+/// Suffix of the file stem of a morph target, as in `car.morph.obj` for
+/// `car.obj`
+const MORPH_SUFFIX: &str = ".morph";
+
+/// Frames an imported model takes to morph into its target and back
+const MORPH_PERIOD: u16 = 480;
+
+/// Strip length flag of a culled strip
+/// 
+/// Mirrors `CULLED` in `uhouse_core::mesh`.
+const CULLED: u8 = 0x80;
+
+/// Position of the style bits of a strip length
+/// 
+/// Mirrors `STYLE_SHIFT` in `uhouse_core::mesh`.
+const STYLE_SHIFT: u8 = 5;
+
+/// Largest number of faces of a mesh
+/// 
+/// Mirrors `MAX_FACES` in `uhouse_core::mesh`.
+const MAX_FACES: usize = 16;
+
+/// Largest distance of a vertex from the Y axis, in model units
+/// 
+/// Mirrors `MAX_RADIUS` in `uhouse_core::validate`.
+const MAX_RADIUS: i32 = 0x1800;
+
+/// Largest number of faces an object can fill
+/// 
+/// Mirrors `MAX_FILLS` in `uhouse_core::fill`.
+const MAX_FILLS: usize = 16;
+
+/// Shade of filled faces facing sideways, out of 16
+const SIDE_SHADE: f64 = 8.0;
+
+/// Change in shade from faces facing sideways to faces facing straight up,
+/// lighter, or down, darker
+const LIGHT_SHADE: f64 = 6.0;
+
+/// Largest magnitude of a vertex Y coordinate, in model units
+/// 
+/// Mirrors `MAX_HEIGHT` in `uhouse_core::validate`.
+const MAX_HEIGHT: i32 = 0x1800;
+
+/// Width and height of a scene thumbnail, in pixels
+/// 
+/// Mirrors `THUMBNAIL_SIZE` in `src/select.rs`.
+const THUMBNAIL_SIZE: usize = 28;
+
+/// Bytes in a row of a thumbnail
+const THUMBNAIL_ROW_BYTES: usize = THUMBNAIL_SIZE.div_ceil(8);
+
+/// Turn of a scene about its vertical axis in its thumbnail, in degrees
+const THUMBNAIL_YAW: f64 = 30.0;
+
+/// Tilt of a scene towards the camera in its thumbnail, in degrees
+const THUMBNAIL_PITCH: f64 = 20.0;
+
+/// Tables of an object to render a thumbnail of
+struct Object {
+    /// Name of the object, and of its thumbnail
+    name: String,
+    verts: Vec<Vec3>,
+    strips: Vec<u8>,
+    faces: Vec<[u8; 3]>,
+
+    /// Axis of the mirror instance completing the object, if any
+    mirror: Option<Mirror>,
+}
+
+/// Vertices, faces and polylines as read from an OBJ file
+struct Obj {
+    /// Vertices quantized to Q12
+    verts: Vec<[i32; 3]>,
+
+    /// Faces as their vertex indices in winding order
+    faces: Vec<Vec<usize>>,
+
+    /// Segments of polylines as pairs of vertex indices
+    lines: Vec<(usize, usize)>,
+}
+
+impl Obj {
+    /// Every segment of the outlines of the faces, closing back to their
+    /// first vertex, and of the polylines
+    fn segments(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let outlines = self.faces.iter().flat_map(|face| {
+            let closing = face.last().copied().zip(face.first().copied());
+            face.windows(2).map(|w| (w[0], w[1])).chain(closing)
+        });
+        outlines.chain(self.lines.iter().copied())
+    }
+}
+
+/// A model ready to be written out as tables
+struct Model {
+    /// Vertices quantized to Q12
+    verts: Vec<[i32; 3]>,
+
+    /// Faces for backface culling as their first three vertices
+    faces: Vec<[usize; 3]>,
+
+    /// Triangles and quads to fill, with the third vertex repeated for a
+    /// triangle, and their shade
+    fills: Vec<([usize; 4], u8)>,
+
+    /// Unique edges as pairs of vertex indices, lower index first, and the
+    /// mask of the faces they border, or `None` if always drawn
+    edges: BTreeMap<(usize, usize), Option<u16>>,
+}
+
+/// Quantize an OBJ coordinate to Q12
+fn quantize(value: &str) -> Result<i32, String> {
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("bad coordinate `{}`", value))?;
+    Ok((value * 4096.0).round() as i32)
+}
+
+/// Resolve a possibly relative OBJ vertex reference such as `3`, `-1` or
+/// `3/1/2` to an index into the vertices read so far
+fn vertex_index(reference: &str, num_verts: usize) -> Result<usize, String> {
+    let index: i64 = reference
+        .split('/')
+        .next()
+        .and_then(|index| index.parse().ok())
+        .ok_or_else(|| format!("bad vertex reference `{}`", reference))?;
+    let index = if index < 0 { num_verts as i64 + index } else { index - 1 };
+    if index < 0 || index >= num_verts as i64 {
+        return Err(format!("vertex reference `{}` out of range", reference));
+    }
+    Ok(index as usize)
+}
+
+/// Parse the vertices, faces and polylines of an OBJ file
+/// 
+/// Everything else is ignored.
+fn parse_obj(source: &str) -> Result<Obj, String> {
+    let mut obj = Obj { verts: Vec::new(), faces: Vec::new(), lines: Vec::new() };
+    for (number, line) in source.lines().enumerate() {
+        let at_line = |error: String| format!("line {}: {}", number + 1, error);
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => {
+                let coords = words
+                    .take(3)
+                    .map(quantize)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(at_line)?;
+                let [x, y, z] = <[i32; 3]>::try_from(coords)
+                    .map_err(|_| at_line("vertex needs three coordinates".into()))?;
+
+                // OBJ is Y up, the renderer is Y down
+                let v = [x, -y, z];
+                if x*x + z*z > MAX_RADIUS*MAX_RADIUS || y.abs() > MAX_HEIGHT {
+                    return Err(at_line(format!(
+                        "vertex {:?} lies outside the renderable range",
+                        v
+                    )));
+                }
+                obj.verts.push(v);
+            }
+            Some(kind @ ("f" | "l")) => {
+                let indices = words
+                    .map(|reference| vertex_index(reference, obj.verts.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(at_line)?;
+                match kind {
+                    "f" => obj.faces.push(indices),
+                    _ => obj.lines.extend(indices.windows(2).map(|w| (w[0], w[1]))),
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(obj)
+}
+
+/// Axis a model is symmetric across, if it can be stored as one half
+/// completed by a mirror instance
+/// 
+/// Every vertex, edge and face must have a mirror image in the model. No
+/// edge may reach across the mirror plane and no face may have corners on
+/// both sides of it, as only the edges and faces of one side are kept, see
+/// [`mirror_half`].
+fn find_mirror(obj: &Obj) -> Option<Mirror> {
+    [(Mirror::X, 0), (Mirror::Z, 2)]
+        .into_iter()
+        .find(|&(_, axis)| is_symmetric(obj, axis))
+        .map(|(mirror, _)| mirror)
+}
+
+fn is_symmetric(obj: &Obj, axis: usize) -> bool {
+    let flip = |mut v: [i32; 3]| {
+        v[axis] = -v[axis];
+        v
+    };
+    let side = |i: usize| obj.verts[i][axis].signum();
+
+    // Edges as their ends in either order, and faces as their corners in any
+    // order, so that mirror images compare equal however they wind
+    let edge = |a: [i32; 3], b: [i32; 3]| (a.min(b), a.max(b));
+    let corners = |face: &[[i32; 3]]| face.iter().copied().collect::<BTreeSet<_>>();
+    let verts: BTreeSet<_> = obj.verts.iter().copied().collect();
+    let edges: BTreeSet<_> = obj.segments().map(|(a, b)| edge(obj.verts[a], obj.verts[b])).collect();
+    let faces: BTreeSet<_> = obj
+        .faces
+        .iter()
+        .map(|face| corners(&face.iter().map(|&i| obj.verts[i]).collect::<Vec<_>>()))
+        .collect();
+
+    let crossing = obj.segments().any(|(a, b)| side(a)*side(b) < 0)
+        || obj.faces.iter().any(|face| face.iter().any(|&i| side(i) < 0) && face.iter().any(|&i| side(i) > 0));
+    !crossing
+        && obj.verts.iter().any(|v| v[axis] != 0)
+        && verts.iter().all(|&v| verts.contains(&flip(v)))
+        && edges.iter().all(|&(a, b)| edges.contains(&edge(flip(a), flip(b))))
+        && faces.iter().all(|face| faces.contains(&corners(&face.iter().map(|&v| flip(v)).collect::<Vec<_>>())))
+}
+
+/// The half of a symmetric model at or beyond zero along the mirror axis,
+/// with its vertices renumbered
+/// 
+/// Edges and faces on the mirror plane are kept, and drawn by both
+/// instances.
+fn mirror_half(obj: &Obj, mirror: Mirror) -> Obj {
+    let axis = match mirror {
+        Mirror::X => 0,
+        Mirror::Z => 2,
+    };
+    let mut renumbered = vec![None; obj.verts.len()];
+    let mut verts = Vec::new();
+    for (i, v) in obj.verts.iter().enumerate() {
+        if v[axis] >= 0 {
+            renumbered[i] = Some(verts.len());
+            verts.push(*v);
+        }
+    }
+    let faces = obj
+        .faces
+        .iter()
+        .filter_map(|face| face.iter().map(|&i| renumbered[i]).collect())
+        .collect();
+    let lines = obj
+        .lines
+        .iter()
+        .filter_map(|&(a, b)| Some((renumbered[a]?, renumbered[b]?)))
+        .collect();
+    Obj { verts, faces, lines }
+}
+
+/// Work out the culling faces, fills and edges of a model
+/// 
+/// Faces contribute their outline, closing back to the first vertex, and
+/// polylines their segments.
+/// 
+/// Faces wind counterclockwise seen from outside with Y up, which is
+/// clockwise on screen once Y is flipped, so their first three vertices are
+/// used for culling as they are. A model with more than [`MAX_FACES`] faces
+/// is not culled at all, and one with more than [`MAX_FILLS`] triangles and
+/// quads is not filled at all, with a warning naming the model from `path`.
+fn build_model(obj: &Obj, path: &Path) -> Result<Model, String> {
+    let mut model = Model { verts: obj.verts.clone(), faces: Vec::new(), fills: Vec::new(), edges: BTreeMap::new() };
+    for indices in &obj.faces {
+        let face = match indices[..] {
+            [a, b, c, ..] => {
+                model.faces.push([a, b, c]);
+                Some(1u16.checked_shl(model.faces.len() as u32 - 1).unwrap_or(0))
+            }
+            _ => None,
+        };
+        match indices[..] {
+            [a, b, c] => model.fills.push(([a, b, c, c], shade(&model.verts, a, b, c))),
+            [a, b, c, d] => model.fills.push(([a, b, c, d], shade(&model.verts, a, b, c))),
+            _ => {}
+        }
+        let closing = indices.last().copied().zip(indices.first().copied());
+        for (a, b) in indices.windows(2).map(|w| (w[0], w[1])).chain(closing) {
+            if a != b {
+                let mask = model.edges.entry((a.min(b), a.max(b))).or_insert(face);
+                *mask = mask.zip(face).map(|(mask, face)| mask | face);
+            }
+        }
+    }
+    for &(a, b) in &obj.lines {
+        if a != b {
+            model.edges.insert((a.min(b), a.max(b)), None);
+        }
+    }
+    if model.verts.is_empty() || model.verts.len() > MAX_VERTS {
+        return Err(format!("model has {} vertices, 1 to {} supported", model.verts.len(), MAX_VERTS));
+    }
+    if model.faces.len() > MAX_FACES {
+        println!(
+            "cargo:warning={}: {} faces, more than the {} that can be culled, so it is drawn without culling",
+            path.display(),
+            model.faces.len(),
+            MAX_FACES,
+        );
+        model.faces.clear();
+        model.edges.values_mut().for_each(|mask| *mask = None);
+    }
+    if model.fills.len() > MAX_FILLS {
+        println!(
+            "cargo:warning={}: {} faces to fill, more than the {} that can be filled, so none are filled",
+            path.display(),
+            model.fills.len(),
+            MAX_FILLS,
+        );
+        model.fills.clear();
+    }
+    Ok(model)
+}
+
+/// Shade to fill the face with corners `a`, `b` and `c` with, as if lit from
+/// straight above
+fn shade(verts: &[[i32; 3]], a: usize, b: usize, c: usize) -> u8 {
+    let [a, b, c] = [a, b, c].map(|i| verts[i].map(|coord| coord as f64));
+    let (u, v) = ([b[0] - a[0], b[1] - a[1], b[2] - a[2]], [c[0] - a[0], c[1] - a[1], c[2] - a[2]]);
+    let normal = [u[1]*v[2] - u[2]*v[1], u[2]*v[0] - u[0]*v[2], u[0]*v[1] - u[1]*v[0]];
+    let length = normal.iter().map(|n| n*n).sum::<f64>().sqrt();
+
+    // Flipping Y down also turns the winding around, so the normal still
+    // points up for a face facing up
+    let up = if length == 0.0 { 0.0 } else { normal[1] / length };
+    (SIDE_SHADE + LIGHT_SHADE*up).round() as u8
+}
+
+/// Join edges into as few line strips as a greedy walk finds
+/// 
+/// Edges bordering the same faces are joined into one culled strip, and
+/// edges drawn always into plain strips. Returns the strip data in the
+/// format of `Mesh::strips`.
+fn build_strips(edges: BTreeMap<(usize, usize), Option<u16>>) -> Vec<u8> {
+    let mut groups = BTreeMap::<Option<u16>, BTreeSet<(usize, usize)>>::new();
+    for (edge, mask) in edges {
+        groups.entry(mask).or_default().insert(edge);
+    }
+
+    let mut strips = Vec::new();
+    for (mask, edges) in groups {
+        build_group_strips(edges, mask, &mut strips);
+    }
+    strips
+}
+
+/// Join edges bordering the same faces into strips, appending them to
+/// `strips`
+fn build_group_strips(mut edges: BTreeSet<(usize, usize)>, mask: Option<u16>, strips: &mut Vec<u8>) {
+    while let Some(&(start, next)) = edges.iter().next() {
+        edges.remove(&(start, next));
+        let mut strip = vec![start, next];
+        while strip.len() < MAX_STRIP_LEN {
+            let last = *strip.last().unwrap();
+            let edge = edges
+                .iter()
+                .find(|&&(a, b)| a == last || b == last)
+                .copied();
+            match edge {
+                Some((a, b)) => {
+                    edges.remove(&(a, b));
+                    strip.push(if a == last { b } else { a });
+                }
+                None => break,
+            }
+        }
+        match mask {
+            Some(mask) => {
+                strips.push(CULLED | strip.len() as u8);
+                strips.extend(mask.to_le_bytes());
+            }
+            None => strips.push(strip.len() as u8),
+        }
+        strips.extend(strip.iter().map(|&index| index as u8));
+    }
+}
+
+/// Match the vertices of a morph target to those of its model
+/// 
+/// A target with fewer vertices is padded by sending each model vertex
+/// without a partner to the nearest target vertex, so that extra detail
+/// folds into the target shape. A target with more vertices than the model
+/// cannot be drawn with the model's strips and is an error.
+fn match_morph(verts: &[[i32; 3]], mut target: Vec<[i32; 3]>) -> Result<Vec<[i32; 3]>, String> {
+    if target.is_empty() {
+        return Err("morph target has no vertices".into());
+    }
+    if target.len() > verts.len() {
+        return Err(format!(
+            "morph target has {} vertices, more than the {} of its model",
+            target.len(),
+            verts.len()
+        ));
+    }
+    let matched = target.len();
+    for v in &verts[matched..] {
+        let distance = |t: &&[i32; 3]| (0..3).map(|k| (t[k] - v[k]).pow(2) as i64).sum::<i64>();
+        let nearest = *target[..matched].iter().min_by_key(distance).unwrap();
+        target.push(nearest);
+    }
+    Ok(target)
+}
+
+/// Write a vertex table the way the hand-written tables do
+fn write_verts(out: &mut String, verts: &[[i32; 3]]) {
+    for [x, y, z] in verts {
+        let _ = writeln!(out, "            vec3!({}, {}, {}),", hex(*x), hex(*y), hex(*z));
+    }
+}
+
+/// Center and radius of a sphere holding every point, close to the smallest
+/// 
+/// Ritter's approximation: start from the two points furthest apart along a
+/// sweep, then grow the sphere over every point left outside. The radius is
+/// rounded up so that every point is still inside once the center is rounded
+/// to whole units.
+fn bounding_sphere(points: &[[i32; 3]]) -> ([i32; 3], i32) {
+    let point = |p: &[i32; 3]| p.map(f64::from);
+    let distance = |a: [f64; 3], b: [f64; 3]| {
+        ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+    };
+    let farthest_from = |from: [f64; 3]| {
+        points
+            .iter()
+            .map(point)
+            .max_by(|&a, &b| distance(from, a).total_cmp(&distance(from, b)))
+            .unwrap_or(from)
+    };
+
+    let start = points.first().map_or([0.0; 3], point);
+    let a = farthest_from(start);
+    let b = farthest_from(a);
+    let mut center = [0, 1, 2].map(|k| (a[k] + b[k])/2.0);
+    let mut radius = distance(a, b)/2.0;
+    for p in points.iter().map(point) {
+        let d = distance(center, p);
+        if d > radius {
+            let grow = (d - radius)/2.0;
+            center = [0, 1, 2].map(|k| center[k] + (p[k] - center[k])*grow/d);
+            radius += grow;
+        }
+    }
+
+    let center = center.map(|c| c.round() as i32);
+    let radius = points
+        .iter()
+        .map(|&p| distance(point(&center), point(&p)).ceil() as i32)
+        .max()
+        .unwrap_or(0);
+    (center, radius)
+}
+
+/// Format a Q12 value the way the hand-written tables do
+fn hex(value: i32) -> String {
+    if value < 0 {
+        format!("-{:#05x}", -value)
+    } else {
+        format!(" {:#05x}", value)
+    }
+}
+
+/// Convert every `.obj` file in [`MODELS_DIR`] into a `scene!` object
+/// 
+/// Each object is named after its file in upper case and does not move, as
+/// OBJ files have no animation. The objects are written to `models.rs` in `OUT_DIR`,
+/// which `main` includes as the `models` module.
+/// 
+/// A model with a morph target beside it, named with [`MORPH_SUFFIX`], morphs
+/// into the target's vertices and back, see [`match_morph`]. Only the
+/// vertices of the target are used.
+/// 
+/// A model without a morph target that is symmetric across X or Z is stored
+/// as one half with `mirror:` set, see [`find_mirror`].
+fn import_models() -> Result<Vec<Object>, String> {
+    println!("cargo:rerun-if-changed={}", MODELS_DIR);
+
+    let mut paths = match fs::read_dir(MODELS_DIR) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "obj"))
+            .filter(|path| {
+                let stem = path.file_stem().and_then(|stem| stem.to_str());
+                !stem.is_some_and(|stem| stem.ends_with(MORPH_SUFFIX))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+
+    let mut objects = Vec::new();
+    let mut out = String::from("scene! {\n");
+    for path in &paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(|stem| stem.to_uppercase().replace(|c: char| !c.is_ascii_alphanumeric(), "_"))
+            .ok_or_else(|| format!("{}: bad file name", path.display()))?;
+        let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let obj = parse_obj(&source).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        // Morph targets are matched to the whole model, so only models
+        // without one are halved
+        let morph_path = path.with_extension(format!("{}.obj", &MORPH_SUFFIX[1..]));
+        println!("cargo:rerun-if-changed={}", morph_path.display());
+        let morph_source = fs::read_to_string(&morph_path).ok();
+        let mirror = if morph_source.is_none() { find_mirror(&obj) } else { None };
+        let obj = match mirror {
+            Some(mirror) => mirror_half(&obj, mirror),
+            None => obj,
+        };
+        let model = build_model(&obj, path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let _ = writeln!(out, "\n    /// Imported from `{}`", path.display());
+        let _ = writeln!(out, "    pub object {} {{", name);
+        match mirror {
+            Some(Mirror::X) => out.push_str("        mirror: X,\n"),
+            Some(Mirror::Z) => out.push_str("        mirror: Z,\n"),
+            None => {}
+        }
+        let _ = writeln!(out, "        verts: [");
+        write_verts(&mut out, &model.verts);
+        let _ = writeln!(out, "        ],");
+        let strips = build_strips(model.edges);
+        let _ = writeln!(out, "        strips: {:?},", strips);
+        let faces: Vec<_> = model.faces.iter().map(|face| face.map(|index| index as u8)).collect();
+        let _ = writeln!(out, "        faces: {:?},", faces);
+        if !model.fills.is_empty() {
+            let _ = writeln!(out, "        fills: [");
+            for ([a, b, c, d], shade) in &model.fills {
+                if c == d {
+                    let _ = writeln!(out, "            uhouse_core::fill::Fill::triangle({}, {}, {}, {}),", a, b, c, shade);
+                } else {
+                    let _ = writeln!(out, "            uhouse_core::fill::Fill::quad({}, {}, {}, {}, {}),", a, b, c, d, shade);
+                }
+            }
+            let _ = writeln!(out, "        ],");
+        }
+
+        // The bounds hold the morph target as well as the model
+        let mut extent = model.verts.clone();
+        if let Some(source) = morph_source {
+            let target = parse_obj(&source)
+                .and_then(|target| match_morph(&model.verts, target.verts))
+                .map_err(|e| format!("{}: {}", morph_path.display(), e))?;
+            let _ = writeln!(out, "        morph: ([");
+            write_verts(&mut out, &target);
+            let _ = writeln!(out, "        ], {}, InOut),", MORPH_PERIOD);
+            extent.extend(target);
+        }
+        let ([x, y, z], radius) = bounding_sphere(&extent);
+        let _ = writeln!(out, "        bounds: (vec3!({}, {}, {}), {:#05x}),", hex(x), hex(y), hex(z), radius);
+        let _ = writeln!(out, "    }}");
+
+        objects.push(Object {
+            name,
+            verts: model.verts.iter().map(|&[x, y, z]| vec3!(x as IFixed, y as IFixed, z as IFixed)).collect(),
+            strips,
+            faces,
+            mirror,
+        });
+    }
+    out.push_str("}\n");
+
+    let out_dir = env::var_os("OUT_DIR").ok_or("OUT_DIR is not set")?;
+    fs::write(Path::new(&out_dir).join("models.rs"), out).map_err(|e| e.to_string())?;
+    Ok(objects)
+}
+
+/// The built-in house, as an [`Object`] like the imported models
+fn house() -> Object {
+    use uhouse_core::house::HOUSE;
+
+    Object {
+        name: "HOUSE".into(),
+        verts: HOUSE::VERTS.iter().collect(),
+        strips: HOUSE::STRIPS.iter().collect(),
+        faces: HOUSE::FACES.iter().collect(),
+        mirror: HOUSE.mirror,
+    }
+}
+
+/// Render a thumbnail of the built-in house and of every imported model
+/// 
+/// Thumbnails are drawn by the renderer the firmware and the simulator
+/// share, with the scene turned by [`THUMBNAIL_YAW`] and tilted by
+/// [`THUMBNAIL_PITCH`] and scaled to fill [`THUMBNAIL_SIZE`]. Each is written
+/// to `thumbnails.rs` in `OUT_DIR` as a table in program memory named after
+/// its object, one row after another with the leftmost pixel in the top bit
+/// of each byte.
+fn render_thumbnails(models: &[Object]) -> Result<(), String> {
+    let house = house();
+    let mut out = String::from("progmem! {\n");
+    for object in std::iter::once(&house).chain(models) {
+        let thumbnail = render_thumbnail(object);
+        let _ = writeln!(out, "\n    /// Thumbnail of `{}`", object.name);
+        let _ = writeln!(out, "    pub static progmem {}: [u8; {}] = {:?};", object.name, thumbnail.len(), thumbnail);
+    }
+    out.push_str("}\n");
+
+    let out_dir = env::var_os("OUT_DIR").ok_or("OUT_DIR is not set")?;
+    fs::write(Path::new(&out_dir).join("thumbnails.rs"), out).map_err(|e| e.to_string())
+}
+
+/// Render the wireframe of an object with its hidden strips culled into a
+/// thumbnail bitmap
+fn render_thumbnail(object: &Object) -> Vec<u8> {
+    let rotor = |degrees: f64| {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Rotor::from_vec2(vec2!((cos*4096.0).round() as IFixed, (sin*4096.0).round() as IFixed))
+    };
+    let rotation = Mat3::from_rotors(rotor(THUMBNAIL_YAW), rotor(THUMBNAIL_PITCH), rotor(0.0));
+    let instances: Vec<Option<Mirror>> = std::iter::once(None).chain(object.mirror.map(Some)).collect();
+    let projected: Vec<Vec<Vec2>> = instances
+        .iter()
+        .map(|mirror| {
+            object
+                .verts
+                .iter()
+                .map(|&v| mirror.map_or(v, |axis| axis.apply(v)))
+                .map(|v| project::<Perspective>(view_transform(v, &rotation, Rotor::IDENTITY, DEFAULT_DEPTH), vec2!(0, 0)))
+                .collect()
+        })
+        .collect();
+
+    // Fit the projected vertices of every instance into the thumbnail,
+    // keeping their aspect
+    let min = |axis: fn(&Vec2) -> IFixed| projected.iter().flatten().map(axis).min().unwrap_or(0) as f64;
+    let max = |axis: fn(&Vec2) -> IFixed| projected.iter().flatten().map(axis).max().unwrap_or(0) as f64;
+    let (min_x, min_y) = (min(|v| v.x), min(|v| v.y));
+    let (width, height) = (max(|v| v.x) - min_x, max(|v| v.y) - min_y);
+    let last = (THUMBNAIL_SIZE - 1) as f64;
+    let scale = last / width.max(height).max(1.0);
+    let (pad_x, pad_y) = ((last - width*scale) / 2.0, (last - height*scale) / 2.0);
+
+    let mut bitmap = vec![0u8; THUMBNAIL_ROW_BYTES*THUMBNAIL_SIZE];
+    for (mirror, projected) in instances.into_iter().zip(&projected) {
+        let screen: Vec<Vec2> = projected
+            .iter()
+            .map(|v| vec2!(
+                ((v.x as f64 - min_x)*scale + pad_x).round() as IFixed,
+                ((v.y as f64 - min_y)*scale + pad_y).round() as IFixed
+            ))
+            .collect();
+        draw_thumbnail_instance(object, &screen, mirror, &mut bitmap);
+    }
+    bitmap
+}
+
+/// Draw the strips of one instance of an object into a thumbnail, given
+/// the thumbnail position of each vertex
+fn draw_thumbnail_instance(object: &Object, screen: &[Vec2], mirror: Option<Mirror>, bitmap: &mut [u8]) {
+    // Faces wind clockwise on screen while seen from the front, or
+    // counterclockwise for a mirror instance
+    let mut front = 0u16;
+    for (bit, &[a, b, c]) in object.faces.iter().enumerate() {
+        let (a, b, c) = (screen[a as usize], screen[b as usize], screen[c as usize]);
+        let cross = (b.x - a.x) as i32*(c.y - a.y) as i32 - (b.y - a.y) as i32*(c.x - a.x) as i32;
+        let seen = if mirror.is_some() { cross <= 0 } else { cross >= 0 };
+        if seen {
+            front |= 1 << bit;
+        }
+    }
+
+    let bounds = Rect::sized(THUMBNAIL_SIZE as IFixed, THUMBNAIL_SIZE as IFixed);
+    let mut strips = object.strips.iter().copied();
+    while let Some(header) = strips.next() {
+        let len = header as usize & MAX_STRIP_LEN;
+        let style = Style::from_bits(header >> STYLE_SHIFT);
+        let shown = if header & CULLED != 0 {
+            let faces = u16::from_le_bytes([strips.next().unwrap_or(0), strips.next().unwrap_or(0)]);
+            faces & front != 0
+        } else {
+            true
+        };
+        let strip: Vec<u8> = strips.by_ref().take(len).collect();
+        if !shown {
+            continue;
+        }
+        for pair in strip.windows(2) {
+            let (v0, v1) = (screen[pair[0] as usize], screen[pair[1] as usize]);
+            draw_line(
+                |x, y| bitmap[y as usize*THUMBNAIL_ROW_BYTES + x as usize/8] |= 0x80 >> (x % 8),
+                v0,
+                v1,
+                Endpoints::Both,
+                style,
+                &bounds,
+            );
+        }
+    }
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let models = match import_models() {
+        Ok(models) => models,
+        Err(error) => {
+            eprintln!("error: cannot import models: {}", error);
+            process::exit(1);
+        }
+    };
+    if let Err(error) = render_thumbnails(&models) {
+        eprintln!("error: cannot render thumbnails: {}", error);
+        process::exit(1);
+    }
+
+    let board = match BOARDS.iter().find(|board| feature_enabled(board.feature)) {
+        Some(board) => board,
+        None => {
+            eprintln!("error: no board picked; enable one of the board-* features");
+            process::exit(1);
+        }
+    };
+    if let Ok(target) = env::var("TARGET") {
+        if target != board.target {
+            eprintln!(
+                "error: the {} feature builds for {}; pass --target avr-specs/{}.json",
+                board.feature, board.target, board.target
+            );
+            process::exit(1);
+        }
+    }
+
+    let mut flash = 0;
+    let mut ram = 0;
+
+    for subsystem in SUBSYSTEMS {
+        let enabled = match subsystem.feature {
+            Some(feature) => feature_enabled(feature),
+            None => true,
+        };
+        if enabled {
+            flash += subsystem.flash;
+            ram += subsystem.ram;
+            println!(
+                "cargo:warning={:<16} flash {:>5} B  ram {:>4} B",
+                subsystem.name, subsystem.flash, subsystem.ram
+            );
+        }
+    }
+
+    println!(
+        "cargo:warning=estimated total flash {:>5}/{} B  ram {:>4}/{} B",
+        flash, board.flash, ram, board.ram
+    );
+
+    if flash > board.flash || ram > board.ram {
+        eprintln!(
+            "error: enabled features exceed the estimated device budget \
+            (flash {}/{} B, ram {}/{} B); disable a subsystem",
+            flash, board.flash, ram, board.ram
+        );
+        process::exit(1);
+    }
+
+    audit_interrupts(board);
+
+    if feature_enabled("bounded") {
+        check_frame_time(board, &models);
+    }
+}
+
+/// List the interrupt handlers enabled for this build in order of priority
+fn audit_interrupts(board: &Board) {
+    let mut enabled: Vec<(u8, &Interrupt)> = Vec::new();
+    for interrupt in INTERRUPTS {
+        if !interrupt.features.iter().any(|feature| feature_enabled(feature)) {
+            continue;
+        }
+        match board.vectors.iter().find(|(name, _)| *name == interrupt.name) {
+            Some(&(_, vector)) => enabled.push((vector, interrupt)),
+            None => {
+                eprintln!("error: no vector number for {} with {}", interrupt.name, board.feature);
+                process::exit(1);
+            }
+        }
+    }
+    enabled.sort_by_key(|&(vector, _)| vector);
+
+    for (vector, interrupt) in &enabled {
+        println!(
+            "cargo:warning=isr {:<12} vector {:>2}  {}",
+            interrupt.name, vector, interrupt.features.join(", ")
+        );
+    }
+}
+
+/// Work out the longest a frame can take and stop the build if it exceeds
+/// [`FRAME_TIME_LIMIT_MS`]
+/// 
+/// The worst frame draws the largest scene with no strip culled and every
+/// segment spanning [`MAX_LINE_PIXELS`], then flushes every page, at the
+/// [`FRAME_COSTS`] of the enabled subsystems. With `scene-cycle` every
+/// imported model counts, whether or not it is listed in `scenes!`, and
+/// without it only the house.
+fn check_frame_time(board: &Board, models: &[Object]) {
+    let house = house();
+    let scenes: Vec<&Object> = if feature_enabled("scene-cycle") {
+        std::iter::once(&house).chain(models).collect()
+    } else {
+        vec![&house]
+    };
+
+    let costs: Vec<&FrameCost> = FRAME_COSTS
+        .iter()
+        .filter(|cost| match cost.feature {
+            Some(feature) => feature_enabled(feature),
+            None => true,
+        })
+        .collect();
+    let sum = |cost: fn(&FrameCost) -> u32| costs.iter().map(|c| cost(c)).sum::<u32>();
+    let (frame, vertex, face) = (sum(|c| c.frame), sum(|c| c.vertex), sum(|c| c.face));
+    let (segment, pixel) = (sum(|c| c.segment), sum(|c| c.pixel));
+
+    let (object, cycles) = scenes
+        .iter()
+        .map(|object| {
+            let cycles = frame
+                + vertex*object.verts.len() as u32
+                + face*object.faces.len() as u32
+                + (segment + pixel*MAX_LINE_PIXELS)*count_segments(&object.strips);
+            (object, cycles)
+        })
+        .max_by_key(|&(_, cycles)| cycles)
+        .expect("the house is always drawn");
+    let ms = (cycles as u64*1000).div_ceil(board.clock as u64) as u32;
+
+    println!(
+        "cargo:warning=worst frame     {:>5} ms of {} ms, {} cycles drawing {}",
+        ms, FRAME_TIME_LIMIT_MS, cycles, object.name
+    );
+
+    if ms > FRAME_TIME_LIMIT_MS {
+        eprintln!(
+            "error: a frame of {} can take {} ms, over the limit of {} ms; \
+            disable a subsystem or raise FRAME_TIME_LIMIT_MS",
+            object.name, ms, FRAME_TIME_LIMIT_MS
+        );
+        process::exit(1);
+    }
+}
+
+/// Number of segments in line strips, culled or not
+fn count_segments(strips: &[u8]) -> u32 {
+    let mut count = 0;
+    let mut i = 0;
+    while i < strips.len() {
+        let header = strips[i];
+        let len = header as usize & MAX_STRIP_LEN;
+        count += len.saturating_sub(1) as u32;
+        i += 1 + len + if header & CULLED != 0 { 2 } else { 0 };
+    }
+    count
+}
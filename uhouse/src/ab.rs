@@ -0,0 +1,107 @@
+#![cfg(feature = "ab-compare")]
+
+//! A/B comparison of two render paths
+//! 
+//! Renders the same [`BLOCK_FRAMES`] frames of animation with path A, then
+//! rewinds and renders them again with path B, and logs the cycles per frame
+//! and a CRC of every frame rendered by each path. An optimization can then
+//! show on real hardware that it renders identical frames, and how much
+//! faster it does so, in a single run.
+
+use ufmt::{uwriteln, uWrite};
+
+use crate::framebuffer::FrameBuffer;
+use crate::clock;
+use crate::text::Text;
+
+/// Number of frames rendered with each path before switching
+const BLOCK_FRAMES: u16 = 64;
+
+/// Render path in use
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Path {
+    A,
+    B,
+}
+
+/// Alternates between render paths, generic over the animation state `S`
+/// that is replayed for path B
+pub struct AbCompare<S: Copy> {
+    path: Path,
+    frames: u16,
+
+    /// Cycle count at the start of the current block
+    start: u32,
+
+    /// CRC of every frame rendered in the current block
+    crc: u16,
+
+    /// Animation state at the start of the last path A block
+    rewind: Option<S>,
+
+    /// CRC of the last path A block
+    crc_a: u16,
+}
+
+impl<S: Copy> AbCompare<S> {
+    pub const fn new() -> Self {
+        Self {
+            path: Path::A,
+            frames: 0,
+            start: 0,
+            crc: 0xffff,
+            rewind: None,
+            crc_a: 0,
+        }
+    }
+
+    /// Render path to use this frame
+    pub fn path(&self) -> Path {
+        self.path
+    }
+
+    /// Called at the start of every frame with the animation state
+    /// 
+    /// Rewinds the state to the start of the last path A block when path B
+    /// begins. Returns `true` at the start of every block, where the path
+    /// changes, in which case anything cached from earlier frames must be
+    /// discarded.
+    pub fn begin_frame(&mut self, state: &mut S) -> bool {
+        if self.frames != 0 {
+            return false;
+        }
+        self.start = clock::now();
+        self.crc = 0xffff;
+        match (self.path, self.rewind) {
+            (Path::B, Some(rewind)) => *state = rewind,
+            _ => self.rewind = Some(*state),
+        }
+        true
+    }
+
+    /// Called at the end of every frame with the rendered frame
+    /// 
+    /// Logs the result at the end of every block.
+    pub fn end_frame<W: uWrite>(&mut self, frame: &FrameBuffer, serial: &mut W) {
+        self.crc = frame.crc(self.crc);
+        self.frames += 1;
+        if self.frames < BLOCK_FRAMES {
+            return;
+        }
+        self.frames = 0;
+
+        let per_frame = clock::now().wrapping_sub(self.start) / BLOCK_FRAMES as u32;
+        match self.path {
+            Path::A => {
+                let _ = uwriteln!(serial, "{} {} crc {:#x}", Text::PathA, per_frame, self.crc);
+                self.crc_a = self.crc;
+                self.path = Path::B;
+            }
+            Path::B => {
+                let result = if self.crc == self.crc_a { Text::Match } else { Text::Differ };
+                let _ = uwriteln!(serial, "{} {} crc {:#x} {}", Text::PathB, per_frame, self.crc, result);
+                self.path = Path::A;
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Board support
+//! 
+//! Everything that differs between the AVR boards μHouse-rs runs on is kept
+//! here: the clock, which pins the display buses are on, the chip named to
+//! `#[avr_device::interrupt]`, and in the build script its flash, RAM and
+//! interrupt vectors. The rest of the firmware reaches peripherals through
+//! `arduino_hal`, whose types already follow the board picked by its Cargo
+//! feature.
+//! 
+//! Each board is a module behind a `board-*` feature, implementing [`Board`]
+//! and defining the macros below. To support another board that
+//! `arduino_hal` supports, add its module here, its feature to `Cargo.toml`
+//! enabling the matching `arduino-hal` feature, and its row to `BOARDS` in
+//! `build.rs`.
+//! 
+//! | Macro                 | Expands to                                      |
+//! |-----------------------|-------------------------------------------------|
+//! | `interrupt! { fn }`   | The handler, declared for the board's chip      |
+//! | `i2c_pins!(pins)`     | The [`Board::I2cPins`] split off `pins`         |
+//! | `spi_pins!(pins)`     | The [`Board::SpiPins`] split off `pins`         |
+//! 
+//! The serial port, taken with `arduino_hal::default_serial!`, and the other
+//! pins the firmware uses, D2, D4, D8, D9, D13, A0 and A1, are named the same
+//! on every supported board. Boards on chips `arduino_hal` does not support
+//! yet, such as the ATmega4809 of the Arduino Nano Every, need support there
+//! first.
+
+use arduino_hal::pac::{SPI, TWI};
+use arduino_hal::spi::Settings;
+
+#[cfg(feature = "board-uno")]
+#[macro_use]
+pub mod uno;
+
+#[cfg(feature = "board-mega2560")]
+#[macro_use]
+pub mod mega2560;
+
+/// Clock and display bus setup of a board
+pub trait Board {
+    /// Clock frequency of the board, in Hz
+    const CLOCK_FREQ: u32;
+
+    /// SDA and SCL pins of the I2C bus, as split off by `i2c_pins!`
+    type I2cPins;
+
+    /// SCLK, MOSI, MISO and CS pins of the SPI bus, as split off by
+    /// `spi_pins!`
+    type SpiPins;
+
+    /// Chip select pin of the SPI bus once set up
+    type ChipSelect;
+
+    /// Set up the I2C bus at `speed` in Hz
+    fn i2c(twi: TWI, pins: Self::I2cPins, speed: u32) -> arduino_hal::I2c;
+
+    /// Set up the SPI bus as a master
+    fn spi(spi: SPI, pins: Self::SpiPins, settings: Settings) -> (arduino_hal::Spi, Self::ChipSelect);
+}
@@ -0,0 +1,67 @@
+//! Arduino Mega 2560, built around an Atmega2560 at 16 MHz
+//! 
+//! The display's I2C bus is on D20 and D21, and its SPI bus on D50 to D53.
+//! With eight times the RAM and flash of the UNO, every feature fits at
+//! once, as far as they can be combined at all.
+
+use arduino_hal::clock::Clock;
+use arduino_hal::hal::port::{PB0, PB1, PB2, PB3, PD0, PD1};
+use arduino_hal::pac::{SPI, TWI};
+use arduino_hal::port::{Pin, mode::{Floating, Input}};
+use arduino_hal::spi::{ChipSelectPin, Settings};
+
+use super::Board;
+
+pub struct Mega2560;
+
+impl Board for Mega2560 {
+    const CLOCK_FREQ: u32 = arduino_hal::DefaultClock::FREQ;
+
+    type I2cPins = (Pin<Input<Floating>, PD1>, Pin<Input<Floating>, PD0>);
+
+    type SpiPins = (
+        Pin<Input<Floating>, PB1>,
+        Pin<Input<Floating>, PB2>,
+        Pin<Input<Floating>, PB3>,
+        Pin<Input<Floating>, PB0>,
+    );
+
+    type ChipSelect = ChipSelectPin<PB0>;
+
+    fn i2c(twi: TWI, (sda, scl): Self::I2cPins, speed: u32) -> arduino_hal::I2c {
+        arduino_hal::I2c::new(twi, sda.into_pull_up_input(), scl.into_pull_up_input(), speed)
+    }
+
+    fn spi(spi: SPI, (sclk, mosi, miso, cs): Self::SpiPins, settings: Settings) -> (arduino_hal::Spi, Self::ChipSelect) {
+        arduino_hal::Spi::new(
+            spi,
+            sclk.into_output(),
+            mosi.into_output(),
+            miso.into_pull_up_input(),
+            cs.into_output(),
+            settings,
+        )
+    }
+}
+
+/// Declare an interrupt handler of the Atmega2560
+macro_rules! interrupt {
+    (fn $name:ident() $body:block) => {
+        #[avr_device::interrupt(atmega2560)]
+        fn $name() $body
+    };
+}
+
+/// Split the I2C pins, D20 and D21, off the board's pins
+macro_rules! i2c_pins {
+    ($pins:ident) => {
+        ($pins.d20, $pins.d21)
+    };
+}
+
+/// Split the SPI pins, D52, D51, D50 and D53, off the board's pins
+macro_rules! spi_pins {
+    ($pins:ident) => {
+        ($pins.d52, $pins.d51, $pins.d50, $pins.d53)
+    };
+}
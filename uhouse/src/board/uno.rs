@@ -0,0 +1,65 @@
+//! Arduino UNO, and other boards built around an Atmega328P at 16 MHz
+//! 
+//! The display's I2C bus is on A4 and A5, and its SPI bus on D10 to D13.
+
+use arduino_hal::clock::Clock;
+use arduino_hal::hal::port::{PB2, PB3, PB4, PB5, PC4, PC5};
+use arduino_hal::pac::{SPI, TWI};
+use arduino_hal::port::{Pin, mode::{Floating, Input}};
+use arduino_hal::spi::{ChipSelectPin, Settings};
+
+use super::Board;
+
+pub struct Uno;
+
+impl Board for Uno {
+    const CLOCK_FREQ: u32 = arduino_hal::DefaultClock::FREQ;
+
+    type I2cPins = (Pin<Input<Floating>, PC4>, Pin<Input<Floating>, PC5>);
+
+    type SpiPins = (
+        Pin<Input<Floating>, PB5>,
+        Pin<Input<Floating>, PB3>,
+        Pin<Input<Floating>, PB4>,
+        Pin<Input<Floating>, PB2>,
+    );
+
+    type ChipSelect = ChipSelectPin<PB2>;
+
+    fn i2c(twi: TWI, (sda, scl): Self::I2cPins, speed: u32) -> arduino_hal::I2c {
+        arduino_hal::I2c::new(twi, sda.into_pull_up_input(), scl.into_pull_up_input(), speed)
+    }
+
+    fn spi(spi: SPI, (sclk, mosi, miso, cs): Self::SpiPins, settings: Settings) -> (arduino_hal::Spi, Self::ChipSelect) {
+        arduino_hal::Spi::new(
+            spi,
+            sclk.into_output(),
+            mosi.into_output(),
+            miso.into_pull_up_input(),
+            cs.into_output(),
+            settings,
+        )
+    }
+}
+
+/// Declare an interrupt handler of the Atmega328P
+macro_rules! interrupt {
+    (fn $name:ident() $body:block) => {
+        #[avr_device::interrupt(atmega328p)]
+        fn $name() $body
+    };
+}
+
+/// Split the I2C pins, A4 and A5, off the board's pins
+macro_rules! i2c_pins {
+    ($pins:ident) => {
+        ($pins.a4, $pins.a5)
+    };
+}
+
+/// Split the SPI pins, D13, D11, D12 and D10, off the board's pins
+macro_rules! spi_pins {
+    ($pins:ident) => {
+        ($pins.d13, $pins.d11, $pins.d12, $pins.d10)
+    };
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "brownout")]
+
+//! Low supply voltage handling
+//! 
+//! The brown-out detector holds the Atmega328P in reset below the level set
+//! by the `BODLEVEL` fuses, 2.7 V on the UNO. That is well below what 16 MHz
+//! needs, so on a sagging supply the MCU keeps running out of spec for a
+//! while, long enough to corrupt an EEPROM write or leave the SSD1306 stuck
+//! with its charge pump running.
+//! 
+//! [`Supply`] measures the supply every frame against the internal bandgap
+//! reference. Once it has stayed below [`LOW_SUPPLY_MV`](crate::LOW_SUPPLY_MV)
+//! for a few frames, the display is parked with its charge pump off and the
+//! device waits for the supply to recover before starting over through the
+//! watchdog. Should the supply keep falling instead, the brown-out detector
+//! takes over with the display already off.
+
+use arduino_hal::Adc;
+use arduino_hal::adc::channel::Vbg;
+use ssd1306::command::Command;
+
+use crate::panic_policy::{steal_display_interface, watchdog_reset};
+use crate::{BANDGAP_MV, LOW_SUPPLY_MV};
+
+/// Consecutive frames the supply must measure low to count as low, riding
+/// out the dips of single conversions
+const LOW_FRAMES: u8 = 4;
+
+/// Margin above [`LOW_SUPPLY_MV`] the supply must come back to before
+/// starting over
+const RECOVERY_MARGIN_MV: u16 = 200;
+
+/// Time between measurements while parked, in milliseconds
+const PARKED_POLL_MS: u16 = 100;
+
+/// Consecutive measurements while parked the supply must stay recovered for
+const RECOVERY_POLLS: u8 = 10;
+
+pub struct Supply {
+    adc: Adc,
+
+    /// Consecutive frames measured below [`LOW_SUPPLY_MV`]
+    low_frames: u8,
+}
+
+impl Supply {
+    pub fn new(adc: Adc) -> Self {
+        Self {
+            adc,
+            low_frames: 0,
+        }
+    }
+
+    /// Supply voltage in millivolts
+    /// 
+    /// The ADC converts against the supply, so the fixed bandgap reads
+    /// lower the higher the supply is.
+    fn millivolts(&mut self) -> u16 {
+        let reading = self.adc.read_blocking(&Vbg).max(1);
+        (BANDGAP_MV as u32 * 1024 / reading as u32).min(u16::MAX as u32) as u16
+    }
+
+    /// Measure the supply, once per frame, and return whether it has been
+    /// low for [`LOW_FRAMES`] frames in a row
+    pub fn is_low(&mut self) -> bool {
+        if self.millivolts() < LOW_SUPPLY_MV {
+            self.low_frames = self.low_frames.saturating_add(1);
+        } else {
+            self.low_frames = 0;
+        }
+        self.low_frames >= LOW_FRAMES
+    }
+
+    /// Turn the display and its charge pump off, then wait for the supply
+    /// to recover and reset
+    /// 
+    /// Anything to persist must be written before calling this.
+    pub fn park(&mut self) -> ! {
+        // SAFETY: Nothing draws to the display from here on
+        let mut interface = unsafe { steal_display_interface() };
+        let _ = Command::DisplayOn(false).send(&mut interface);
+        let _ = Command::ChargePump(false).send(&mut interface);
+
+        let mut recovered = 0;
+        while recovered < RECOVERY_POLLS {
+            arduino_hal::delay_ms(PARKED_POLL_MS);
+            if self.millivolts() >= LOW_SUPPLY_MV + RECOVERY_MARGIN_MV {
+                recovered += 1;
+            } else {
+                recovered = 0;
+            }
+        }
+        watchdog_reset()
+    }
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "pixel-shift")]
+
+//! OLED burn-in protection
+//! 
+//! Pixels that stay lit wear out faster and leave a ghost image, which for
+//! this demo means the HUD and the middle of the screen the house spins
+//! around. [`PixelShift`] moves the whole image by up to one pixel in a slow
+//! cycle to spread that wear over neighbouring pixels.
+
+use uhouse_core::vec::*;
+
+/// Frames spent at each offset before moving on to the next
+const SHIFT_PERIOD: u16 = 3600;
+
+/// Offsets cycled through, in pixels
+/// 
+/// Consecutive offsets are one pixel apart so the shift is never noticeable.
+const OFFSETS: [Vec2; 9] = [
+    vec2!( 0,  0),
+    vec2!( 1,  0),
+    vec2!( 1,  1),
+    vec2!( 0,  1),
+    vec2!(-1,  1),
+    vec2!(-1,  0),
+    vec2!(-1, -1),
+    vec2!( 0, -1),
+    vec2!( 1, -1),
+];
+
+pub struct PixelShift {
+    frames: u16,
+    step: u8,
+}
+
+impl PixelShift {
+
+    /// Start without any offset
+    pub const fn new() -> Self {
+        Self {
+            frames: 0,
+            step: 0,
+        }
+    }
+
+    /// Advance by one frame
+    pub fn update(&mut self) {
+        self.frames += 1;
+        if self.frames >= SHIFT_PERIOD {
+            self.frames = 0;
+            self.step = (self.step + 1) % OFFSETS.len() as u8;
+        }
+    }
+
+    /// Offset to add to everything drawn this frame, in pixels
+    /// 
+    /// Applied to the projection center for the scene, and to be added to the
+    /// position of anything drawn on the overlay.
+    pub fn offset(&self) -> Vec2 {
+        OFFSETS[self.step as usize]
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(any(feature = "fps", feature = "fps-overlay", feature = "profile", feature = "remote"))]
+
+//! Cycle counter shared by the frame rate counter, the profiler and the
+//! remote benchmark
+//! 
+//! TC1 runs free at the full clock rate, and its overflows are counted in
+//! an interrupt to extend it to 32 bits, which wraps around after about four
+//! and a half minutes at 16 MHz. The `fps` and `fps-overlay` features count
+//! frames against it one second at a time, and the `profile` feature times
+//! the stages of each frame with it, so that the frame rate can be shown on
+//! the display while profiling. The `remote` feature times its benchmark
+//! with it.
+
+use core::cell::Cell;
+use arduino_hal::pac::TC1;
+use avr_device::interrupt::Mutex;
+
+/// Upper 16 bits of the cycle counter, incremented on TC1 overflow
+static OVERFLOWS: Mutex<Cell<u16>> = Mutex::new(Cell::new(0));
+
+/// Start counting cycles on TC1
+/// 
+/// # Safety
+/// 
+/// Interrupts must not yet be enabled before calling, and nothing else may
+/// use TC1 afterwards.
+pub unsafe fn start(tc1: TC1) {
+    use arduino_hal::pac::tc1::tccr1b::CS1_A;
+
+    // Free-running at the full clock rate
+    tc1.tccr1a.write(|w| w.wgm1().bits(0));
+    tc1.tccr1b.write(|w| w.cs1().variant(CS1_A::DIRECT));
+    tc1.tcnt1.write(|w| w.bits(0));
+    tc1.timsk1.write(|w| w.toie1().set_bit()); // Enable this interrupt
+}
+
+/// Read the 32-bit cycle counter
+pub fn now() -> u32 {
+    avr_device::interrupt::free(|cs| {
+        // SAFETY: TC1 is only set up by `start`, and reading its counter and
+        // flags has no side effects
+        let tc1 = unsafe { &*TC1::ptr() };
+
+        let mut high = OVERFLOWS.borrow(&cs).get();
+        let low = tc1.tcnt1.read().bits();
+
+        // Account for an overflow that happened after interrupts were
+        // disabled and has not been serviced yet
+        if tc1.tifr1.read().tov1().bit_is_set() && low < 0x8000 {
+            high = high.wrapping_add(1);
+        }
+
+        (high as u32) << 16 | low as u32
+    })
+}
+
+interrupt! {
+    fn TIMER1_OVF() {
+        isr_budget!(Timer1, {
+            avr_device::interrupt::free(|cs| {
+                let overflows = OVERFLOWS.borrow(&cs);
+                overflows.set(overflows.get().wrapping_add(1));
+            });
+        });
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(any(feature = "remote", feature = "console"))]
+
+//! Single-byte commands received over serial
+//! 
+//! The `remote` and `console` features both take commands from a serial
+//! terminal or host tooling, each acting on its own. Every command byte is
+//! listed here once, so that no byte means one thing to a build with one
+//! feature and something else to a build with the other. A build acts on
+//! the commands of its feature and answers any other as unknown.
+//! 
+//! | Byte      | Command                    | Feature   |
+//! |-----------|----------------------------|-----------|
+//! | `r`       | Soft reset                 | `remote`  |
+//! | `b`       | Jump to the bootloader     | `remote`  |
+//! | `t`       | Time a run of frames       | `remote`  |
+//! | `p`       | Pause or resume            | `console` |
+//! | `+` / `-` | Speed up or slow down      | `console` |
+//! | `z` / `Z` | Move nearer or further     | `console` |
+//! | `a`       | Restart the animation      | `console` |
+//! | `s`       | Next scene                 | `console` |
+//! | `c` / `C` | Dim or brighten            | `console` |
+//! | `?`       | Print the command list     | both      |
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    Reset,
+    Bootloader,
+    Bench,
+    Pause,
+    Faster,
+    Slower,
+    Nearer,
+    Further,
+    Restart,
+    NextScene,
+    Dimmer,
+    Brighter,
+    Help,
+
+    /// Line endings from terminals, which are ignored
+    LineEnd,
+}
+
+impl Command {
+
+    /// The command a byte stands for, if any
+    pub fn parse(byte: u8) -> Option<Self> {
+        Some(match byte {
+            b'r' => Command::Reset,
+            b'b' => Command::Bootloader,
+            b't' => Command::Bench,
+            b'p' => Command::Pause,
+            b'+' => Command::Faster,
+            b'-' => Command::Slower,
+            b'z' => Command::Nearer,
+            b'Z' => Command::Further,
+            b'a' => Command::Restart,
+            b's' => Command::NextScene,
+            b'c' => Command::Dimmer,
+            b'C' => Command::Brighter,
+            b'?' => Command::Help,
+            b'\r' | b'\n' => Command::LineEnd,
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,197 @@
+#![cfg(feature = "console")]
+
+//! Commands from the host over the FPS serial port
+//! 
+//! The `fps` feature only ever writes to the serial port. With the `console`
+//! feature the main loop also reads single-byte commands from it every frame
+//! and applies them to the animation, so a demo can be paused, sped up or
+//! moved closer from any serial terminal without reflashing. Every command
+//! is acknowledged with a line of text, among the frame rates.
+//! 
+//! | Command   | Effect                                          |
+//! |-----------|-------------------------------------------------|
+//! | `p`       | Pause or resume the animation                   |
+//! | `+` / `-` | Double or halve the animation speed             |
+//! | `z` / `Z` | Move the scene nearer or further away           |
+//! | `a`       | Restart the animation of the scene              |
+//! | `s`       | Move on to the next scene, with `scene-cycle`   |
+//! | `c` / `C` | Dim or brighten the display, with `settings`    |
+//! | `?`       | Print the command list                          |
+//! 
+//! With the `settings` feature, everything but a restart is kept over a
+//! reset, see [`settings`](crate::settings). The bytes are shared with the
+//! remote control, see [`command`](crate::command), so `r` is left to mean a
+//! reset.
+
+use embedded_hal::serial::Read;
+use ufmt::{uwriteln, uWrite};
+use uhouse_core::vec::IFixed;
+
+use crate::command::Command;
+use crate::text::Text;
+
+#[cfg(feature = "settings")]
+use crate::settings::{Settings, BRIGHTNESSES};
+
+/// Speeds to pick from with `+` and `-`, in quarter steps per frame
+const SPEEDS: [u8; 5] = [1, 2, 4, 8, 16];
+
+/// Speed at start, of one animation step per frame
+const NORMAL_SPEED: usize = 2;
+
+/// Depth moved by each `z` or `Z`
+const DEPTH_STEP: IFixed = 0x200;
+
+/// Furthest the scene can be moved from its own depth either way
+/// 
+/// Pick this to keep the furthest vertex within the range of [`IFixed`].
+/// Moved nearest, a large model at the default depth reaches past the near
+/// plane, where its segments are clipped.
+const DEPTH_RANGE: IFixed = 0x1800;
+
+/// What the commands of a frame ask of the main loop, beyond what
+/// [`Console`] applies itself
+#[derive(Copy, Clone, Default)]
+pub struct Requests {
+    /// Start the animation of the scene over
+    pub restart: bool,
+
+    /// Move on to the next scene
+    pub next_scene: bool,
+
+    /// A setting was changed, to be saved with the `settings` feature
+    pub changed: bool,
+}
+
+pub struct Console {
+    paused: bool,
+
+    /// Index into [`SPEEDS`]
+    speed: usize,
+
+    /// Quarter steps not yet taken
+    quarters: u8,
+
+    /// Depth added to the scene's own
+    depth: IFixed,
+
+    /// Index into [`BRIGHTNESSES`]
+    #[cfg(feature = "settings")]
+    brightness: u8,
+}
+
+impl Console {
+
+    /// Start at normal speed and depth
+    pub fn new() -> Self {
+        Self {
+            paused: false,
+            speed: NORMAL_SPEED,
+            quarters: 0,
+            depth: 0,
+            #[cfg(feature = "settings")]
+            brightness: Settings::DEFAULT.brightness,
+        }
+    }
+
+    /// Take up the speed, pause, depth and brightness of saved settings
+    #[cfg(feature = "settings")]
+    pub fn restore(&mut self, settings: &Settings) {
+        self.paused = settings.paused;
+        self.speed = (settings.speed as usize).min(SPEEDS.len() - 1);
+        self.depth = settings.depth.clamp(-DEPTH_RANGE, DEPTH_RANGE);
+        self.brightness = settings.brightness;
+    }
+
+    /// Put the speed, pause, depth and brightness into settings to save
+    #[cfg(feature = "settings")]
+    pub fn store(&self, settings: &mut Settings) {
+        settings.paused = self.paused;
+        settings.speed = self.speed as u8;
+        settings.depth = self.depth;
+        settings.brightness = self.brightness;
+    }
+
+    /// Act on every command received since the last frame
+    pub fn poll<S>(&mut self, serial: &mut S) -> Requests
+    where
+        S: uWrite + Read<u8>,
+    {
+        let mut requests = Requests::default();
+        while let Ok(byte) = serial.read() {
+            self.command(serial, byte, &mut requests);
+        }
+        requests
+    }
+
+    /// Act on a command byte
+    fn command<S: uWrite>(&mut self, serial: &mut S, byte: u8, requests: &mut Requests) {
+        let command = Command::parse(byte);
+        requests.changed |= matches!(
+            command,
+            Some(Command::Pause | Command::Faster | Command::Slower | Command::Nearer
+                | Command::Further | Command::NextScene | Command::Dimmer | Command::Brighter)
+        );
+        match command {
+            Some(Command::Pause) => {
+                self.paused = !self.paused;
+                let text = if self.paused { Text::OkPause } else { Text::OkResume };
+                let _ = uwriteln!(serial, "{}", text);
+            }
+            Some(Command::Faster) => {
+                self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+                let _ = uwriteln!(serial, "{} {}%", Text::OkSpeed, SPEEDS[self.speed] as u16*25);
+            }
+            Some(Command::Slower) => {
+                self.speed = self.speed.saturating_sub(1);
+                let _ = uwriteln!(serial, "{} {}%", Text::OkSpeed, SPEEDS[self.speed] as u16*25);
+            }
+            Some(Command::Nearer) | Some(Command::Further) => {
+                let step = if command == Some(Command::Nearer) { -DEPTH_STEP } else { DEPTH_STEP };
+                self.depth = (self.depth + step).clamp(-DEPTH_RANGE, DEPTH_RANGE);
+                let _ = uwriteln!(serial, "{} {}", Text::OkDepth, self.depth);
+            }
+            Some(Command::Restart) => {
+                requests.restart = true;
+                let _ = uwriteln!(serial, "{}", Text::OkRestart);
+            }
+            #[cfg(feature = "scene-cycle")]
+            Some(Command::NextScene) => {
+                requests.next_scene = true;
+                let _ = uwriteln!(serial, "{}", Text::OkScene);
+            }
+            #[cfg(feature = "settings")]
+            Some(Command::Dimmer) => {
+                self.brightness = self.brightness.saturating_sub(1);
+                let _ = uwriteln!(serial, "{} {}", Text::OkBrightness, self.brightness);
+            }
+            #[cfg(feature = "settings")]
+            Some(Command::Brighter) => {
+                self.brightness = (self.brightness + 1).min(BRIGHTNESSES.len() as u8 - 1);
+                let _ = uwriteln!(serial, "{} {}", Text::OkBrightness, self.brightness);
+            }
+            Some(Command::Help) => {
+                let _ = uwriteln!(serial, "{}", Text::ConsoleCommands);
+            }
+            Some(Command::LineEnd) => {}
+            _ => {
+                let _ = uwriteln!(serial, "{} {}", Text::UnknownCommand, byte);
+            }
+        }
+    }
+
+    /// Animation steps to take this frame for `steps` at normal speed
+    pub fn steps(&mut self, steps: u8) -> u8 {
+        if self.paused {
+            return 0;
+        }
+        let quarters = self.quarters as u16 + steps as u16*SPEEDS[self.speed] as u16;
+        self.quarters = (quarters & 3) as u8;
+        (quarters >> 2) as u8
+    }
+
+    /// Depth to draw a scene at instead of its own `depth`
+    pub fn depth(&self, depth: IFixed) -> IFixed {
+        depth.saturating_add(self.depth)
+    }
+}
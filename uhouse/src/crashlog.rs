@@ -0,0 +1,324 @@
+#![cfg(feature = "crash-log")]
+
+//! Crash log in EEPROM
+//! 
+//! Panics and resets other than power-on are recorded in a small ring of
+//! [`CrashRecord`]s in EEPROM, which is dumped to serial on every boot. An
+//! always-on unit that failed in the field can then be diagnosed after the
+//! fact by connecting to it and pressing reset.
+//! 
+//! Crashes early in a run are also counted, and after several in a row the
+//! device boots into a safe mode instead of looping through the same crash.
+//! 
+//! Writes made while rendering go through an [`EepromQueue`], which has to
+//! be serviced every frame with [`CrashLog::service`].
+
+use arduino_hal::Eeprom;
+use ufmt::{uwriteln, uWrite};
+
+use crate::eeprom_queue::EepromQueue;
+use crate::framebuffer::{FrameBuffer, Layer};
+use crate::text::Text;
+
+/// EEPROM offset of the crash log
+const BASE: u16 = 0;
+
+/// Number of records kept, after which the oldest is overwritten
+const NUM_RECORDS: u16 = 8;
+
+/// Size of a record in EEPROM, in bytes
+const RECORD_BYTES: u16 = 4;
+
+/// EEPROM offset of the index of the next record to write
+const NEXT: u16 = BASE;
+
+/// EEPROM offset of the first record
+const RECORDS: u16 = BASE + 1;
+
+/// EEPROM offset of the number of consecutive early crashes
+const EARLY_CRASHES: u16 = RECORDS + NUM_RECORDS * RECORD_BYTES;
+
+/// EEPROM bytes taken by the crash log
+#[allow(unused)]
+pub const EEPROM_BYTES: u16 = EARLY_CRASHES + 1 - BASE;
+
+/// Frames a run must last for a crash not to count as early
+pub const STABLE_FRAMES: u16 = 600;
+
+/// Consecutive early crashes after which the device boots into safe mode
+const SAFE_MODE_CRASHES: u8 = 3;
+
+/// `MCUSR` flag set by a power-on reset
+const PORF: u8 = 1 << 0;
+
+/// `MCUSR` flag set by a reset through the reset pin
+const EXTRF: u8 = 1 << 1;
+
+/// `MCUSR` flag set by a watchdog reset
+const WDRF: u8 = 1 << 3;
+
+/// `MCUSR` flags of resets recorded in the log
+const RECORDED_RESETS: u8 = !(PORF | EXTRF);
+
+/// Frame number written to records when the frame is unknown
+const UNKNOWN_FRAME: u16 = 0xffff;
+
+/// Number of the frame being rendered
+/// 
+/// Kept out of `.bss` so it survives watchdog and brown-out resets and can
+/// be recorded on the next boot.
+#[link_section = ".noinit"]
+static mut LAST_FRAME: u16 = 0;
+
+/// What went wrong
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ErrorCode {
+    /// No error, the device was reset
+    None = 0,
+
+    /// The firmware panicked
+    Panic = 1,
+
+    /// The supply voltage fell too low and the display was parked, see
+    /// the `brownout` module
+    LowSupply = 2,
+}
+
+/// One entry of the crash log
+#[derive(Copy, Clone)]
+pub struct CrashRecord {
+    /// `MCUSR` reset flags, or 0 if the record was not made on boot
+    pub reset_cause: u8,
+
+    /// Error code as an [`ErrorCode`]
+    pub error: u8,
+
+    /// Number of the frame being rendered, or `0xffff` if unknown
+    pub frame: u16,
+}
+
+impl CrashRecord {
+    fn to_bytes(self) -> [u8; RECORD_BYTES as usize] {
+        let frame = self.frame.to_le_bytes();
+        [self.reset_cause, self.error, frame[0], frame[1]]
+    }
+
+    fn from_bytes(bytes: [u8; RECORD_BYTES as usize]) -> Self {
+        Self {
+            reset_cause: bytes[0],
+            error: bytes[1],
+            frame: u16::from_le_bytes([bytes[2], bytes[3]]),
+        }
+    }
+
+    /// Whether this slot was never written since the EEPROM was erased
+    fn is_empty(&self) -> bool {
+        self.reset_cause == 0xff
+    }
+}
+
+/// Ring buffer of [`CrashRecord`]s in EEPROM
+pub struct CrashLog {
+    eeprom: EepromQueue,
+}
+
+impl CrashLog {
+    pub fn new(eeprom: Eeprom) -> Self {
+        Self { eeprom: EepromQueue::new(eeprom) }
+    }
+
+    /// Carry on with pending writes, called once per frame
+    pub fn service(&mut self) {
+        self.eeprom.service();
+    }
+
+    /// Index of the slot the next record is written to
+    fn next(&self) -> u16 {
+        match self.eeprom.read_byte(NEXT) as u16 {
+            next if next < NUM_RECORDS => next,
+            _ => 0,
+        }
+    }
+
+    fn read(&self, slot: u16) -> CrashRecord {
+        let offset = RECORDS + slot * RECORD_BYTES;
+        let mut bytes = [0; RECORD_BYTES as usize];
+        for (i, byte) in (offset..).zip(&mut bytes) {
+            *byte = self.eeprom.read_byte(i);
+        }
+        CrashRecord::from_bytes(bytes)
+    }
+
+    /// Append a record, overwriting the oldest once the log is full
+    pub fn record(&mut self, record: CrashRecord) {
+        let next = self.next();
+        let offset = RECORDS + next * RECORD_BYTES;
+        for (i, byte) in (offset..).zip(record.to_bytes()) {
+            self.eeprom.write_byte(i, byte);
+        }
+        self.eeprom.write_byte(NEXT, ((next + 1) % NUM_RECORDS) as u8);
+
+        if record.frame < STABLE_FRAMES {
+            let early = self.early_crashes().saturating_add(1);
+            self.eeprom.write_byte(EARLY_CRASHES, early);
+        }
+    }
+
+    /// Number of consecutive crashes within [`STABLE_FRAMES`] of boot
+    fn early_crashes(&self) -> u8 {
+        match self.eeprom.read_byte(EARLY_CRASHES) {
+            0xff => 0,
+            early => early,
+        }
+    }
+
+    /// Note that this run lasted [`STABLE_FRAMES`], which ends a series of
+    /// early crashes
+    pub fn mark_stable(&mut self) {
+        if self.early_crashes() != 0 {
+            self.eeprom.write_byte(EARLY_CRASHES, 0);
+        }
+    }
+
+    /// Whether the last runs crashed early often enough in a row to boot
+    /// into safe mode
+    pub fn safe_mode(&self) -> bool {
+        self.early_crashes() >= SAFE_MODE_CRASHES
+    }
+
+    /// Record the cause of the last reset if it was not a power-on or a
+    /// press of the reset button
+    /// 
+    /// Call once on boot with the contents of `MCUSR`.
+    pub fn record_reset(&mut self, mcusr: u8) {
+        if mcusr & RECORDED_RESETS == 0 {
+            return;
+        }
+
+        // Frame numbers do not survive a power cycle
+        let frame = if mcusr & PORF != 0 {
+            UNKNOWN_FRAME
+        } else {
+            // SAFETY: Only accessed from the main thread and the panic handler
+            unsafe { LAST_FRAME }
+        };
+
+        // A panic policy that resets makes a watchdog reset of its own, which
+        // is part of the panic already recorded, and so does recovering from
+        // a low supply
+        let after_error = self.last().is_some_and(|last| {
+            let error = last.error == ErrorCode::Panic as u8 || last.error == ErrorCode::LowSupply as u8;
+            error && last.frame == frame
+        });
+        if mcusr & RECORDED_RESETS == WDRF && after_error {
+            return;
+        }
+
+        self.record(CrashRecord {
+            reset_cause: mcusr,
+            error: ErrorCode::None as u8,
+            frame,
+        });
+
+        // Nothing is rendered yet, and a crash before the queue drains would
+        // lose the record
+        self.eeprom.flush();
+    }
+
+    /// Record that the supply fell too low to carry on, and write out
+    /// everything pending while the EEPROM can still be written safely
+    #[cfg(feature = "brownout")]
+    pub fn record_low_supply(&mut self) {
+        // SAFETY: Only accessed from the main thread and the panic handler
+        let frame = unsafe { LAST_FRAME };
+
+        self.record(CrashRecord {
+            reset_cause: 0,
+            error: ErrorCode::LowSupply as u8,
+            frame,
+        });
+        self.eeprom.flush();
+    }
+
+    /// Iterate over the records, oldest first
+    pub fn records(&self) -> impl Iterator<Item = CrashRecord> + '_ {
+        let next = self.next();
+        (0..NUM_RECORDS)
+            .map(move |i| self.read((next + i) % NUM_RECORDS))
+            .filter(|record| !record.is_empty())
+    }
+
+    /// Most recent record
+    pub fn last(&self) -> Option<CrashRecord> {
+        let last = (self.next() + NUM_RECORDS - 1) % NUM_RECORDS;
+        Some(self.read(last)).filter(|record| !record.is_empty())
+    }
+
+    /// Draw the reset cause and error code of the last record to the overlay
+    /// as a diagnostic code
+    /// 
+    /// Each is drawn as a row of eight bits in the top-left corner, most
+    /// significant first, with a block for a set bit and a dot for a clear
+    /// one.
+    pub fn draw_diagnostic(&self, frame: &mut FrameBuffer) {
+        let record = match self.last() {
+            Some(record) => record,
+            None => return,
+        };
+        for (row, byte) in [record.reset_cause, record.error].into_iter().enumerate() {
+            for bit in 0..8 {
+                let (x, y) = (bit * 4, row as u32 * 4);
+                let size = if byte & 0x80 >> bit != 0 { 3 } else { 1 };
+                for dy in 0..size {
+                    for dx in 0..size {
+                        frame.set_layer_pixel(Layer::Overlay, x + dx, y + dy);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Print every record to serial, oldest first
+    pub fn dump<W: uWrite>(&self, serial: &mut W) {
+        let _ = uwriteln!(serial, "{}", Text::CrashLog);
+        for record in self.records() {
+            let _ = uwriteln!(
+                serial,
+                "{} {:#x} {} {} {} {}",
+                Text::Reset,
+                record.reset_cause,
+                Text::Error,
+                record.error,
+                Text::Frame,
+                record.frame
+            );
+        }
+    }
+}
+
+/// Remember the number of the frame being rendered
+pub fn set_frame(frame: u16) {
+    // SAFETY: Only accessed from the main thread and the panic handler
+    unsafe {
+        LAST_FRAME = frame;
+    }
+}
+
+/// Record a panic, called from the panic handler
+pub fn record_panic() {
+    // SAFETY: The panicking code is abandoned, so nothing else uses the
+    // EEPROM from here on
+    let dp = unsafe { arduino_hal::Peripherals::steal() };
+
+    // SAFETY: Only accessed from the main thread and the panic handler
+    let frame = unsafe { LAST_FRAME };
+
+    let mut crash_log = CrashLog::new(Eeprom::new(dp.EEPROM));
+    crash_log.record(CrashRecord {
+        reset_cause: 0,
+        error: ErrorCode::Panic as u8,
+        frame,
+    });
+    crash_log.eeprom.flush();
+}
@@ -0,0 +1,182 @@
+#![cfg(feature = "scene-cycle")]
+
+//! Switching between scenes at runtime
+//! 
+//! The scenes listed in [`scenes!`] take turns, each shown for
+//! [`SCENE_STEPS`](crate::SCENE_STEPS) animation steps. Pulling the button
+//! pin low moves on to the next scene right away.
+//! 
+//! With the `scene-select` feature the scene select screen is open at start.
+//! A short press of the button highlights the next scene and holding it down
+//! starts that scene, and holding it down during a scene opens the screen
+//! again, see [`select`](crate::select).
+
+use arduino_hal::port::{Pin, mode::{Input, PullUp}};
+
+use crate::{NUM_SCENES, SCENE_STEPS};
+
+/// Animation steps at normal speed the button must be held down for a long
+/// press, with the `scene-select` feature
+/// 
+/// A second with `frame-pacing` at its default rate, and the same number of
+/// frames without it.
+#[cfg(feature = "scene-select")]
+const HOLD_STEPS: u8 = 30;
+
+/// What the button did this frame
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Press {
+    /// Pressed, or with the `scene-select` feature released before a long
+    /// press
+    Short,
+
+    /// Held down for [`HOLD_STEPS`]
+    #[cfg(feature = "scene-select")]
+    Long,
+}
+
+pub struct SceneCycle {
+    button: Pin<Input<PullUp>>,
+
+    /// Button level seen at the last update
+    was_high: bool,
+
+    /// Index of the scene being shown
+    index: usize,
+
+    /// Animation steps the scene has been shown for
+    steps: u16,
+
+    /// Whether to act on a press at the next update without the button, see
+    /// [`SceneCycle::skip`]
+    skip: bool,
+
+    /// Animation steps at normal speed the button has been held down for
+    #[cfg(feature = "scene-select")]
+    held: u8,
+
+    /// Whether the scene select screen is open, with [`SceneCycle::index`]
+    /// the scene highlighted
+    #[cfg(feature = "scene-select")]
+    selecting: bool,
+}
+
+impl SceneCycle {
+
+    /// Start at the first scene, or with the `scene-select` feature on the
+    /// scene select screen
+    pub fn new(button: Pin<Input<PullUp>>) -> Self {
+        Self {
+            was_high: button.is_high(),
+            button,
+            index: 0,
+            steps: 0,
+            skip: false,
+            #[cfg(feature = "scene-select")]
+            held: 0,
+            #[cfg(feature = "scene-select")]
+            selecting: true,
+        }
+    }
+
+    /// Index of the scene being shown
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Show the scene at `index` instead of the first, or with the
+    /// `scene-select` feature highlight it
+    pub fn start_at(&mut self, index: usize) {
+        self.index = index % NUM_SCENES;
+    }
+
+    /// Whether the scene select screen is open
+    #[cfg(feature = "scene-select")]
+    pub fn selecting(&self) -> bool {
+        self.selecting
+    }
+
+    /// Act on a short press of the button at the next update, such as one
+    /// sent over the serial console
+    pub fn skip(&mut self) {
+        self.skip = true;
+    }
+
+    /// Advance by `steps` animation steps, once per frame
+    /// 
+    /// `elapsed` is the steps the frame took at normal speed, whether the
+    /// animation is paused or sped up, which time how long the button is held.
+    /// 
+    /// Returns `true` if the scene changed, on a press of the button or once
+    /// the scene has been shown long enough, or with the `scene-select`
+    /// feature when a scene is started from the scene select screen.
+    pub fn update(
+        &mut self,
+        steps: u8,
+        #[cfg_attr(not(feature = "scene-select"), allow(unused_variables))] elapsed: u8,
+    ) -> bool {
+        let press = self.press(elapsed);
+        let press = if core::mem::take(&mut self.skip) { Some(Press::Short) } else { press };
+
+        #[cfg(feature = "scene-select")]
+        if self.selecting {
+            match press {
+                Some(Press::Short) => self.index = (self.index + 1) % NUM_SCENES,
+                Some(Press::Long) => {
+                    self.selecting = false;
+                    self.steps = 0;
+                    return true;
+                }
+                None => {}
+            }
+            return false;
+        }
+
+        #[cfg(feature = "scene-select")]
+        if press == Some(Press::Long) {
+            self.selecting = true;
+            return false;
+        }
+
+        self.steps = self.steps.saturating_add(steps as u16);
+        if press != Some(Press::Short) && (SCENE_STEPS == 0 || self.steps < SCENE_STEPS) {
+            return false;
+        }
+        self.steps = 0;
+        self.index = (self.index + 1) % NUM_SCENES;
+        true
+    }
+
+    /// Read the button, once per frame, which is slow enough to ride out
+    /// contact bounce
+    /// 
+    /// A short press counts on the falling edge of the button.
+    #[cfg(not(feature = "scene-select"))]
+    fn press(&mut self, _elapsed: u8) -> Option<Press> {
+        let high = self.button.is_high();
+        let pressed = self.was_high && !high;
+        self.was_high = high;
+        pressed.then_some(Press::Short)
+    }
+
+    /// Read the button, once per frame, which is slow enough to ride out
+    /// contact bounce
+    /// 
+    /// A short press counts on the rising edge of the button, so that it is
+    /// not mistaken for the start of a long press, and a long press once it
+    /// has been held down for [`HOLD_STEPS`] over `elapsed` steps this frame.
+    #[cfg(feature = "scene-select")]
+    fn press(&mut self, elapsed: u8) -> Option<Press> {
+        let high = self.button.is_high();
+        let released = !self.was_high && high;
+        self.was_high = high;
+
+        if !high {
+            let was_held = self.held;
+            self.held = self.held.saturating_add(elapsed);
+            return (was_held < HOLD_STEPS && self.held >= HOLD_STEPS).then_some(Press::Long);
+        }
+        let held = core::mem::replace(&mut self.held, 0);
+        (released && held < HOLD_STEPS).then_some(Press::Short)
+    }
+}
@@ -0,0 +1,96 @@
+//! EEPROM writes spread over frames
+//! 
+//! Writing an EEPROM byte takes about 3.3 ms, during which the next write
+//! has to wait. [`EepromQueue`] holds writes back and starts at most one per
+//! frame from [`EepromQueue::service`], once the previous one has finished,
+//! so persisting data never holds up rendering.
+
+use arduino_hal::Eeprom;
+
+/// Number of writes that can be pending
+/// 
+/// Writing while the queue is full waits for the oldest write instead.
+const QUEUE_LEN: usize = 16;
+
+pub struct EepromQueue {
+    eeprom: Eeprom,
+
+    /// Pending writes as offset and byte, oldest first from `start`
+    pending: [(u16, u8); QUEUE_LEN],
+    start: u8,
+    len: u8,
+}
+
+impl EepromQueue {
+    pub fn new(eeprom: Eeprom) -> Self {
+        Self {
+            eeprom,
+            pending: [(0, 0); QUEUE_LEN],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Position in `pending` of the `i`th pending write
+    fn slot(&self, i: u8) -> usize {
+        (self.start as usize + i as usize) % QUEUE_LEN
+    }
+
+    /// Read a byte, as it will be once pending writes are done
+    pub fn read_byte(&self, offset: u16) -> u8 {
+        (0..self.len)
+            .map(|i| self.pending[self.slot(i)])
+            .find(|&(pending, _)| pending == offset)
+            .map_or_else(|| self.eeprom.read_byte(offset), |(_, byte)| byte)
+    }
+
+    /// Queue a byte to be written
+    /// 
+    /// A pending write to the same offset is replaced rather than written
+    /// twice.
+    pub fn write_byte(&mut self, offset: u16, byte: u8) {
+        if let Some(i) = (0..self.len).find(|&i| self.pending[self.slot(i)].0 == offset) {
+            let slot = self.slot(i);
+            self.pending[slot].1 = byte;
+            return;
+        }
+        if self.len as usize == QUEUE_LEN {
+            self.write_next();
+        }
+        let slot = self.slot(self.len);
+        self.pending[slot] = (offset, byte);
+        self.len += 1;
+    }
+
+    /// Start the oldest pending write if the EEPROM is idle
+    /// 
+    /// Call once per frame.
+    pub fn service(&mut self) {
+        if self.len != 0 && idle() {
+            self.write_next();
+        }
+    }
+
+    /// Write everything pending, waiting for each write in turn
+    pub fn flush(&mut self) {
+        while self.len != 0 {
+            self.write_next();
+        }
+    }
+
+    /// Write the oldest pending byte, waiting for the previous write first
+    fn write_next(&mut self) {
+        let (offset, byte) = self.pending[self.slot(0)];
+        self.eeprom.write_byte(offset, byte);
+        self.start = self.slot(1) as u8;
+        self.len -= 1;
+    }
+}
+
+/// Whether no EEPROM write is in progress
+fn idle() -> bool {
+    // SAFETY: Only reads the control register, which the HAL never relies on
+    // keeping unchanged
+    let dp = unsafe { arduino_hal::Peripherals::steal() };
+    dp.EEPROM.eecr.read().eepe().bit_is_clear()
+}
@@ -0,0 +1,40 @@
+#![cfg(any(feature = "fps", feature = "fps-overlay"))]
+
+use crate::clock;
+use super::CLOCK_FREQ;
+
+pub struct FpsCounter {
+    count: u16,
+
+    /// Cycle count at the start of the current second
+    start: u32,
+}
+
+impl FpsCounter {
+
+    /// Create a new FPS Counter
+    /// 
+    /// The cycle counter must already be started, see [`clock::start`]
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            start: clock::now(),
+        }
+    }
+
+    /// Update the FPS Counter
+    /// 
+    /// Will reset and return the count once a second has passed
+    pub fn update(&mut self) -> Option<u16> {
+        self.count += 1;
+
+        let now = clock::now();
+        if now.wrapping_sub(self.start) < CLOCK_FREQ {
+            return None;
+        }
+        self.start = now;
+        let count = self.count;
+        self.count = 0;
+        Some(count)
+    }
+}
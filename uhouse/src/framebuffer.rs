@@ -0,0 +1,488 @@
+//! Framebuffer with dirty-region tracking
+//! 
+//! The SSD1306 driver's buffered mode clears and resends all of its buffer
+//! every frame. A wireframe usually covers only part of the screen, so this
+//! framebuffer remembers the region drawn each frame, erases only that region
+//! before the next one and sends the display only what changed.
+//! 
+//! Changes are tracked in chunks of [`CHUNK_COLUMNS`] columns of each page,
+//! so that a wireframe spanning the screen diagonally is sent as a staircase
+//! of short transfers rather than its whole bounding box.
+//! 
+//! With the `erase-list` feature the exact bytes written each frame are
+//! recorded as runs instead, so a sparse wireframe spanning the screen
+//! diagonally is erased without clearing its whole bounding box.
+
+use display_interface::DisplayError;
+use ssd1306::{Ssd1306, mode::BasicMode, prelude::*};
+
+use crate::Display;
+
+#[cfg(feature = "async-flush")]
+use crate::twiflush::AsyncFlush;
+
+const WIDTH: usize = Display::WIDTH as usize;
+const PAGES: usize = Display::HEIGHT as usize / 8;
+
+/// Framebuffer size in bytes, one bit per pixel
+pub const FRAME_BYTES: usize = WIDTH * PAGES;
+
+/// Columns of a page tracked by one bit of a [`DirtyPages`] mask
+const CHUNK_COLUMNS: usize = 8;
+
+/// Chunks in a page
+const CHUNKS: usize = WIDTH / CHUNK_COLUMNS;
+
+const _: () = assert!(WIDTH % CHUNK_COLUMNS == 0, "the screen width must be a whole number of chunks");
+const _: () = assert!(CHUNKS <= u16::BITS as usize, "a page has more chunks than its dirty mask can hold");
+
+/// Bounding box of touched bytes, in columns and 8-pixel pages
+#[derive(Copy, Clone)]
+pub struct Region {
+    min_x: u8,
+    max_x: u8,
+    min_page: u8,
+    max_page: u8,
+}
+
+impl Region {
+
+    /// The region containing nothing
+    pub const EMPTY: Self = Self {
+        min_x: u8::MAX,
+        max_x: 0,
+        min_page: u8::MAX,
+        max_page: 0,
+    };
+
+    /// Whether the region contains nothing
+    pub fn is_empty(&self) -> bool {
+        self.min_x > self.max_x || self.min_page > self.max_page
+    }
+
+    /// Grow the region to contain a byte
+    pub fn include(&mut self, x: u8, page: u8) {
+        self.min_x = self.min_x.min(x);
+        self.max_x = self.max_x.max(x);
+        self.min_page = self.min_page.min(page);
+        self.max_page = self.max_page.max(page);
+    }
+
+    /// Whether the region contains a byte
+    #[cfg(feature = "erase-list")]
+    pub fn contains(&self, x: u8, page: u8) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_page..=self.max_page).contains(&page)
+    }
+
+    /// Whether the regions share any byte
+    pub fn intersects(&self, other: Self) -> bool {
+        self.min_x.max(other.min_x) <= self.max_x.min(other.max_x)
+            && self.min_page.max(other.min_page) <= self.max_page.min(other.max_page)
+    }
+}
+
+/// Bytes changed since the last flush, as a mask for every page with bit `i`
+/// standing for chunk `i` of [`CHUNK_COLUMNS`] columns
+#[derive(Copy, Clone)]
+struct DirtyPages([u16; PAGES]);
+
+impl DirtyPages {
+    const CLEAN: Self = Self([0; PAGES]);
+
+    const ALL: Self = Self([((1u32 << CHUNKS) - 1) as u16; PAGES]);
+
+    /// Mark the byte at column `x` of `page` as changed
+    fn mark(&mut self, x: usize, page: usize) {
+        self.0[page] |= 1 << (x / CHUNK_COLUMNS);
+    }
+
+    /// Chunks of `page` to send
+    /// 
+    /// A single clean chunk between dirty ones is sent along with them, as
+    /// starting another transfer costs about as much as sending the chunk.
+    fn to_send(&self, page: usize) -> u16 {
+        let mask = self.0[page];
+        mask | (mask << 1 & mask >> 1)
+    }
+
+    /// Call `f` with every region to send, stopping at the first error
+    /// 
+    /// Each run of chunks in a page is a region, and consecutive pages with
+    /// the same runs are merged into one.
+    fn for_each_region<F>(&self, mut f: F) -> Result<(), DisplayError>
+    where
+        F: FnMut(Region) -> Result<(), DisplayError>,
+    {
+        let mut page = 0;
+        while page < PAGES {
+            let mask = self.to_send(page);
+            let mut end = page + 1;
+            while end < PAGES && self.to_send(end) == mask {
+                end += 1;
+            }
+            for (first, last) in runs(mask) {
+                f(Region {
+                    min_x: first * CHUNK_COLUMNS as u8,
+                    max_x: last * CHUNK_COLUMNS as u8 + (CHUNK_COLUMNS - 1) as u8,
+                    min_page: page as u8,
+                    max_page: (end - 1) as u8,
+                })?;
+            }
+            page = end;
+        }
+        Ok(())
+    }
+}
+
+/// Runs of set bits in a mask, as the first and last bit of each
+fn runs(mut mask: u16) -> impl Iterator<Item = (u8, u8)> {
+    core::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let first = mask.trailing_zeros();
+        let len = (!(mask >> first)).trailing_zeros();
+        mask &= !((((1u32 << len) - 1) << first) as u16);
+        Some((first as u8, (first + len - 1) as u8))
+    })
+}
+
+/// Maximum number of runs in the erase list
+#[cfg(feature = "erase-list")]
+const ERASE_RUNS: usize = 96;
+
+/// Consecutive framebuffer bytes written in a frame
+#[cfg(feature = "erase-list")]
+#[derive(Copy, Clone)]
+struct Run {
+    start: u16,
+    len: u8,
+}
+
+/// Bounded list of runs of bytes written since the last clear
+#[cfg(feature = "erase-list")]
+struct EraseList {
+    runs: [Run; ERASE_RUNS],
+    count: u8,
+
+    /// Set when a run did not fit, falling back to clearing the drawn region
+    overflowed: bool,
+}
+
+#[cfg(feature = "erase-list")]
+impl EraseList {
+    const fn new() -> Self {
+        Self {
+            runs: [Run { start: 0, len: 0 }; ERASE_RUNS],
+            count: 0,
+            overflowed: false,
+        }
+    }
+
+    /// Record a byte written since the last clear, unless it ends the last
+    /// run already
+    fn push(&mut self, idx: u16) {
+        let count = self.count as usize;
+        if let Some(last) = self.runs[..count].last_mut() {
+            let end = last.start + last.len as u16;
+            if idx + 1 == end {
+                return;
+            }
+            if end == idx && last.len < u8::MAX {
+                last.len += 1;
+                return;
+            }
+        }
+        if count < ERASE_RUNS {
+            self.runs[count] = Run { start: idx, len: 1 };
+            self.count += 1;
+        } else {
+            self.overflowed = true;
+        }
+    }
+}
+
+/// Independently drawn and cleared part of the screen
+/// 
+/// Each layer tracks its own drawn region, so the scene can be erased every
+/// frame while an overlay that changes once a second is left alone.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Layer {
+    /// The 3D scene, redrawn every frame
+    Scene,
+
+    /// HUD text and other overlays, redrawn only when they change
+    #[allow(unused)]
+    Overlay,
+}
+
+const NUM_LAYERS: usize = 2;
+
+/// Dirty tracking of one [`Layer`]
+#[derive(Copy, Clone)]
+struct LayerRegions {
+    /// Pixels drawn since the last clear
+    drawn: Region,
+
+    /// Set when clearing another layer erased part of this one
+    damaged: bool,
+}
+
+impl LayerRegions {
+    const EMPTY: Self = Self {
+        drawn: Region::EMPTY,
+        damaged: false,
+    };
+}
+
+pub struct FrameBuffer {
+    buffer: [u8; FRAME_BYTES],
+
+    /// Bytes written to the scene since the last clear
+    #[cfg(feature = "erase-list")]
+    erase_list: EraseList,
+
+    layers: [LayerRegions; NUM_LAYERS],
+
+    /// Bytes changed since the last flush
+    dirty: DirtyPages,
+}
+
+impl FrameBuffer {
+
+    /// Create an empty framebuffer
+    /// 
+    /// The display contents are unknown at this point, so the first flush
+    /// sends the whole screen.
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; FRAME_BYTES],
+            #[cfg(feature = "erase-list")]
+            erase_list: EraseList::new(),
+            layers: [LayerRegions::EMPTY; NUM_LAYERS],
+            dirty: DirtyPages::ALL,
+        }
+    }
+
+    /// Turn a pixel of the scene on
+    /// 
+    /// Pixels outside of the screen are ignored.
+    pub fn set_pixel(&mut self, x: u32, y: u32) {
+        self.set_layer_pixel(Layer::Scene, x, y);
+    }
+
+    /// Turn a pixel of a layer on
+    /// 
+    /// Pixels outside of the screen are ignored.
+    pub fn set_layer_pixel(&mut self, layer: Layer, x: u32, y: u32) {
+        if x as usize >= WIDTH {
+            return;
+        }
+        let page = y >> 3;
+        let idx = page as usize * WIDTH + x as usize;
+        if let Some(byte) = self.buffer.get_mut(idx) {
+            let bit = 1 << (y & 7);
+
+            // A byte the scene has not written since the last clear is empty
+            // or holds overlay bits, so a byte under the overlay is recorded
+            // for every bit the scene adds to it
+            #[cfg(feature = "erase-list")]
+            if layer == Layer::Scene
+                && *byte & bit == 0
+                && (*byte == 0 || self.layers[Layer::Overlay as usize].drawn.contains(x as u8, page as u8))
+            {
+                self.erase_list.push(idx as u16);
+            }
+
+            if *byte & bit == 0 {
+                *byte |= bit;
+                self.dirty.mark(x as usize, page as usize);
+            }
+            self.layers[layer as usize].drawn.include(x as u8, page as u8);
+        }
+    }
+
+    /// Turn a pixel of the scene off
+    /// 
+    /// Pixels outside of the screen are ignored. Only pixels drawn since the
+    /// last clear need turning off, so the drawn region is left as it is.
+    #[cfg(feature = "filled")]
+    pub fn clear_pixel(&mut self, x: u32, y: u32) {
+        if x as usize >= WIDTH {
+            return;
+        }
+        let page = y >> 3;
+        let idx = page as usize * WIDTH + x as usize;
+        if let Some(byte) = self.buffer.get_mut(idx) {
+            let bit = 1 << (y & 7);
+            if *byte & bit != 0 {
+                *byte &= !bit;
+                self.dirty.mark(x as usize, page as usize);
+            }
+        }
+    }
+
+    /// Whether a pixel is on
+    /// 
+    /// Pixels outside of the screen are off.
+    #[allow(unused)]
+    pub fn pixel(&self, x: u32, y: u32) -> bool {
+        if x as usize >= WIDTH {
+            return false;
+        }
+        let idx = (y >> 3) as usize * WIDTH + x as usize;
+        self.buffer.get(idx).is_some_and(|byte| byte & 1 << (y & 7) != 0)
+    }
+
+    /// Raw contents in display order
+    /// 
+    /// Each byte is a column of eight pixels with the top one in the least
+    /// significant bit, and each run of one screen width of bytes is a page of
+    /// rows, topmost first.
+    #[allow(unused)]
+    pub fn bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Update a CRC-16/CCITT-FALSE checksum with the framebuffer contents
+    /// 
+    /// Start with `0xffff`, or chain several frames by passing in the CRC
+    /// of the previous one.
+    #[allow(unused)]
+    pub fn crc(&self, mut crc: u16) -> u16 {
+        for &byte in &self.buffer {
+            crc = uhouse_core::validate::crc16_update(crc, byte);
+        }
+        crc
+    }
+
+    /// Erase what was drawn to the scene since the last clear
+    pub fn clear(&mut self) {
+        self.clear_layer(Layer::Scene);
+    }
+
+    /// Erase what was drawn to a layer since the last clear
+    /// 
+    /// Only the bytes within the drawn region are touched, or for the scene
+    /// with the `erase-list` feature only the bytes written unless the list
+    /// overflowed. Other layers sharing those bytes are marked as damaged.
+    pub fn clear_layer(&mut self, layer: Layer) {
+        let region = self.layers[layer as usize].drawn;
+
+        #[cfg(feature = "erase-list")]
+        let region_clear = layer != Layer::Scene || {
+            let list = &mut self.erase_list;
+            if !list.overflowed {
+                for run in &list.runs[..list.count as usize] {
+                    let start = run.start as usize;
+                    for (idx, byte) in (start..).zip(&mut self.buffer[start..start + run.len as usize]) {
+                        *byte = 0;
+                        self.dirty.mark(idx % WIDTH, idx / WIDTH);
+                    }
+                }
+            }
+            let overflowed = list.overflowed;
+            *list = EraseList::new();
+            overflowed
+        };
+
+        #[cfg(not(feature = "erase-list"))]
+        let region_clear = true;
+
+        if region_clear && !region.is_empty() {
+            let columns = region.min_x as usize..=region.max_x as usize;
+            for page in region.min_page as usize..=region.max_page as usize {
+                let row = page * WIDTH;
+                for (x, byte) in columns.clone().zip(&mut self.buffer[row..row + WIDTH][columns.clone()]) {
+                    if *byte != 0 {
+                        *byte = 0;
+                        self.dirty.mark(x, page);
+                    }
+                }
+            }
+        }
+
+        for (i, other) in self.layers.iter_mut().enumerate() {
+            if i != layer as usize && other.drawn.intersects(region) {
+                other.damaged = true;
+            }
+        }
+
+        let regions = &mut self.layers[layer as usize];
+        regions.drawn = Region::EMPTY;
+        regions.damaged = false;
+    }
+
+    /// Whether clearing another layer erased part of this layer, which then
+    /// needs to be cleared and redrawn
+    #[allow(unused)]
+    pub fn is_damaged(&self, layer: Layer) -> bool {
+        self.layers[layer as usize].damaged
+    }
+
+    /// Send every byte changed since the last flush to the display
+    /// 
+    /// Each run of dirty chunks in a page is sent as its own transfer, and
+    /// consecutive pages dirty in the same chunks are sent together. If
+    /// sending fails every byte is kept dirty, to be sent again by the next
+    /// flush.
+    pub fn flush<DI>(&mut self, display: &mut Ssd1306<DI, Display, BasicMode>) -> Result<(), DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+    {
+        self.dirty.for_each_region(|region| self.flush_region(display, region))?;
+        self.dirty = DirtyPages::CLEAN;
+        Ok(())
+    }
+
+    /// Stage every byte changed since the last flush and start sending them
+    /// from the TWI interrupt, see [`twiflush`](crate::twiflush)
+    /// 
+    /// Waits for the last flush to finish first. The framebuffer can be
+    /// drawn to again as soon as this returns. As with [`Self::flush`], the
+    /// bytes stay dirty if staging them fails.
+    #[cfg(feature = "async-flush")]
+    pub fn flush_async(&mut self, flush: &mut AsyncFlush) -> Result<(), DisplayError> {
+        flush.wait()?;
+        self.dirty.for_each_region(|region| {
+            // The same draw area the driver sets for a blocking flush
+            let first_row = (region.min_page << 3) + Display::OFFSETY;
+            let last_row = ((region.max_page + 1) << 3) - 1 + Display::OFFSETY;
+            flush.commands(&[
+                0x21, region.min_x + Display::OFFSETX, region.max_x + Display::OFFSETX,
+                0x22, first_row >> 3, last_row >> 3,
+            ])?;
+
+            let columns = region.min_x as usize..=region.max_x as usize;
+            for page in region.min_page as usize..=region.max_page as usize {
+                flush.data(&self.buffer[page * WIDTH..(page + 1) * WIDTH][columns.clone()])?;
+            }
+            Ok(())
+        })?;
+        flush.start();
+        self.dirty = DirtyPages::CLEAN;
+        Ok(())
+    }
+
+    /// Send one region of the framebuffer to the display
+    fn flush_region<DI>(
+        &self,
+        display: &mut Ssd1306<DI, Display, BasicMode>,
+        region: Region,
+    ) -> Result<(), DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+    {
+        // Column and page ends are exclusive
+        let start = (region.min_x, region.min_page << 3);
+        let end = (region.max_x + 1, (region.max_page + 1) << 3);
+
+        display.set_draw_area(
+            (start.0 + Display::OFFSETX, start.1 + Display::OFFSETY),
+            (end.0 + Display::OFFSETX, end.1 + Display::OFFSETY),
+        )?;
+
+        // Page range passed to the driver is inclusive
+        display.bounded_draw(&self.buffer, WIDTH, start, (end.0, region.max_page << 3))
+    }
+}
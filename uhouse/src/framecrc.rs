@@ -0,0 +1,53 @@
+#![cfg(feature = "frame-crc")]
+
+//! Frame checksums over serial
+//! 
+//! Prints the CRC-16/CCITT-FALSE of every completed frame, one line each as
+//! `crc <frame> <crc> <steps>`, with the frame number counting from 0 at
+//! power on and the animation steps the frame advanced by. The simulator
+//! prints the same lines with `--crc`, and `uhouse-run --diverge` runs it in
+//! lock-step with the board to find the first frame where the two differ.
+//! 
+//! With `frame-pacing`, the steps vary with how long frames take, so a
+//! capture of these lines is replayed with the simulator's `--replay`
+//! instead, stepping the animation as the board did.
+
+use ufmt::{uwriteln, uWrite};
+
+use crate::framebuffer::FrameBuffer;
+use crate::hooks::FrameHooks;
+
+pub struct FrameCrc<W: uWrite> {
+    serial: W,
+
+    /// Number of the current frame, counting from 0 at power on
+    frame: u32,
+
+    /// Animation steps the current frame advanced by
+    steps: u8,
+}
+
+impl<W> FrameCrc<W> where W: uWrite {
+
+    /// Create a frame checksum reporter
+    /// 
+    /// This takes full ownership of the serial device, so it cannot be used
+    /// together with any other feature using serial
+    pub fn new(serial: W) -> Self {
+        Self { serial, frame: 0, steps: 1 }
+    }
+}
+
+impl<W> FrameHooks for FrameCrc<W> where W: uWrite {
+
+    /// Remember the animation steps of the frame for its report
+    fn post_update(&mut self, steps: u8) {
+        self.steps = steps;
+    }
+
+    /// Print the checksum of the completed frame
+    fn pre_flush(&mut self, frame: &mut FrameBuffer) {
+        let _ = uwriteln!(self.serial, "crc {} {:#x} {}", self.frame, frame.crc(0xffff), self.steps);
+        self.frame = self.frame.wrapping_add(1);
+    }
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "frame-stream")]
+
+//! Compressed framebuffer streaming over serial
+//! 
+//! Every frame is sent run-length encoded, which shrinks the mostly empty
+//! frames of a wireframe from 1 KiB to a few hundred bytes so a host can
+//! watch the animation at 57600 baud. `scripts/decode-stream.py` turns the
+//! stream back into images.
+//! 
+//! Each frame is sent as the four bytes `UHF1`, the framebuffer bytes in
+//! display order encoded as in [`rle`], then the CRC-16/CCITT-FALSE of the
+//! decoded framebuffer in little endian.
+//! 
+//! Every frame is encoded whole. Encoding the difference to the last frame
+//! would need a second framebuffer to hold that frame, which does not fit
+//! in RAM beside the first.
+
+use embedded_hal::serial::Write;
+use uhouse_core::rle;
+
+use crate::framebuffer::FrameBuffer;
+use crate::hooks::FrameHooks;
+
+/// Marks the start of a frame
+const MAGIC: &[u8; 4] = b"UHF1";
+
+pub struct FrameStream<W: Write<u8>> {
+    serial: W,
+}
+
+impl<W> FrameStream<W> where W: Write<u8> {
+
+    /// Create a frame stream
+    /// 
+    /// This takes full ownership of the serial device, so it cannot be used
+    /// together with any other feature using serial
+    pub fn new(serial: W) -> Self {
+        Self { serial }
+    }
+
+    /// Send one byte, waiting for room in the transmitter
+    fn send(&mut self, byte: u8) {
+        while self.serial.write(byte).is_err() {}
+    }
+}
+
+impl<W> FrameHooks for FrameStream<W> where W: Write<u8> {
+
+    /// Send the completed frame
+    fn pre_flush(&mut self, frame: &mut FrameBuffer) {
+        MAGIC.iter().for_each(|&byte| self.send(byte));
+        rle::encode(frame.bytes(), |byte| self.send(byte));
+        frame.crc(0xffff).to_le_bytes().iter().for_each(|&byte| self.send(byte));
+    }
+}
@@ -0,0 +1,40 @@
+//! Per-frame hook points
+//! 
+//! Implement [`FrameHooks`] to inject custom logic or drawing into the main
+//! loop without changing it, then pass the implementation in place of
+//! [`NoHooks`] in `main`.
+
+use uhouse_core::mesh::ScreenVert;
+
+use crate::framebuffer::FrameBuffer;
+
+/// Callbacks made by the main loop each frame
+/// 
+/// Every method has an empty default, so implementations only provide the
+/// hooks they need and unused ones compile away.
+pub trait FrameHooks {
+
+    /// Called at the start of every frame, before the animation advances
+    fn pre_update(&mut self) {}
+
+    /// Called once the number of animation steps due this frame is known
+    fn post_update(&mut self, _steps: u8) {}
+
+    /// Called after the mesh is transformed, with its screen-space vertices
+    /// 
+    /// Not called when last frame's vertices are reused or in streaming mode,
+    /// where no full set of transformed vertices exists. Vertices in front of
+    /// the near plane are at [`BEHIND_NEAR`](uhouse_core::clip::BEHIND_NEAR).
+    /// With the `subpixel` feature, vertices are in sixteenths of a pixel and
+    /// those in front of the near plane at
+    /// [`SUBPIXEL_BEHIND_NEAR`](uhouse_core::clip::SUBPIXEL_BEHIND_NEAR).
+    fn post_transform(&mut self, _screen_verts: &mut [ScreenVert]) {}
+
+    /// Called after the scene is drawn, before the framebuffer is flushed
+    fn pre_flush(&mut self, _frame: &mut FrameBuffer) {}
+}
+
+/// Hooks that do nothing
+pub struct NoHooks;
+
+impl FrameHooks for NoHooks {}
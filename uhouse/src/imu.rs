@@ -0,0 +1,165 @@
+#![cfg(feature = "imu")]
+
+//! Camera control by tilting the board, with an MPU6050
+//! 
+//! An MPU6050 module on the I2C bus, sharing it with the display, replaces
+//! the canned animation. Its accelerometer is read every frame and low-pass
+//! filtered into the direction of gravity. Tilting the board left and right
+//! sends the house around its orbit, faster the further it is tilted, and
+//! tilting it towards and away from you pitches the house the same way.
+//! 
+//! Mount the module flat, with its X axis pointing to the right of the
+//! display and its Y axis to the top.
+
+use core::cell::RefCell;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use uhouse_core::rotor::Rotor;
+use uhouse_core::units::Raw;
+use uhouse_core::vec::*;
+
+/// Address of the MPU6050 with its AD0 pin low
+const ADDRESS: u8 = 0x68;
+
+/// Register taking the MPU6050 out of sleep, which it starts in
+const PWR_MGMT_1: u8 = 0x6B;
+
+/// Register of the digital low-pass filter setting
+const CONFIG: u8 = 0x1A;
+
+/// Setting of the digital low-pass filter, 44 Hz for the accelerometer
+const DLPF_44HZ: u8 = 3;
+
+/// First of the six accelerometer registers, X, Y and Z, each big-endian
+const ACCEL_XOUT_H: u8 = 0x3B;
+
+/// Shift from the accelerometer's 16384 per g at its default range of 2 g
+/// to 4096 per g, one in [`IFixed`] fixed point
+const ACCEL_SHIFT: u8 = 2;
+
+/// Each frame the filtered gravity moves one `1 << FILTER_SHIFT`th of the
+/// way to the new reading
+const FILTER_SHIFT: u8 = 3;
+
+/// Sideways tilt ignored as a board held by hand, as the sine of the tilt
+/// 
+/// About 6 degrees.
+const DEAD_ZONE: IFixed = 0x1a0;
+
+/// Sideways tilt orbiting at [`MAX_ORBIT_SPEED`], as the sine of the tilt
+/// 
+/// 45 degrees.
+const FULL_TILT: IFixed = 0xb50;
+
+/// Angle orbited per animation step at [`FULL_TILT`] or more, in 65536ths of
+/// a turn
+/// 
+/// 3 degrees.
+const MAX_ORBIT_SPEED: i32 = 546;
+
+/// Handle on an I2C bus shared between the display and the IMU
+/// 
+/// Both only use the bus from the main loop, one after the other, so
+/// borrowing it for each transaction never clashes.
+pub struct SharedI2c<'a, I>(&'a RefCell<I>);
+
+impl<'a, I> SharedI2c<'a, I> {
+    pub fn new(bus: &'a RefCell<I>) -> Self {
+        Self(bus)
+    }
+}
+
+impl<I: Write> Write for SharedI2c<'_, I> {
+    type Error = I::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write(address, bytes)
+    }
+}
+
+impl<I: WriteRead> WriteRead for SharedI2c<'_, I> {
+    type Error = I::Error;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write_read(address, bytes, buffer)
+    }
+}
+
+pub struct Imu<I> {
+    i2c: I,
+
+    /// Set once the MPU6050 has been woken up and set up
+    awake: bool,
+
+    /// Filtered direction of gravity, in g
+    gravity: Vec3<Raw>,
+
+    /// Accumulated orbit angle, in 65536ths of a turn
+    angle: u16,
+}
+
+impl<I: Write + WriteRead> Imu<I> {
+
+    /// Start with the board taken as lying flat
+    /// 
+    /// The MPU6050 is set up on the first [`update`](Self::update), and
+    /// again on any after a failed read, so one plugged in late or
+    /// reconnected is picked up.
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            awake: false,
+            gravity: vec3!(0, 0, 0x1000),
+            angle: 0,
+        }
+    }
+
+    /// Read the accelerometer and orbit the camera by `steps` animation steps
+    /// 
+    /// If the MPU6050 does not answer, the camera stays where it is.
+    pub fn update(&mut self, steps: u8) {
+        if !self.awake {
+            self.awake = self.i2c.write(ADDRESS, &[PWR_MGMT_1, 0]).is_ok()
+                && self.i2c.write(ADDRESS, &[CONFIG, DLPF_44HZ]).is_ok();
+        }
+
+        let mut bytes = [0; 6];
+        if !self.awake || self.i2c.write_read(ADDRESS, &[ACCEL_XOUT_H], &mut bytes).is_err() {
+            self.awake = false;
+            return;
+        }
+
+        let axis = |i: usize| i16::from_be_bytes([bytes[2*i], bytes[2*i + 1]]) >> ACCEL_SHIFT;
+        let filter = |filtered: IFixed, reading: IFixed| filtered + ((reading - filtered) >> FILTER_SHIFT);
+        self.gravity = vec3!(
+            filter(self.gravity.x, axis(0)),
+            filter(self.gravity.y, axis(1)),
+            filter(self.gravity.z, axis(2))
+        );
+
+        let tilt = self.gravity.x;
+        if tilt.abs() > DEAD_ZONE {
+            let speed = ((tilt.abs() - DEAD_ZONE) as i32 * MAX_ORBIT_SPEED
+                / (FULL_TILT - DEAD_ZONE) as i32).min(MAX_ORBIT_SPEED) as u16;
+            let speed = speed.wrapping_mul(steps as u16);
+            self.angle = if tilt < 0 {
+                self.angle.wrapping_sub(speed)
+            } else {
+                self.angle.wrapping_add(speed)
+            };
+        }
+    }
+
+    /// Rotation vector of the orbit
+    pub fn orbit(&self) -> Rotor {
+        Rotor::from_angle(self.angle)
+    }
+
+    /// Rotation vector pitching the house as far as the board is tilted
+    /// towards or away from you
+    pub fn pitch(&self) -> Rotor {
+        let (z, y) = (self.gravity.z as i32, self.gravity.y as i32);
+        let length = Vec2::<Raw>::new(self.gravity.z, self.gravity.y).length().max(1) as i32;
+        Rotor::from_vec2(vec2!((z*0x1000 / length) as IFixed, (y*0x1000 / length) as IFixed))
+    }
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "isr-budget")]
+
+//! Worst-case duration of every interrupt handler
+//! 
+//! Interrupt handlers run with every other interrupt held off, so one that
+//! takes too long delays the rest, such as the timer frame pacing counts
+//! milliseconds with or the TWI interrupt feeding the display. With the
+//! `isr-budget` feature every handler wrapped in [`isr_budget!`] is timed
+//! against the [`clock`](crate::clock), and the longest run of each
+//! since the last report is printed along with the profiler's own report,
+//! with the number of runs over its budget in [`ISR_BUDGETS`].
+//! 
+//! Only the body of a handler is timed. Saving and restoring registers on
+//! entry and exit adds some tens of cycles more, which the budgets allow for.
+//! 
+//! The build script lists the interrupts enabled by the features of a build
+//! in order of priority, see `INTERRUPTS` in `build.rs`.
+
+use core::cell::Cell;
+use arduino_hal::pac::TC1;
+use avr_device::interrupt::Mutex;
+use ufmt::{uwriteln, uWrite};
+
+use crate::text::Text;
+
+/// Interrupt handlers that are timed
+#[derive(Copy, Clone)]
+pub enum Isr {
+    /// Millisecond tick of the `frame-pacing` feature
+    Timer0,
+
+    /// Overflow of the cycle counter, see [`clock`](crate::clock)
+    Timer1,
+
+    /// Panel refresh timing of the `vsync` feature
+    Timer2,
+
+    /// Byte sent to the display with the `async-flush` feature
+    Twi,
+}
+
+const NUM_ISRS: usize = 4;
+
+const ISR_NAMES: [Text; NUM_ISRS] = [Text::Timer0, Text::Timer1, Text::Timer2, Text::Twi];
+
+/// Pick the cycles each [`Isr`] may take here, with the `isr-budget`
+/// feature
+/// 
+/// A byte at 400 kHz takes 360 cycles on the bus, so the TWI interrupt must
+/// stay well under that for the bus not to sit idle. The timer ticks only
+/// count, and anything more is a driver doing work that belongs in the main
+/// loop.
+const ISR_BUDGETS: [u16; NUM_ISRS] = [64, 64, 96, 160];
+
+/// Longest run of each [`Isr`] since the last report, in cycles
+static WORST: Mutex<[Cell<u16>; NUM_ISRS]> = Mutex::new([
+    Cell::new(0),
+    Cell::new(0),
+    Cell::new(0),
+    Cell::new(0),
+]);
+
+/// Runs of each [`Isr`] over its budget since the last report
+static OVERRUNS: Mutex<[Cell<u16>; NUM_ISRS]> = Mutex::new([
+    Cell::new(0),
+    Cell::new(0),
+    Cell::new(0),
+    Cell::new(0),
+]);
+
+/// Run the body of an interrupt handler and record the cycles it took
+#[inline(always)]
+pub fn measure<F: FnOnce()>(isr: Isr, f: F) {
+    // SAFETY: TC1 is only set up by `clock::start`, and reading its
+    // counter has no side effects
+    let tc1 = unsafe { &*TC1::ptr() };
+
+    // Interrupts are held off in the handler, so the low half of the cycle
+    // counter is enough for any handler shorter than its period
+    let start = tc1.tcnt1.read().bits();
+    f();
+    let elapsed = tc1.tcnt1.read().bits().wrapping_sub(start);
+
+    avr_device::interrupt::free(|cs| {
+        let worst = &WORST.borrow(&cs)[isr as usize];
+        worst.set(worst.get().max(elapsed));
+        if elapsed > ISR_BUDGETS[isr as usize] {
+            let overruns = &OVERRUNS.borrow(&cs)[isr as usize];
+            overruns.set(overruns.get().saturating_add(1));
+        }
+    });
+}
+
+/// Print the longest run of every handler that ran since the last report
+/// and how many runs went over budget, then start over
+pub fn report<W: uWrite>(serial: &mut W) {
+    let mut worst = [0; NUM_ISRS];
+    let mut overruns = [0; NUM_ISRS];
+    avr_device::interrupt::free(|cs| {
+        for (cycles, cell) in worst.iter_mut().zip(WORST.borrow(&cs)) {
+            *cycles = cell.replace(0);
+        }
+        for (count, cell) in overruns.iter_mut().zip(OVERRUNS.borrow(&cs)) {
+            *count = cell.replace(0);
+        }
+    });
+
+    for ((name, cycles), overruns) in ISR_NAMES.iter().zip(worst).zip(overruns) {
+        if cycles > 0 {
+            let _ = uwriteln!(serial, "{} {} {} {} {}", Text::Isr, *name, cycles, Text::Over, overruns);
+        }
+    }
+}
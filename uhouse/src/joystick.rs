@@ -0,0 +1,136 @@
+#![cfg(feature = "joystick")]
+
+//! Manual camera control with an analog joystick
+//! 
+//! A two-axis joystick module on A0 and A1 replaces the canned animation.
+//! Deflecting the stick left and right orbits the house, and up and down
+//! dollies the camera towards and away from it.
+//! 
+//! With the `settings` feature, the camera is saved whenever the stick comes
+//! back to rest.
+
+use arduino_hal::{Adc, adc::Channel};
+
+use uhouse_core::house::MESH_DEPTH;
+use uhouse_core::rotor::Rotor;
+use uhouse_core::vec::*;
+
+/// ADC reading with the stick centered
+const CENTER: i16 = 512;
+
+/// Deflection from [`CENTER`] ignored as noise of a stick at rest
+const DEAD_ZONE: i16 = 64;
+
+/// Largest deflection from [`CENTER`]
+const FULL_SCALE: i16 = 512;
+
+/// Angle orbited per animation step at each orbit speed, slowest first, in
+/// 65536ths of a turn
+/// 
+/// From the equation `round(65536*a/360)` for angles `a` of 0.5, 1, 2 and 3
+/// degrees.
+const ORBIT_SPEEDS: [u16; 4] = [91, 182, 364, 546];
+
+/// Depth change per animation step at full deflection is
+/// `FULL_SCALE >> DOLLY_SHIFT`
+const DOLLY_SHIFT: u8 = 2;
+
+/// Closest the camera can be dollied in
+const MIN_DEPTH: IFixed = 0x1000;
+
+/// Furthest the camera can be dollied out
+/// 
+/// Keeps the depth of every vertex within an [`IFixed`].
+const MAX_DEPTH: IFixed = 0x5000;
+
+pub struct Joystick {
+    adc: Adc,
+    x: Channel,
+    y: Channel,
+
+    /// Accumulated orbit angle, in 65536ths of a turn
+    angle: u16,
+
+    /// Depth of the center of the house
+    depth: IFixed,
+
+    /// Whether the stick was deflected at the last update
+    moving: bool,
+
+    /// Whether the stick came back to rest at the last update
+    settled: bool,
+}
+
+impl Joystick {
+
+    /// Start with the camera where the canned animation starts
+    pub fn new(adc: Adc, x: Channel, y: Channel) -> Self {
+        Self {
+            adc,
+            x,
+            y,
+            angle: 0,
+            depth: MESH_DEPTH,
+            moving: false,
+            settled: false,
+        }
+    }
+
+    /// Move the camera to where it was saved
+    #[cfg(feature = "settings")]
+    pub fn restore(&mut self, angle: u16, depth: IFixed) {
+        self.angle = angle;
+        self.depth = depth.clamp(MIN_DEPTH, MAX_DEPTH);
+    }
+
+    /// Read the stick and move the camera by `steps` animation steps
+    pub fn update(&mut self, steps: u8) {
+        let orbit = deflection(&mut self.adc, &self.x);
+        let dolly = deflection(&mut self.adc, &self.y);
+
+        if orbit != 0 {
+            let speed = ((orbit.abs() - DEAD_ZONE) as i32 * ORBIT_SPEEDS.len() as i32
+                / (FULL_SCALE - DEAD_ZONE) as i32) as usize;
+            let step = ORBIT_SPEEDS[speed.min(ORBIT_SPEEDS.len() - 1)].wrapping_mul(steps as u16);
+            self.angle = if orbit < 0 {
+                self.angle.wrapping_sub(step)
+            } else {
+                self.angle.wrapping_add(step)
+            };
+        }
+
+        let dolly_step = (dolly >> DOLLY_SHIFT) * steps as IFixed;
+        self.depth = self.depth.saturating_add(dolly_step).clamp(MIN_DEPTH, MAX_DEPTH);
+
+        let moving = orbit != 0 || dolly != 0;
+        self.settled = self.moving && !moving;
+        self.moving = moving;
+    }
+
+    /// Whether the stick came back to rest at the last update, after moving
+    /// the camera
+    pub fn settled(&self) -> bool {
+        self.settled
+    }
+
+    /// Orbit angle, in 65536ths of a turn
+    pub fn angle(&self) -> u16 {
+        self.angle
+    }
+
+    /// Rotation vector of the orbit
+    pub fn rotation(&self) -> Rotor {
+        Rotor::from_angle(self.angle)
+    }
+
+    /// Depth to push the house into the screen
+    pub fn depth(&self) -> IFixed {
+        self.depth
+    }
+}
+
+/// Deflection of an axis from center, zero inside the dead zone
+fn deflection(adc: &mut Adc, channel: &Channel) -> i16 {
+    let deflection = adc.read_blocking(channel) as i16 - CENTER;
+    if deflection.abs() < DEAD_ZONE { 0 } else { deflection }
+}
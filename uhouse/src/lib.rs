@@ -0,0 +1,1225 @@
+//! # μHouse-rs
+//! 
+//! This project renders to an SSD1306 display over I2C, or over SPI with the
+//! `spi` feature. It uses a resolution of 128x64 by default but can be
+//! changed by editing the [`Panel`] variable. The SH1106 is not supported,
+//! as it has no horizontal addressing mode for the driver to flush with.
+//! 
+//! This was made for an Arduino UNO running an Atmega328P, and also runs on
+//! an Arduino Mega 2560 with the `board-mega2560` feature, see [`board`].
+//! 
+//! The renderer itself lives in the `uhouse-core` crate, shared with the
+//! simulator on the host. This library wires it up to the board, the display
+//! and the optional subsystems, and [`run`] runs it. The `uhouse` binary
+//! runs it on the board and display picked by Cargo feature, and each crate
+//! in `boards/` runs it on one combination of them without any features to
+//! pass.
+//! 
+//! Enjoy!
+
+#![no_std]
+
+#![feature(generic_arg_infer)]
+#![feature(abi_avr_interrupt)]
+
+#[macro_use]
+extern crate uhouse_core;
+
+/// Accumulate the cycles spent evaluating an expression into a
+/// [`profile::Section`] when built with the `profile` feature
+macro_rules! profile {
+    ($section:ident, $e:expr) => {{
+        #[cfg(feature = "profile")]
+        let result = crate::profile::measure(crate::profile::Section::$section, || $e);
+
+        #[cfg(not(feature = "profile"))]
+        let result = $e;
+
+        result
+    }};
+}
+
+/// Time the body of an interrupt handler against its [`isr::Isr`] budget
+/// when built with the `isr-budget` feature
+macro_rules! isr_budget {
+    ($isr:ident, $body:block) => {{
+        #[cfg(feature = "isr-budget")]
+        crate::isr::measure(crate::isr::Isr::$isr, || $body);
+
+        #[cfg(not(feature = "isr-budget"))]
+        $body;
+    }};
+}
+
+/// Pick the scenes to cycle through here, in order, with the `scene-cycle`
+/// feature
+/// 
+/// Each is an object declared with `scene!`, such as one of the [`models`].
+/// Without the feature only the first scene is drawn. See [`for_scenes!`]
+/// for what this expands to.
+#[cfg(feature = "scene-cycle")]
+macro_rules! scenes {
+    ($($args:tt)*) => {
+        for_scenes!([HOUSE, models::CUBE] $($args)*)
+    };
+}
+
+#[cfg(not(feature = "scene-cycle"))]
+macro_rules! scenes {
+    ($($args:tt)*) => {
+        for_scenes!([HOUSE] $($args)*)
+    };
+}
+
+/// Expand [`scenes!`] for a list of scene objects
+/// 
+/// `scenes!(count)` is the number of scenes, `scenes!(num_verts)` an array
+/// of their vertex counts and `scenes!(thumbnails)` an array of their
+/// thumbnails, see [`select`]. `scenes!(with index, OBJECT => body)` evaluates
+/// `body` with `OBJECT` naming the object of the scene at `index`, both its
+/// [`Mesh`](mesh::Mesh) and the module of its tables and tracks. The body is
+/// expanded once per scene, so every scene gets code specialized for its
+/// table sizes.
+macro_rules! for_scenes {
+    ([$($($object:ident)::+),+] count) => {
+        [$(stringify!($($object)::+)),+].len()
+    };
+    ([$($($object:ident)::+),+] num_verts) => {
+        [$($($object)::+::NUM_VERTS),+]
+    };
+    ([$($($object:ident)::+),+] thumbnails) => {
+        [$(for_scenes!(@thumbnail $($object)::+)),+]
+    };
+    ([$($list:tt)*] with $index:expr, $alias:ident => $body:expr) => {{
+        #[allow(unused_variables)]
+        let index: usize = $index;
+        for_scenes!(@with index, 0, $alias => $body; $($list)*)
+    }};
+
+    // Thumbnails are named after the object without its path
+    (@thumbnail $name:ident) => {
+        &select::thumbnails::$name
+    };
+    (@thumbnail $module:ident :: $($rest:tt)+) => {
+        for_scenes!(@thumbnail $($rest)+)
+    };
+
+    // The last scene also takes any index past the end
+    (@with $index:ident, $n:expr, $alias:ident => $body:expr; $($last:ident)::+) => {{
+        use $($last)::+ as $alias;
+        $body
+    }};
+    (@with $index:ident, $n:expr, $alias:ident => $body:expr; $($first:ident)::+, $($rest:tt)*) => {
+        if $index == $n {
+            use $($first)::+ as $alias;
+            $body
+        } else {
+            for_scenes!(@with $index, $n + 1, $alias => $body; $($rest)*)
+        }
+    };
+}
+
+#[macro_use]
+mod board;
+
+mod framebuffer;
+mod hooks;
+mod panic_policy;
+
+use core::panic::PanicInfo;
+use board::Board;
+use ssd1306::{Ssd1306, command::AddrMode, prelude::*};
+
+#[cfg(feature = "i2c")]
+use ssd1306::I2CDisplayInterface;
+
+use framebuffer::FrameBuffer;
+
+#[cfg(feature = "crash-log")]
+use framebuffer::Layer;
+use hooks::FrameHooks;
+
+#[cfg(not(any(feature = "screenshot", feature = "remote", feature = "frame-stream", feature = "frame-crc")))]
+use hooks::NoHooks;
+use panic_policy::PanicPolicy;
+use uhouse_core::{clip, mesh};
+use uhouse_core::clip::Rect;
+use uhouse_core::house::HOUSE;
+
+#[cfg(feature = "filled")]
+use uhouse_core::fill;
+
+#[cfg(feature = "keyframes")]
+use uhouse_core::animation::{self, AnimationState};
+
+#[cfg(feature = "background")]
+use uhouse_core::background::Backdrop;
+
+#[cfg(feature = "morph")]
+use uhouse_core::morph::{self, MorphState};
+use uhouse_core::projection::Perspective;
+
+#[cfg(feature = "radar")]
+use uhouse_core::radar::Sweep;
+use uhouse_core::rotor::Rotor;
+
+#[cfg(not(any(feature = "joystick", feature = "imu")))]
+use uhouse_core::scene::TrackState;
+use uhouse_core::transform::{project_vert, view_transform};
+use uhouse_core::vec::*;
+
+#[cfg(any(feature = "fps", feature = "fps-overlay", feature = "profile", feature = "remote"))]
+mod clock;
+
+#[cfg(any(feature = "fps", feature = "fps-overlay"))]
+mod fps;
+
+#[cfg(feature = "fps-overlay")]
+mod stats;
+
+#[cfg(feature = "profile")]
+mod profile;
+
+#[cfg(feature = "isr-budget")]
+mod isr;
+
+#[cfg(feature = "screenshot")]
+mod screenshot;
+
+#[cfg(feature = "pixel-shift")]
+mod burnin;
+
+#[cfg(feature = "ab-compare")]
+mod ab;
+
+#[cfg(feature = "crash-log")]
+mod crashlog;
+
+#[cfg(any(feature = "crash-log", feature = "settings"))]
+mod eeprom_queue;
+
+#[cfg(feature = "settings")]
+mod settings;
+
+#[cfg(any(feature = "remote", feature = "console"))]
+mod command;
+
+#[cfg(feature = "remote")]
+mod remote;
+
+#[cfg(feature = "console")]
+mod console;
+
+#[cfg(feature = "joystick")]
+mod joystick;
+
+#[cfg(feature = "imu")]
+mod imu;
+
+#[cfg(feature = "frame-stream")]
+mod framestream;
+
+#[cfg(feature = "frame-crc")]
+mod framecrc;
+
+#[cfg(feature = "vsync")]
+mod vsync;
+
+#[cfg(feature = "frame-pacing")]
+mod pacing;
+
+#[cfg(feature = "reveal")]
+mod reveal;
+
+#[cfg(any(feature = "serial", feature = "fps-overlay", feature = "panic-display"))]
+mod text;
+
+#[cfg(feature = "scene-cycle")]
+mod cycle;
+
+#[cfg(feature = "brownout")]
+mod brownout;
+
+#[cfg(feature = "scene-select")]
+mod select;
+
+#[cfg(feature = "async-flush")]
+mod twiflush;
+
+#[cfg(feature = "panic-display")]
+mod panicscreen;
+
+#[cfg(all(feature = "fps", feature = "profile"))]
+compile_error!("the profile feature reports the frame rate over the serial port itself and cannot be combined with fps");
+
+#[cfg(all(feature = "screenshot", any(feature = "fps", feature = "profile")))]
+compile_error!("the screenshot feature needs the serial port and cannot be combined with fps or profile");
+
+#[cfg(all(feature = "crash-log", any(feature = "fps", feature = "profile", feature = "screenshot")))]
+compile_error!("the crash-log feature needs the serial port and cannot be combined with fps, profile or screenshot");
+
+#[cfg(all(feature = "remote", any(feature = "fps", feature = "profile", feature = "screenshot", feature = "crash-log")))]
+compile_error!("the remote feature needs the serial port and cannot be combined with fps, profile, screenshot or crash-log");
+
+#[cfg(all(feature = "frame-stream", any(feature = "fps", feature = "profile", feature = "screenshot", feature = "crash-log", feature = "remote")))]
+compile_error!("the frame-stream feature needs the serial port and cannot be combined with fps, profile, screenshot, crash-log or remote");
+
+#[cfg(all(feature = "frame-crc", any(feature = "fps", feature = "profile", feature = "screenshot", feature = "crash-log", feature = "remote", feature = "frame-stream")))]
+compile_error!("the frame-crc feature needs the serial port and cannot be combined with fps, profile, screenshot, crash-log, remote or frame-stream");
+
+#[cfg(not(any(feature = "i2c", feature = "spi")))]
+compile_error!("enable either the i2c or the spi feature to select the display bus");
+
+#[cfg(all(feature = "i2c", feature = "spi"))]
+compile_error!("the i2c and spi features select different display buses and cannot be combined");
+
+#[cfg(all(feature = "async-flush", feature = "spi"))]
+compile_error!("the async-flush feature sends frames from the TWI interrupt and needs the i2c feature");
+
+#[cfg(all(feature = "spi", feature = "checked-indices"))]
+compile_error!("the checked-indices LED is on D13, which the spi feature uses as SCK");
+
+#[cfg(all(feature = "panic-reset", feature = "panic-show"))]
+compile_error!("the panic-reset and panic-show features select different panic policies and cannot be combined");
+
+#[cfg(all(feature = "panic-display", any(feature = "panic-reset", feature = "panic-show")))]
+compile_error!("the panic-display feature selects a different panic policy from panic-reset and panic-show and cannot be combined with them");
+
+#[cfg(all(feature = "joystick", feature = "ab-compare"))]
+compile_error!("the ab-compare feature replays the canned animation and cannot be combined with joystick");
+
+#[cfg(all(feature = "morph", feature = "ab-compare"))]
+compile_error!("the ab-compare feature replays the rotation tracks but not the morph and cannot be combined with morph");
+
+#[cfg(all(feature = "keyframes", feature = "ab-compare"))]
+compile_error!("the ab-compare feature replays the rotation tracks but not the keyframes and cannot be combined with keyframes");
+
+#[cfg(all(feature = "radar", feature = "ab-compare"))]
+compile_error!("the ab-compare feature replays the rotation tracks but not the radar sweep and cannot be combined with radar");
+
+#[cfg(all(feature = "imu", any(feature = "joystick", feature = "ab-compare")))]
+compile_error!("the imu feature replaces the canned animation and cannot be combined with joystick or ab-compare");
+
+#[cfg(all(feature = "imu", feature = "async-flush"))]
+compile_error!("the async-flush feature sends frames from the TWI interrupt and cannot share the bus with imu");
+
+#[cfg(all(feature = "brownout", feature = "joystick"))]
+compile_error!("the brownout and joystick features both need the ADC and cannot be combined");
+
+#[cfg(all(feature = "scene-cycle", feature = "ab-compare"))]
+compile_error!("the ab-compare feature replays the animation of one scene and cannot be combined with scene-cycle");
+
+#[cfg(all(feature = "frame-pacing", feature = "ab-compare"))]
+compile_error!("the ab-compare feature replays the animation one step per frame and cannot be combined with frame-pacing");
+
+#[cfg(all(feature = "frame-crc", any(feature = "joystick", feature = "imu")))]
+compile_error!("the frame-crc feature is checked against the simulator stepping the same animation and cannot be combined with joystick or imu");
+
+#[cfg(all(feature = "settings", not(any(feature = "console", feature = "joystick"))))]
+compile_error!("the settings feature saves what is set over the console or with the joystick and needs console or joystick");
+
+#[cfg(all(feature = "settings", feature = "async-flush"))]
+compile_error!("the settings feature sets the display brightness over the blocking interface and cannot be combined with async-flush");
+
+/// Pick your display size here
+type Panel = DisplaySize128x64;
+
+#[cfg(not(feature = "vsync"))]
+type Display = Panel;
+
+#[cfg(feature = "vsync")]
+type Display = vsync::Paced<Panel>;
+
+/// Pick the panel refresh divider here, from 1 to 16, with the `vsync`
+/// feature
+/// 
+/// The panel refreshes at about 109 Hz divided by this.
+#[cfg(feature = "vsync")]
+const REFRESH_DIVIDER: u8 = 2;
+
+/// Pick how many panel refreshes each frame is shown for at least here,
+/// with the `vsync` feature
+#[cfg(feature = "vsync")]
+const REFRESHES_PER_FRAME: u8 = 2;
+
+/// Calibrate the panel oscillator frequency here, with the `vsync` feature
+/// 
+/// The datasheet gives a typical 370 kHz. If the tear line creeps down the
+/// screen the panel runs faster than this, so raise it, and if it creeps up
+/// lower it.
+#[cfg(feature = "vsync")]
+const PANEL_OSC_HZ: u32 = 370_000;
+
+/// Pick your projection here
+type Projection = Perspective;
+
+/// Pick the projection compared against [`Projection`] here, rendered as
+/// path B with the `ab-compare` feature
+#[cfg(feature = "ab-compare")]
+type ProjectionB = uhouse_core::projection::FlatPerspective;
+
+/// Pick the animation rate here, in steps per second, with the
+/// `frame-pacing` feature
+#[cfg(feature = "frame-pacing")]
+const TARGET_FPS: u8 = 30;
+
+/// Pick how far the radar beam turns each animation step here, in 65536ths
+/// of a turn, with the `radar` feature
+#[cfg(feature = "radar")]
+const RADAR_STEP: u16 = uhouse_core::sine::degrees(6);
+
+/// Pick what is drawn behind the scene here, with the `background`
+/// feature
+#[cfg(feature = "background")]
+const BACKDROP: Backdrop = Backdrop::Starfield;
+
+/// Pick how many animation steps each scene is shown for here, with the
+/// `scene-cycle` feature, or 0 to switch only on the button
+#[cfg(feature = "scene-cycle")]
+const SCENE_STEPS: u16 = 600;
+
+/// Number of scenes in [`scenes!`]
+#[cfg(feature = "scene-cycle")]
+const NUM_SCENES: usize = scenes!(count);
+
+/// Pick the order edges appear in here, with the `reveal` feature
+#[cfg(feature = "reveal")]
+const REVEAL_ORDER: reveal::Order = reveal::Order::Shuffled;
+
+/// Pick the supply voltage the display is parked below here, in
+/// millivolts, with the `brownout` feature
+/// 
+/// Keep it above the brown-out level of the fuses and below the lowest
+/// voltage the supply sags to under normal load.
+#[cfg(feature = "brownout")]
+const LOW_SUPPLY_MV: u16 = 4000;
+
+#[cfg(all(feature = "bounded", feature = "serial"))]
+compile_error!("the bounded feature cannot be combined with features writing over serial, which wait on the port");
+
+#[cfg(all(feature = "bounded", any(feature = "brownout", feature = "imu", feature = "scene-select")))]
+compile_error!("the bounded feature cannot be combined with brownout, imu or scene-select, which can wait indefinitely");
+
+#[cfg(all(feature = "bounded", any(feature = "filled", feature = "streaming", feature = "clip-debug")))]
+compile_error!("the build script has no worst-case frame cost for filled, streaming or clip-debug, which cannot be combined with bounded");
+
+#[cfg(not(any(feature = "board-uno", feature = "board-mega2560")))]
+compile_error!("pick the board to build for with the board-uno or board-mega2560 feature");
+
+#[cfg(all(feature = "board-uno", feature = "board-mega2560"))]
+compile_error!("the board-uno and board-mega2560 features pick the board to build for and cannot be combined");
+
+/// Calibrate the internal bandgap reference here, in millivolts, with the
+/// `brownout` feature
+/// 
+/// The datasheet gives 1.1 V, but parts range from 1.0 V to 1.2 V. If the
+/// measured supply reads high, lower this, and if it reads low raise it.
+#[cfg(feature = "brownout")]
+const BANDGAP_MV: u16 = 1100;
+
+/// The board being built for, picked by the `board-uno` and
+/// `board-mega2560` features
+#[cfg(feature = "board-uno")]
+type Target = board::uno::Uno;
+
+#[cfg(feature = "board-mega2560")]
+type Target = board::mega2560::Mega2560;
+
+/// Pick your clock frequency here
+#[allow(unused)]
+const CLOCK_FREQ: u32 = Target::CLOCK_FREQ;
+
+const SCREEN_WIDTH: IFixed = Display::WIDTH as IFixed;
+const SCREEN_HEIGHT: IFixed = Display::HEIGHT as IFixed;
+const SCREEN_CENTER: Vec2 = vec2!(SCREEN_WIDTH>>1, SCREEN_HEIGHT>>1);
+
+/// The visible screen
+const SCREEN: Rect = Rect::sized(SCREEN_WIDTH, SCREEN_HEIGHT);
+
+/// RAM budget for screen-space vertex buffers, in bytes
+const SCREEN_VERTS_BUDGET: usize = 256;
+
+/// Pick what happens after a panic here, or with the `panic-reset`,
+/// `panic-show` and `panic-display` features
+#[cfg(not(any(feature = "panic-reset", feature = "panic-show", feature = "panic-display")))]
+type OnPanic = panic_policy::Halt;
+
+#[cfg(feature = "panic-reset")]
+type OnPanic = panic_policy::Reset;
+
+#[cfg(feature = "panic-show")]
+type OnPanic = panic_policy::ShowThenReset;
+
+#[cfg(feature = "panic-display")]
+type OnPanic = panic_policy::ShowMessage;
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    #[cfg(feature = "crash-log")]
+    crashlog::record_panic();
+
+    OnPanic::on_panic(info)
+}
+
+/// Models imported from the OBJ files in `models/` by the build script
+/// 
+/// Draw one by adding it to the [`scenes!`].
+#[allow(dead_code)]
+mod models {
+    use super::*;
+
+    include!(concat!(env!("OUT_DIR"), "/models.rs"));
+}
+
+/// Size of the screen-space scratch buffer shared by every mesh
+/// 
+/// Meshes are transformed and drawn one at a time, so the buffer only needs
+/// to fit the largest of them.
+#[cfg(not(feature = "streaming"))]
+const SCRATCH_VERTS: usize = mesh::max_verts(&scenes!(num_verts));
+
+/// Size of the screen-space scratch buffer in streaming mode
+/// 
+/// Meshes are transformed this many vertices at a time.
+#[cfg(feature = "streaming")]
+const SCRATCH_VERTS: usize = 16;
+
+const _: () = assert!(
+    mesh::screen_verts_bytes(SCRATCH_VERTS) <= SCREEN_VERTS_BUDGET,
+    "screen-space vertex buffers exceed SCREEN_VERTS_BUDGET",
+);
+
+/// Screen rectangles the 3D scene never draws into, such as the area under
+/// the HUD
+const SCENE_MASKS: &[Rect] = &[
+    #[cfg(feature = "fps-overlay")]
+    stats::AREA,
+];
+
+/// Whether a pixel is hidden by one of the [`SCENE_MASKS`]
+fn masked(v: Vec2) -> bool {
+    SCENE_MASKS.iter().any(|mask| mask.contains(v))
+}
+
+/// Entry point attribute for the binaries, which run [`run`] from it
+pub use arduino_hal::entry;
+
+/// Set up the board and the display, then draw frames forever
+pub fn run() -> ! {
+    let dp = arduino_hal::Peripherals::take().unwrap();
+
+    let pins = arduino_hal::pins!(dp);
+
+    // Record why the last run ended, then print the crash log
+    #[cfg(feature = "crash-log")]
+    let mut crash_log = {
+        let mut crash_log = crashlog::CrashLog::new(arduino_hal::Eeprom::new(dp.EEPROM));
+        let mcusr = dp.CPU.mcusr.read().bits();
+
+        // SAFETY: Writing zero clears every reset flag
+        dp.CPU.mcusr.write(|w| unsafe { w.bits(0) });
+        crash_log.record_reset(mcusr);
+        crash_log.dump(&mut arduino_hal::default_serial!(dp, pins, 57600));
+        crash_log
+    };
+
+    // After repeated early crashes, run only the built-in scene with
+    // optional subsystems off and the display bus slowed down
+    #[cfg(feature = "crash-log")]
+    let safe_mode = crash_log.safe_mode();
+
+    #[cfg(not(feature = "crash-log"))]
+    let safe_mode = false;
+
+    #[cfg(feature = "checked-indices")]
+    let mut bad_index_led = pins.d13.into_output();
+
+    // Watch the supply voltage against the internal bandgap
+    #[cfg(feature = "brownout")]
+    let mut supply = brownout::Supply::new(arduino_hal::Adc::new(dp.ADC, Default::default()));
+
+    #[cfg(feature = "fps")]
+    let mut fps_serial = arduino_hal::default_serial!(dp, pins, 57600);
+
+    // Pause, speed and depth set by commands over the FPS serial port
+    #[cfg(feature = "console")]
+    let mut console = console::Console::new();
+
+    // Cycle counter the frame rate counter, the profiler and the remote
+    // benchmark share
+    // SAFETY: Interrupts are not yet enabled, and nothing else uses TC1
+    #[cfg(any(feature = "fps", feature = "fps-overlay", feature = "profile", feature = "remote"))]
+    unsafe { clock::start(dp.TC1) };
+
+    #[cfg(any(feature = "fps", feature = "fps-overlay"))]
+    let mut fps_counter = fps::FpsCounter::new();
+
+    #[cfg(feature = "profile")]
+    let mut profiler = profile::Profiler::new(arduino_hal::default_serial!(dp, pins, 57600));
+
+    // SAFETY: Interrupts are not yet enabled
+    #[cfg(feature = "frame-pacing")]
+    let mut frame_pacer = unsafe { pacing::FramePacer::new(dp.TC0) };
+
+    // SAFETY: Interrupts are not yet enabled
+    #[cfg(feature = "vsync")]
+    let mut pacer = unsafe { vsync::Pacer::new(dp.TC2) };
+
+    #[cfg(feature = "i2c")]
+    let i2c = Target::i2c(dp.TWI, i2c_pins!(pins), if safe_mode { 100000 } else { 400000 });
+
+    // The IMU takes turns with the display on the bus
+    #[cfg(all(feature = "i2c", feature = "imu"))]
+    let i2c_bus = core::cell::RefCell::new(i2c);
+
+    #[cfg(all(feature = "i2c", feature = "imu"))]
+    let i2c = imu::SharedI2c::new(&i2c_bus);
+
+    #[cfg(feature = "i2c")]
+    let interface = I2CDisplayInterface::new(i2c);
+
+    // Hardware SPI with the board's CS pin, and D9 as DC
+    #[cfg(feature = "spi")]
+    let interface = {
+        use arduino_hal::spi::{SerialClockRate, Settings};
+
+        let (spi, cs) = Target::spi(
+            dp.SPI,
+            spi_pins!(pins),
+            Settings {
+                clock: if safe_mode { SerialClockRate::OscfOver16 } else { SerialClockRate::OscfOver2 },
+                ..Default::default()
+            },
+        );
+        SPIInterface::new(spi, pins.d9.into_output(), cs)
+    };
+
+    #[cfg(not(feature = "vsync"))]
+    let size = Panel{};
+
+    #[cfg(feature = "vsync")]
+    let size = vsync::Paced(Panel{});
+
+    let mut display = Ssd1306::new(
+        interface,
+        size,
+        DisplayRotation::Rotate0,
+    );
+
+    // SPI modules have a reset line, on D8, which must be pulsed before init
+    #[cfg(feature = "spi")]
+    display.reset(&mut pins.d8.into_output(), &mut arduino_hal::Delay::new()).unwrap();
+
+    display.init_with_addr_mode(AddrMode::Horizontal).unwrap();
+
+    // Settings kept over a reset, falling back to defaults if they do not
+    // check out
+    #[cfg(feature = "settings")]
+    let (mut settings_store, mut settings) = {
+        #[cfg(not(feature = "crash-log"))]
+        let eeprom = dp.EEPROM;
+
+        // SAFETY: The crash log owns the EEPROM as well, at other offsets,
+        // and every write waits for the one before it to finish
+        #[cfg(feature = "crash-log")]
+        let eeprom = unsafe { arduino_hal::Peripherals::steal() }.EEPROM;
+
+        settings::Store::load(arduino_hal::Eeprom::new(eeprom))
+    };
+
+    #[cfg(feature = "settings")]
+    display.set_brightness(settings::BRIGHTNESSES[settings.brightness as usize]).unwrap();
+
+    // Send frames from the TWI interrupt from here on, drawing the next
+    // frame while the last one is sent
+    // SAFETY: The blocking interface is not used again after init
+    #[cfg(feature = "async-flush")]
+    let mut async_flush = unsafe { twiflush::AsyncFlush::new(arduino_hal::Peripherals::steal().TWI) };
+
+    // SAFETY: Every peripheral with an interrupt handler, see `INTERRUPTS`
+    // in build.rs, and the data the handlers use are set up by now
+    #[cfg(any(feature = "vsync", feature = "fps", feature = "fps-overlay", feature = "profile", feature = "remote", feature = "frame-pacing", feature = "async-flush"))]
+    unsafe { avr_device::interrupt::enable() };
+
+    let mut frame = FrameBuffer::new();
+
+    #[cfg(feature = "crash-log")]
+    if safe_mode {
+        crash_log.draw_diagnostic(&mut frame);
+    }
+
+    #[cfg(not(any(feature = "screenshot", feature = "remote", feature = "frame-stream", feature = "frame-crc")))]
+    let mut hooks = NoHooks;
+
+    // Dump the next frame over serial when D2 is pulled low
+    #[cfg(feature = "screenshot")]
+    let mut hooks = screenshot::Screenshot::new(
+        arduino_hal::default_serial!(dp, pins, 57600),
+        pins.d2.into_pull_up_input().downgrade(),
+    );
+
+    // Take reset and bootloader commands from host tooling over serial
+    #[cfg(feature = "remote")]
+    let mut hooks = remote::Remote::new(arduino_hal::default_serial!(dp, pins, 57600));
+
+    // Send every frame over serial, see scripts/decode-stream.py
+    #[cfg(feature = "frame-stream")]
+    let mut hooks = framestream::FrameStream::new(arduino_hal::default_serial!(dp, pins, 57600));
+
+    // Print a checksum of every frame, see `uhouse-run --diverge`
+    #[cfg(feature = "frame-crc")]
+    let mut hooks = framecrc::FrameCrc::new(arduino_hal::default_serial!(dp, pins, 57600));
+
+    #[cfg(not(feature = "streaming"))]
+    let mut screen_verts = [mesh::ScreenVert::default(); SCRATCH_VERTS];
+
+    // Rotation, location, depth, center, mirror, morph blend and keyframe
+    // clock the screen vertices were last transformed with
+    #[cfg(not(feature = "streaming"))]
+    let mut transformed_view = None;
+
+    // Orbit and dolly the camera by hand instead of the canned animation
+    #[cfg(feature = "joystick")]
+    let mut joystick = {
+        let mut adc = arduino_hal::Adc::new(dp.ADC, Default::default());
+        let x = pins.a0.into_analog_input(&mut adc).into_channel();
+        let y = pins.a1.into_analog_input(&mut adc).into_channel();
+        joystick::Joystick::new(adc, x, y)
+    };
+
+    // Orbit and pitch the camera by tilting the board instead of the canned
+    // animation, with the IMU on the display's bus or on its own with SPI
+    #[cfg(feature = "imu")]
+    let mut imu = {
+        #[cfg(feature = "i2c")]
+        let i2c = imu::SharedI2c::new(&i2c_bus);
+
+        #[cfg(feature = "spi")]
+        let i2c = Target::i2c(dp.TWI, i2c_pins!(pins), if safe_mode { 100000 } else { 400000 });
+
+        imu::Imu::new(i2c)
+    };
+
+    // Progress along each animation track, updated per-frame
+    #[cfg(not(any(feature = "joystick", feature = "imu")))]
+    let mut tracks = [TrackState::new(); 4];
+
+    #[cfg(feature = "pixel-shift")]
+    let mut pixel_shift = burnin::PixelShift::new();
+
+    #[cfg(feature = "ab-compare")]
+    let mut ab = ab::AbCompare::new();
+
+    #[cfg(feature = "crash-log")]
+    let mut frame_number: u16 = 0;
+
+    #[cfg(feature = "fps-overlay")]
+    let mut stats = stats::StatsOverlay::new();
+
+    // Progress of the scene morphing into its target shape and back
+    #[cfg(feature = "morph")]
+    let mut morph = MorphState::new();
+
+    // Progress of the parts of the scene along their keyframes
+    #[cfg(feature = "keyframes")]
+    let mut animation = AnimationState::new();
+
+    // Grow the scene in edge by edge after start
+    #[cfg(feature = "reveal")]
+    let mut reveal = scenes!(with 0, OBJECT => {
+        reveal::Reveal::new(REVEAL_ORDER, OBJECT.num_segments())
+    });
+
+    // Beam of the radar sweep, turning about the center of the scene
+    #[cfg(feature = "radar")]
+    let mut sweep = Sweep::new();
+
+    // Move on to the next scene on a timer, or early when D4 is pulled low
+    #[cfg(feature = "scene-cycle")]
+    let mut scene_cycle = cycle::SceneCycle::new(pins.d4.into_pull_up_input().downgrade());
+
+    // Pick up where the last run left off, keeping to the first scene in
+    // safe mode
+    #[cfg(feature = "settings")]
+    {
+        #[cfg(feature = "console")]
+        console.restore(&settings);
+
+        #[cfg(feature = "joystick")]
+        joystick.restore(settings.orbit, settings.dolly);
+
+        #[cfg(feature = "scene-cycle")]
+        if !safe_mode {
+            scene_cycle.start_at(settings.scene as usize);
+        }
+    }
+
+    loop {
+        hooks.pre_update();
+
+        #[cfg(feature = "console")]
+        let requests = console.poll(&mut fps_serial);
+
+        #[cfg(feature = "crash-log")]
+        {
+            crashlog::set_frame(frame_number);
+            if frame_number == crashlog::STABLE_FRAMES {
+                crash_log.mark_stable();
+            }
+            crash_log.service();
+            frame_number = frame_number.wrapping_add(1);
+        }
+
+        // Persist the crash log and park the display while the supply is
+        // still high enough to do both safely
+        #[cfg(feature = "brownout")]
+        if supply.is_low() {
+            #[cfg(feature = "crash-log")]
+            crash_log.record_low_supply();
+
+            supply.park();
+        }
+
+        // Replay the same animation for path B as was rendered with path A
+        #[cfg(feature = "ab-compare")]
+        {
+            if ab.begin_frame(&mut tracks) {
+                #[cfg(not(feature = "streaming"))]
+                {
+                    transformed_view = None;
+                }
+            }
+        }
+
+        // Leave the optional drawing passes out in safe mode, and for a frame
+        // after one went over budget to keep the frame rate up
+        #[cfg(feature = "profile")]
+        #[cfg_attr(not(any(feature = "background", feature = "filled")), allow(unused_variables))]
+        let skip_optional = safe_mode || profiler.over_budget();
+
+        #[cfg(not(feature = "profile"))]
+        #[cfg_attr(not(any(feature = "background", feature = "filled")), allow(unused_variables))]
+        let skip_optional = safe_mode;
+
+        #[cfg(feature = "pixel-shift")]
+        let center = if safe_mode {
+            SCREEN_CENTER
+        } else {
+            pixel_shift.update();
+            SCREEN_CENTER + pixel_shift.offset()
+        };
+
+        #[cfg(not(feature = "pixel-shift"))]
+        let center = SCREEN_CENTER;
+
+        // Animation steps due since the last frame, keeping motion at the
+        // same speed however long frames take
+        #[cfg(feature = "frame-pacing")]
+        let steps = frame_pacer.wait();
+
+        #[cfg(not(feature = "frame-pacing"))]
+        let steps = 1;
+
+        // Steps at normal speed, for what is moved or timed by hand even
+        // while the animation is paused or sped up
+        #[cfg(any(feature = "joystick", feature = "imu", feature = "scene-cycle"))]
+        let elapsed = steps;
+
+        // Paused or sped up over the console
+        #[cfg(feature = "console")]
+        #[cfg_attr(any(feature = "joystick", feature = "imu"), allow(unused_variables))]
+        let steps = console.steps(steps);
+
+        hooks.post_update(steps);
+
+        #[cfg(all(feature = "console", feature = "scene-cycle"))]
+        if requests.next_scene {
+            scene_cycle.skip();
+        }
+
+        // Move on to the next scene, keeping to the first one in safe mode
+        #[cfg(feature = "scene-cycle")]
+        let restart = !safe_mode && scene_cycle.update(steps, elapsed);
+
+        #[cfg(not(feature = "scene-cycle"))]
+        let restart = false;
+
+        #[cfg(feature = "console")]
+        let restart = restart || requests.restart;
+
+        #[cfg(feature = "scene-cycle")]
+        let scene = scene_cycle.index();
+
+        #[cfg(not(feature = "scene-cycle"))]
+        let scene = 0;
+
+        // Save the settings when changed over the console, or once the
+        // joystick comes back to rest
+        #[cfg(feature = "settings")]
+        {
+            #[cfg(feature = "console")]
+            if requests.changed {
+                console.store(&mut settings);
+                let _ = display.set_brightness(settings::BRIGHTNESSES[settings.brightness as usize]);
+            }
+
+            #[cfg(not(feature = "console"))]
+            let changed = false;
+
+            #[cfg(feature = "console")]
+            let changed = requests.changed;
+
+            #[cfg(feature = "joystick")]
+            let changed = changed || joystick.settled();
+
+            if changed {
+                #[cfg(feature = "joystick")]
+                {
+                    settings.orbit = joystick.angle();
+                    settings.dolly = joystick.depth();
+                }
+                #[cfg(feature = "scene-cycle")]
+                if !safe_mode {
+                    settings.scene = scene as u8;
+                }
+                settings_store.save(settings);
+            }
+            settings_store.service();
+        }
+
+        // Start the scene from the beginning of its animation
+        if restart {
+            #[cfg(not(any(feature = "joystick", feature = "imu")))]
+            {
+                tracks = [TrackState::new(); 4];
+            }
+            #[cfg(feature = "morph")]
+            {
+                morph = MorphState::new();
+            }
+            #[cfg(feature = "keyframes")]
+            {
+                animation = AnimationState::new();
+            }
+            #[cfg(feature = "reveal")]
+            {
+                reveal = scenes!(with scene, OBJECT => {
+                    reveal::Reveal::new(REVEAL_ORDER, OBJECT.num_segments())
+                });
+            }
+            #[cfg(not(feature = "streaming"))]
+            {
+                transformed_view = None;
+            }
+        }
+
+        #[cfg(feature = "radar")]
+        for _ in 0..steps {
+            sweep.advance(RADAR_STEP);
+        }
+
+        // Area behind the beam drawn this frame
+        #[cfg(feature = "radar")]
+        let sector = sweep.sector(center);
+
+        frame.clear();
+
+        // Show the scene select screen in place of the scene while it is open
+        #[cfg(feature = "scene-select")]
+        let selecting = !safe_mode && scene_cycle.selecting();
+
+        #[cfg(not(feature = "scene-select"))]
+        let selecting = false;
+
+        if selecting {
+            #[cfg(feature = "scene-select")]
+            select::draw(&mut frame, &scenes!(thumbnails), scene);
+        } else {
+            // Animate and draw the scene with code specialized for its tables
+            scenes!(with scene, OBJECT => {
+                // Advance the animation and combine the rotations about each axis
+                #[cfg(not(any(feature = "joystick", feature = "imu")))]
+                let (rotation, location, depth) = {
+                    let [spin, orbit, pitch, roll] = &mut tracks;
+                    for _ in 0..steps {
+                        spin.advance(OBJECT::SPIN);
+                        orbit.advance(OBJECT::ORBIT);
+                        pitch.advance(OBJECT::PITCH);
+                        roll.advance(OBJECT::ROLL);
+                    }
+                    let rotation = Mat3::from_rotors(spin.rotor, pitch.rotor, roll.rotor);
+                    (rotation, orbit.rotor, OBJECT::DEPTH)
+                };
+
+                #[cfg(feature = "joystick")]
+                let (rotation, location, depth) = {
+                    joystick.update(elapsed);
+                    let still = Rotor::IDENTITY;
+                    (Mat3::from_rotors(joystick.rotation(), still, still), still, joystick.depth())
+                };
+
+                #[cfg(feature = "imu")]
+                let (rotation, location, depth) = {
+                    // Keep off the bus in safe mode, holding the camera still
+                    if !safe_mode {
+                        imu.update(elapsed);
+                    }
+                    let still = Rotor::IDENTITY;
+                    (Mat3::from_rotors(still, imu.pitch(), still), imu.orbit(), OBJECT::DEPTH)
+                };
+
+                // Moved nearer or further over the console
+                #[cfg(feature = "console")]
+                let depth = console.depth(depth);
+
+                // Blend of the vertices towards the morph target
+                #[cfg(feature = "morph")]
+                let blend = {
+                    for _ in 0..steps {
+                        morph.advance(OBJECT::MORPH);
+                    }
+                    morph.blend(OBJECT::MORPH)
+                };
+
+                #[cfg(not(feature = "morph"))]
+                let blend = 0;
+
+                // Parts of the scene placed along their keyframes
+                #[cfg(feature = "keyframes")]
+                #[cfg_attr(feature = "streaming", allow(unused_variables))]
+                let (poses, clock) = {
+                    for _ in 0..steps {
+                        animation.advance(OBJECT::PERIOD);
+                    }
+                    (animation.poses(&OBJECT::PARTS, &OBJECT::KEYS), animation.frame())
+                };
+
+                #[cfg(not(feature = "keyframes"))]
+                #[cfg_attr(feature = "streaming", allow(unused_variables))]
+                let clock = 0;
+
+                // Vertex positions in model space
+                let vertex = |i: usize| {
+                    let v = OBJECT.verts.load_at(i);
+
+                    #[cfg(feature = "morph")]
+                    let v = if OBJECT::NUM_MORPH_VERTS == 0 {
+                        v
+                    } else {
+                        morph::lerp(v, OBJECT::MORPH_VERTS.load_at(i), blend)
+                    };
+
+                    #[cfg(feature = "keyframes")]
+                    let v = animation::pose_vertex(&poses, i, v);
+
+                    v
+                };
+
+                #[cfg(feature = "ab-compare")]
+                let path = ab.path();
+
+                // Onto the screen, in sixteenths of a pixel with the
+                // `subpixel` feature
+                let to_screen = |view: Vec3| {
+                    #[cfg(feature = "ab-compare")]
+                    if path == ab::Path::B {
+                        return project_vert::<ProjectionB>(view, center);
+                    }
+                    project_vert::<Projection>(view, center)
+                };
+
+                // Draw the backdrop turning with the scene before the scene
+                // itself, unless optional passes are skipped
+                #[cfg(feature = "background")]
+                if !skip_optional {
+                    profile!(Draw, BACKDROP.draw::<Projection, _>(&rotation, center, &SCREEN, |x, y| {
+                        if !masked(vec2!(x as IFixed, y as IFixed)) {
+                            frame.set_pixel(x, y);
+                        }
+                    }));
+                }
+
+                for mirror in OBJECT.instances() {
+                    let place = |v: Vec3| {
+                        view_transform(mirror.map_or(v, |axis| axis.apply(v)), &rotation, location, depth)
+                    };
+                    let to_view = |i: usize| place(vertex(i));
+
+                    // Skip instances entirely off screen before transforming
+                    // any of their vertices
+                    #[cfg(feature = "sphere-cull")]
+                    if !OBJECT::BOUNDS.load().on_screen(&place, |v| mesh::pixels(to_screen(v)), &SCREEN) {
+                        continue;
+                    }
+
+                    // Redo segments with an endpoint in front of the near plane in
+                    // view space
+                    let clip_near = |i0: usize, i1: usize| {
+                        clip::clip_near(to_view(i0), to_view(i1))
+                            .map(|(v0, v1)| (to_screen(v0), to_screen(v1)))
+                    };
+
+                    // How much of each segment has grown in so far
+                    #[cfg(feature = "reveal")]
+                    let portion = |n: u16, i0: u8, i1: u8| {
+                        reveal.edge(n, || {
+                            let z = |i: u8| to_view(i as usize).z;
+                            reveal::depth_rank((z(i0) >> 1) + (z(i1) >> 1), depth)
+                        })
+                    };
+
+                    #[cfg(not(feature = "reveal"))]
+                    let portion = |_: u16, _: u8, _: u8| None;
+
+                    // Transform vertices from model space into screen space, reusing
+                    // last frame's result while the view stands still
+                    #[cfg(not(feature = "streaming"))]
+                    if transformed_view != Some((rotation, location, depth, center, mirror, blend, clock)) {
+                        profile!(Transform, {
+                            for (i, screen) in screen_verts.iter_mut().take(OBJECT::NUM_VERTS).enumerate() {
+                                *screen = to_screen(to_view(i));
+                            }
+                        });
+                        transformed_view = Some((rotation, location, depth, center, mirror, blend, clock));
+                        hooks.post_transform(&mut screen_verts);
+                    }
+
+                    // Fill the faces before drawing the wireframe over them,
+                    // unless optional passes are skipped
+                    #[cfg(feature = "filled")]
+                    if !skip_optional {
+                        #[cfg(not(feature = "streaming"))]
+                        let position = |i: usize| mesh::pixels(screen_verts[i]);
+
+                        #[cfg(feature = "streaming")]
+                        let position = |i: usize| mesh::pixels(to_screen(to_view(i)));
+
+                        profile!(Draw, fill::draw_fills(
+                            &OBJECT::FILLS,
+                            position,
+                            |i| to_view(i).z,
+                            &SCREEN,
+                            mirror,
+                            |x, y, on| {
+                                if masked(vec2!(x as IFixed, y as IFixed)) {
+                                    return;
+                                }
+                                #[cfg(feature = "radar")]
+                                if !sector.shows(x, y) {
+                                    return;
+                                }
+                                if on {
+                                    frame.set_pixel(x, y);
+                                } else {
+                                    frame.clear_pixel(x, y);
+                                }
+                            },
+                        ));
+                    }
+
+                    // Keep the scene out from under the HUD
+                    let put_pixel = |x: u32, y: u32| {
+                        #[cfg(feature = "radar")]
+                        if !sector.shows(x, y) {
+                            return;
+                        }
+                        if !masked(vec2!(x as IFixed, y as IFixed)) {
+                            frame.set_pixel(x, y);
+                        }
+                    };
+
+                    // Skip segments the radar trail does not reach before
+                    // rasterizing them
+                    #[cfg(feature = "radar")]
+                    let portion = {
+                        #[cfg(not(feature = "streaming"))]
+                        let position = |i: u8| screen_verts.get(i as usize).copied().map_or(clip::BEHIND_NEAR, mesh::pixels);
+
+                        #[cfg(feature = "streaming")]
+                        let position = |i: u8| mesh::pixels(to_screen(to_view(i as usize)));
+
+                        let mut portion = portion;
+                        let sector = &sector;
+                        move |n: u16, i0: u8, i1: u8| {
+                            if sector.hits(position(i0), position(i1), &SCREEN) {
+                                portion(n, i0, i1)
+                            } else {
+                                Some(0)
+                            }
+                        }
+                    };
+
+                    #[cfg(not(feature = "streaming"))]
+                    {
+                        #[cfg_attr(not(feature = "checked-indices"), allow(unused_variables))]
+                        let valid = profile!(Draw, OBJECT.draw(&screen_verts, &SCREEN, mirror, clip_near, portion, put_pixel));
+
+                        // Latch the LED on so a bad mesh is visible without a serial
+                        // connection
+                        #[cfg(feature = "checked-indices")]
+                        if !valid {
+                            bad_index_led.set_high();
+                        }
+                    }
+
+                    // Transforms happen while drawing, and count towards it
+                    #[cfg(feature = "streaming")]
+                    profile!(Draw, OBJECT.draw_streamed::<SCRATCH_VERTS, _, _, _, _>(
+                        |i| to_screen(to_view(i)),
+                        &SCREEN,
+                        mirror,
+                        clip_near,
+                        portion,
+                        put_pixel,
+                    ));
+                }
+            });
+        }
+
+        #[cfg(feature = "reveal")]
+        reveal.advance(steps);
+
+        // Redraw the diagnostic code if clearing the scene erased part of it
+        #[cfg(feature = "crash-log")]
+        if safe_mode && frame.is_damaged(Layer::Overlay) {
+            frame.clear_layer(Layer::Overlay);
+            crash_log.draw_diagnostic(&mut frame);
+        }
+
+        // Frame statistics are an optional subsystem, left off in safe mode
+        #[cfg(feature = "fps-overlay")]
+        if !safe_mode {
+            #[cfg(feature = "pixel-shift")]
+            stats.set_offset(pixel_shift.offset());
+            stats.draw(&mut frame);
+        }
+
+        hooks.pre_flush(&mut frame);
+
+        #[cfg(feature = "vsync")]
+        pacer.wait();
+
+        #[cfg(not(feature = "async-flush"))]
+        profile!(Flush, frame.flush(&mut display)).unwrap();
+
+        // Only staging counts towards the flush, the sending overlaps with
+        // the next frame
+        #[cfg(feature = "async-flush")]
+        profile!(Flush, frame.flush_async(&mut async_flush)).unwrap();
+
+        #[cfg(any(feature = "fps", feature = "fps-overlay"))]
+        if let Some(fps) = fps_counter.update() {
+            #[cfg(feature = "fps")]
+            let _ = ufmt::uwriteln!(fps_serial, "{}", fps);
+
+            #[cfg(feature = "fps-overlay")]
+            stats.set_fps(fps);
+        }
+
+        #[cfg(feature = "ab-compare")]
+        ab.end_frame(&frame, profiler.serial());
+
+        #[cfg(feature = "profile")]
+        profiler.update();
+    }
+}
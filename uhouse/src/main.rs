@@ -0,0 +1,12 @@
+//! Firmware for the board and display picked by Cargo feature
+//! 
+//! See the `boards/` directory for one binary per board and display, with
+//! its features and target already set.
+
+#![no_std]
+#![no_main]
+
+#[uhouse::entry]
+fn main() -> ! {
+    uhouse::run()
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "frame-pacing")]
+
+//! Fixed timestep animation
+//! 
+//! Without pacing the animation advances once per rendered frame, so the
+//! house spins faster on a 400 kHz bus than on a 100 kHz one. [`FramePacer`]
+//! counts milliseconds on TC0 and turns the time since the last frame into a
+//! whole number of animation steps at [`TARGET_FPS`](crate::TARGET_FPS),
+//! waiting out the rest of the step when a frame finishes early. Motion then
+//! runs at the same speed however fast frames are drawn.
+
+use core::cell::Cell;
+use arduino_hal::pac::TC0;
+use avr_device::interrupt::Mutex;
+
+use crate::{CLOCK_FREQ, TARGET_FPS};
+
+/// Milliseconds per second, the unit the step remainder is kept in along
+/// with [`TARGET_FPS`]
+const MS_PER_SECOND: u32 = 1000;
+
+/// Most animation steps taken in one frame
+/// 
+/// A frame held up for longer, such as by a slow EEPROM write, drops the
+/// time past this instead of jumping the animation ahead.
+const MAX_STEPS: u8 = 4;
+
+const _: () = assert!(TARGET_FPS >= 1, "target frame rate must be at least 1");
+
+/// Milliseconds since the pacer started, incremented by TC0
+static MILLIS: Mutex<Cell<u16>> = Mutex::new(Cell::new(0));
+
+/// Read the millisecond counter
+fn millis() -> u16 {
+    avr_device::interrupt::free(|cs| MILLIS.borrow(&cs).get())
+}
+
+pub struct FramePacer {
+    /// Millisecond count at the last step
+    last: u16,
+
+    /// Time since the last step, in milliseconds times [`TARGET_FPS`]
+    remainder: u32,
+}
+
+impl FramePacer {
+
+    /// Start counting milliseconds
+    /// 
+    /// Interrupts must not yet be enabled before calling
+    pub unsafe fn new(tc0: TC0) -> Self {
+        use arduino_hal::pac::tc0::tccr0b::CS0_A;
+
+        // Clear on compare match every millisecond
+        tc0.tccr0a.write(|w| w.wgm0().bits(0b10));
+        tc0.tccr0b.write(|w| w.cs0().variant(CS0_A::PRESCALE_64));
+        tc0.tcnt0.write(|w| w.bits(0));
+        tc0.ocr0a.write(|w| w.bits((CLOCK_FREQ / 64 / MS_PER_SECOND - 1) as u8));
+        tc0.timsk0.write(|w| w.ocie0a().set_bit()); // Enable this interrupt
+
+        Self {
+            last: 0,
+            remainder: 0,
+        }
+    }
+
+    /// Wait until at least one animation step is due, and return how many
+    /// are
+    /// 
+    /// Call once per frame and advance the animation by the returned number
+    /// of steps, between 1 and [`MAX_STEPS`].
+    pub fn wait(&mut self) -> u8 {
+        let elapsed = loop {
+            let now = millis();
+            let elapsed = now.wrapping_sub(self.last) as u32 * TARGET_FPS as u32 + self.remainder;
+            if elapsed >= MS_PER_SECOND {
+                self.last = now;
+                break elapsed;
+            }
+        };
+
+        let steps = elapsed / MS_PER_SECOND;
+        if steps > MAX_STEPS as u32 {
+            self.remainder = 0;
+            MAX_STEPS
+        } else {
+            self.remainder = elapsed % MS_PER_SECOND;
+            steps as u8
+        }
+    }
+}
+
+interrupt! {
+    fn TIMER0_COMPA() {
+        isr_budget!(Timer0, {
+            avr_device::interrupt::free(|cs| {
+                let millis = MILLIS.borrow(&cs);
+                millis.set(millis.get().wrapping_add(1));
+            });
+        });
+    }
+}
@@ -0,0 +1,146 @@
+//! What to do after a panic
+//! 
+//! The panic handler records the panic where the crash log is enabled, then
+//! hands over to a [`PanicPolicy`] chosen by Cargo feature. Halting keeps
+//! the last frame on screen for a developer to look at, while a product left
+//! unattended would rather reset and carry on. Writing the panic on the
+//! display tells a halted board apart from a locked-up display bus.
+
+use core::panic::PanicInfo;
+use arduino_hal::hal::wdt::{Timeout, Wdt};
+use display_interface::WriteOnlyDataCommand;
+use ssd1306::command::Command;
+
+use crate::Target;
+use crate::board::Board;
+
+#[cfg(feature = "i2c")]
+use ssd1306::I2CDisplayInterface;
+
+#[cfg(feature = "spi")]
+use ssd1306::prelude::SPIInterface;
+
+/// Behavior of the panic handler once the panic is recorded
+pub trait PanicPolicy {
+    fn on_panic(info: &PanicInfo) -> !;
+}
+
+/// Stop with the last frame left on screen, the default
+#[allow(unused)]
+pub struct Halt;
+
+impl PanicPolicy for Halt {
+    fn on_panic(_info: &PanicInfo) -> ! {
+        loop {}
+    }
+}
+
+/// Reset through the watchdog right away
+/// 
+/// Selected by the `panic-reset` feature.
+#[allow(unused)]
+pub struct Reset;
+
+impl PanicPolicy for Reset {
+    fn on_panic(_info: &PanicInfo) -> ! {
+        watchdog_reset()
+    }
+}
+
+/// Time the panic is shown for before resetting, in milliseconds
+const SHOW_MS: u16 = 2000;
+
+/// Watchdog timeout started before the display is touched, longer than
+/// [`SHOW_MS`]
+const SHOW_TIMEOUT: Timeout = Timeout::Ms4000;
+
+/// Invert the display to show that a panic happened, then reset through
+/// the watchdog
+/// 
+/// Selected by the `panic-show` feature. The last frame stays visible,
+/// inverted, for [`SHOW_MS`]. If the panic happened in the middle of a
+/// transfer to the display the inversion may not take, and the device
+/// resets all the same. The I2C driver waits on the bus without a timeout,
+/// so the watchdog is started for [`SHOW_TIMEOUT`] first, resetting a
+/// device whose display holds the bus.
+#[allow(unused)]
+pub struct ShowThenReset;
+
+impl PanicPolicy for ShowThenReset {
+    fn on_panic(_info: &PanicInfo) -> ! {
+        let _watchdog = start_watchdog(SHOW_TIMEOUT);
+
+        // SAFETY: The panicking code is abandoned, so nothing else uses the
+        // display bus from here on
+        let mut interface = unsafe { steal_display_interface() };
+
+        let _ = Command::Invert(true).send(&mut interface);
+
+        arduino_hal::delay_ms(SHOW_MS);
+        watchdog_reset()
+    }
+}
+
+/// Write where the panic happened and its message on the display, then halt
+/// 
+/// Selected by the `panic-display` feature, see
+/// [`panicscreen`](crate::panicscreen).
+#[cfg(feature = "panic-display")]
+pub struct ShowMessage;
+
+#[cfg(feature = "panic-display")]
+impl PanicPolicy for ShowMessage {
+    fn on_panic(info: &PanicInfo) -> ! {
+        // SAFETY: The panicking code is abandoned, so nothing else uses the
+        // display bus from here on
+        let interface = unsafe { steal_display_interface() };
+
+        crate::panicscreen::show(interface, info)
+    }
+}
+
+/// Set up the display bus again from scratch, at a slow and safe speed, to
+/// send commands to the display on the way to a reset
+/// 
+/// # Safety
+/// 
+/// The display must not be used through any other interface afterwards.
+#[allow(unused)]
+pub unsafe fn steal_display_interface() -> impl WriteOnlyDataCommand {
+    // Abandon the frame being sent from the interrupt, if any
+    #[cfg(feature = "async-flush")]
+    crate::twiflush::stop();
+
+    let dp = arduino_hal::Peripherals::steal();
+    let pins = arduino_hal::pins!(dp);
+
+    #[cfg(feature = "i2c")]
+    let interface = I2CDisplayInterface::new(Target::i2c(dp.TWI, i2c_pins!(pins), 100000));
+
+    #[cfg(feature = "spi")]
+    let interface = {
+        let (spi, cs) = Target::spi(dp.SPI, spi_pins!(pins), Default::default());
+        SPIInterface::new(spi, pins.d9.into_output(), cs)
+    };
+
+    interface
+}
+
+/// Reset the device through the watchdog
+#[allow(unused)]
+pub fn watchdog_reset() -> ! {
+    let _watchdog = start_watchdog(Timeout::Ms16);
+    loop {}
+}
+
+/// Start the watchdog, or restart it, to reset the device after `timeout`
+#[allow(unused)]
+fn start_watchdog(timeout: Timeout) -> Wdt {
+    // SAFETY: The device is about to reset, so nothing else uses the
+    // watchdog from here on
+    let dp = unsafe { arduino_hal::Peripherals::steal() };
+
+    let mut watchdog = Wdt::new(dp.WDT, &dp.CPU.mcusr);
+    let _ = watchdog.start(timeout);
+    watchdog
+}
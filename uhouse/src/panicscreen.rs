@@ -0,0 +1,257 @@
+#![cfg(feature = "panic-display")]
+
+//! Panic message on the display
+//! 
+//! A board halted by a panic looks just like one whose display bus locked
+//! up. With the `panic-display` feature the panic handler sets the display
+//! up again from scratch, clears it and writes where the panic happened and
+//! its message in a 3x5 font, one line of text per page. Lowercase letters
+//! are shown as capitals and characters missing from the font as spaces.
+//! 
+//! Nothing is drawn into the framebuffer, whose state is unknown after a
+//! panic. Every character is sent straight to the display as the four
+//! columns of its page, so showing the message takes almost no stack.
+//! 
+//! If the display does not answer, the LED on D13 blinks [`BLINK_PATTERN`]
+//! instead. The I2C driver waits on the bus without a timeout, so a bus
+//! held by a hung display would halt the handler before it could blink.
+//! With `i2c` the display is first addressed straight through the TWI
+//! registers, giving up on it after [`PROBE_POLLS`] polls of every step.
+
+use core::panic::PanicInfo;
+use avr_progmem::progmem;
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use ssd1306::{Ssd1306, command::AddrMode, prelude::*};
+use uhouse_core::numfmt;
+
+use crate::text::Text;
+use crate::Panel;
+
+/// Glyph height in pixels
+const GLYPH_HEIGHT: usize = 5;
+
+/// Columns per character, a 3 pixel glyph and a space
+const ADVANCE: usize = 4;
+
+/// Characters per line of text
+const COLUMNS: usize = Panel::WIDTH as usize / ADVANCE;
+
+/// Lines of text, one per page
+const LINES: usize = Panel::HEIGHT as usize / 8;
+
+/// Characters in the font, in [`FONT`] order
+const CHARSET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ.:/_-()`',!?=<>[]";
+
+progmem! {
+
+    /// Glyphs of [`CHARSET`], one byte per row from the top with the leftmost
+    /// pixel in bit 2
+    static progmem FONT: [[u8; GLYPH_HEIGHT]; 53] = [
+        [0b111, 0b101, 0b101, 0b101, 0b111],
+        [0b010, 0b110, 0b010, 0b010, 0b111],
+        [0b111, 0b001, 0b111, 0b100, 0b111],
+        [0b111, 0b001, 0b111, 0b001, 0b111],
+        [0b101, 0b101, 0b111, 0b001, 0b001],
+        [0b111, 0b100, 0b111, 0b001, 0b111],
+        [0b111, 0b100, 0b111, 0b101, 0b111],
+        [0b111, 0b001, 0b001, 0b010, 0b010],
+        [0b111, 0b101, 0b111, 0b101, 0b111],
+        [0b111, 0b101, 0b111, 0b001, 0b111],
+        [0b010, 0b101, 0b111, 0b101, 0b101],
+        [0b110, 0b101, 0b110, 0b101, 0b110],
+        [0b011, 0b100, 0b100, 0b100, 0b011],
+        [0b110, 0b101, 0b101, 0b101, 0b110],
+        [0b111, 0b100, 0b110, 0b100, 0b111],
+        [0b111, 0b100, 0b110, 0b100, 0b100],
+        [0b011, 0b100, 0b101, 0b101, 0b011],
+        [0b101, 0b101, 0b111, 0b101, 0b101],
+        [0b111, 0b010, 0b010, 0b010, 0b111],
+        [0b001, 0b001, 0b001, 0b101, 0b010],
+        [0b101, 0b101, 0b110, 0b101, 0b101],
+        [0b100, 0b100, 0b100, 0b100, 0b111],
+        [0b101, 0b111, 0b111, 0b101, 0b101],
+        [0b110, 0b101, 0b101, 0b101, 0b101],
+        [0b010, 0b101, 0b101, 0b101, 0b010],
+        [0b110, 0b101, 0b110, 0b100, 0b100],
+        [0b010, 0b101, 0b101, 0b110, 0b011],
+        [0b110, 0b101, 0b110, 0b101, 0b101],
+        [0b011, 0b100, 0b010, 0b001, 0b110],
+        [0b111, 0b010, 0b010, 0b010, 0b010],
+        [0b101, 0b101, 0b101, 0b101, 0b111],
+        [0b101, 0b101, 0b101, 0b101, 0b010],
+        [0b101, 0b101, 0b111, 0b111, 0b101],
+        [0b101, 0b101, 0b010, 0b101, 0b101],
+        [0b101, 0b101, 0b010, 0b010, 0b010],
+        [0b111, 0b001, 0b010, 0b100, 0b111],
+        [0b000, 0b000, 0b000, 0b000, 0b010],
+        [0b000, 0b010, 0b000, 0b010, 0b000],
+        [0b001, 0b001, 0b010, 0b100, 0b100],
+        [0b000, 0b000, 0b000, 0b000, 0b111],
+        [0b000, 0b000, 0b111, 0b000, 0b000],
+        [0b001, 0b010, 0b010, 0b010, 0b001],
+        [0b100, 0b010, 0b010, 0b010, 0b100],
+        [0b100, 0b010, 0b000, 0b000, 0b000],
+        [0b010, 0b010, 0b000, 0b000, 0b000],
+        [0b000, 0b000, 0b000, 0b010, 0b100],
+        [0b010, 0b010, 0b010, 0b000, 0b010],
+        [0b111, 0b001, 0b010, 0b000, 0b010],
+        [0b000, 0b111, 0b000, 0b111, 0b000],
+        [0b001, 0b010, 0b100, 0b010, 0b001],
+        [0b100, 0b010, 0b001, 0b010, 0b100],
+        [0b011, 0b010, 0b010, 0b010, 0b011],
+        [0b110, 0b010, 0b010, 0b010, 0b110],
+    ];
+}
+
+/// Pick how the LED blinks when the display does not answer here, as
+/// alternating on and off times in milliseconds, repeated forever
+/// 
+/// Three short blinks and a pause, unlike anything the firmware does while
+/// running.
+const BLINK_PATTERN: [u16; 6] = [150, 150, 150, 150, 150, 1000];
+
+/// Pick how many times the bus is polled for each step of the probe here,
+/// before the display is taken as hung
+/// 
+/// A byte takes about 1500 cycles at the 100 kHz the handler runs the bus
+/// at, and a poll a few cycles, so this allows for several bytes.
+#[cfg(feature = "i2c")]
+const PROBE_POLLS: u16 = 4000;
+
+/// Address of the display on the bus, the one `I2CDisplayInterface::new`
+/// uses
+#[cfg(feature = "i2c")]
+const ADDRESS: u8 = 0x3C;
+
+/// TWI status codes of a master transmitter, with the prescaler bits masked
+/// off
+#[cfg(feature = "i2c")]
+mod status {
+    pub const START: u8 = 0x08;
+    pub const ADDRESS_ACK: u8 = 0x18;
+}
+
+/// Clear the display and write the panic on it, or blink the LED if the
+/// display does not answer
+pub fn show<DI: WriteOnlyDataCommand>(interface: DI, info: &PanicInfo) -> ! {
+    let (line, file) = match info.location() {
+        Some(location) => (location.line().min(u16::MAX as u32) as u16, location.file()),
+        None => (0, ""),
+    };
+    let mut digits = [0; numfmt::U16_STR_LEN];
+    let digits = numfmt::fmt_u16(line, &mut digits).as_bytes();
+
+    // The first line is the header and the line number, cut off at the end
+    // of the line
+    let mut header = [0; COLUMNS];
+    let start = Text::PanicAtLine.copy_to(&mut header).len();
+    let end = (start + digits.len()).min(COLUMNS);
+    header[start..end].copy_from_slice(&digits[..end - start]);
+    let header = &header[..end];
+
+    // Formatted messages would pull in `core::fmt`, so only plain ones are
+    // shown
+    let message = info.message().as_str().unwrap_or("");
+
+    // Each part starts on a line of its own and wraps onto the next
+    let parts: [&[u8]; 3] = [header, file.as_bytes(), message.as_bytes()];
+    let mut lines = parts.iter().flat_map(|part| part.chunks(COLUMNS));
+
+    #[cfg(feature = "i2c")]
+    if !probe() {
+        blink();
+    }
+
+    if write(interface, &mut lines).is_err() {
+        blink();
+    }
+    loop {}
+}
+
+/// Address the display and let go of the bus, returning whether it answered
+/// without any step taking more than [`PROBE_POLLS`] polls
+#[cfg(feature = "i2c")]
+fn probe() -> bool {
+    // SAFETY: The panicking code is abandoned, and the display interface
+    // set the bus up and is not using it
+    let twi = unsafe { arduino_hal::Peripherals::steal() }.TWI;
+    let transferred = || poll(|| twi.twcr.read().twint().bit_is_set());
+    let code = || twi.twsr.read().bits() & 0xF8;
+
+    twi.twcr.write(|w| w.twint().set_bit().twsta().set_bit().twen().set_bit());
+    let answered = transferred() && code() == status::START && {
+        twi.twdr.write(|w| w.bits(ADDRESS << 1));
+        twi.twcr.write(|w| w.twint().set_bit().twen().set_bit());
+        transferred() && code() == status::ADDRESS_ACK
+    };
+
+    twi.twcr.write(|w| w.twint().set_bit().twsto().set_bit().twen().set_bit());
+    poll(|| twi.twcr.read().twsto().bit_is_clear()) && answered
+}
+
+/// Poll `done` until it returns true, at most [`PROBE_POLLS`] times,
+/// returning whether it did
+#[cfg(feature = "i2c")]
+fn poll(done: impl Fn() -> bool) -> bool {
+    (0..PROBE_POLLS).any(|_| done())
+}
+
+/// Set the display up and write one line of text on every page, leaving
+/// the rest of each page blank
+fn write<'a, DI, L>(interface: DI, lines: &mut L) -> Result<(), DisplayError>
+where
+    DI: WriteOnlyDataCommand,
+    L: Iterator<Item = &'a [u8]>,
+{
+    let mut display = Ssd1306::new(interface, Panel{}, DisplayRotation::Rotate0);
+    display.init_with_addr_mode(AddrMode::Horizontal)?;
+
+    for page in 0..LINES as u8 {
+        display.set_draw_area(
+            (Panel::OFFSETX, (page << 3) + Panel::OFFSETY),
+            (Panel::OFFSETX + Panel::WIDTH, ((page + 1) << 3) + Panel::OFFSETY),
+        )?;
+        let text = lines.next().unwrap_or(&[]);
+        for column in 0..COLUMNS {
+            display.draw(&glyph_columns(text.get(column).copied().unwrap_or(b' ')))?;
+        }
+    }
+    Ok(())
+}
+
+/// The columns of a character as sent to the display, with the top row in
+/// bit 0, followed by a blank column
+fn glyph_columns(c: u8) -> [u8; ADVANCE] {
+    let mut columns = [0; ADVANCE];
+    let index = match CHARSET.iter().position(|&known| known == c.to_ascii_uppercase()) {
+        Some(index) => index,
+        None => return columns,
+    };
+    for (dy, row) in FONT.load_at(index).iter().enumerate() {
+        for (dx, column) in columns.iter_mut().take(3).enumerate() {
+            if row & 0b100 >> dx != 0 {
+                *column |= 1 << dy;
+            }
+        }
+    }
+    columns
+}
+
+/// Blink the LED on D13 in [`BLINK_PATTERN`] forever
+fn blink() -> ! {
+    // SAFETY: The panicking code is abandoned, and the display interface
+    // that may share the pin has given up
+    let dp = unsafe { arduino_hal::Peripherals::steal() };
+    let pins = arduino_hal::pins!(dp);
+    let mut led = pins.d13.into_output();
+    loop {
+        for (i, &ms) in BLINK_PATTERN.iter().enumerate() {
+            if i % 2 == 0 {
+                led.set_high();
+            } else {
+                led.set_low();
+            }
+            arduino_hal::delay_ms(ms);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+#![cfg(feature = "profile")]
+
+use ufmt::{uwriteln, uWrite};
+use core::cell::Cell;
+use avr_device::interrupt::Mutex;
+use super::CLOCK_FREQ;
+use crate::text::Text;
+
+use crate::clock::now;
+
+/// Stages of the frame whose cycles are accumulated
+#[derive(Copy, Clone)]
+pub enum Section {
+    Transform,
+    Draw,
+    Flush,
+}
+
+const NUM_SECTIONS: usize = 3;
+
+const SECTION_NAMES: [Text; NUM_SECTIONS] = [Text::Transform, Text::Draw, Text::Flush];
+
+/// Cycles each [`Section`] may take per frame
+/// 
+/// Frames where a section exceeds its cap are counted in the report.
+const SECTION_BUDGETS: [u32; NUM_SECTIONS] = [
+    CLOCK_FREQ / 200,
+    CLOCK_FREQ / 50,
+    CLOCK_FREQ / 50,
+];
+
+/// Cycles all sections together may take per frame, for 20 frames per second
+const FRAME_BUDGET: u32 = CLOCK_FREQ / 20;
+
+/// Cycles accumulated per [`Section`] since the last frame
+static TOTALS: Mutex<[Cell<u32>; NUM_SECTIONS]> = Mutex::new([
+    Cell::new(0),
+    Cell::new(0),
+    Cell::new(0),
+]);
+
+/// Evaluate `f` and add the cycles it took to `section`
+#[inline(always)]
+pub fn measure<R, F: FnOnce() -> R>(section: Section, f: F) -> R {
+    let start = now();
+    let result = f();
+    let elapsed = now().wrapping_sub(start);
+    avr_device::interrupt::free(|cs| {
+        let total = &TOTALS.borrow(&cs)[section as usize];
+        total.set(total.get().wrapping_add(elapsed));
+    });
+    result
+}
+
+pub struct Profiler<W: uWrite> {
+    last_report: u32,
+    serial: W,
+
+    /// Frames since the last report
+    frames: u16,
+
+    /// Cycles per section since the last report
+    totals: [u32; NUM_SECTIONS],
+
+    /// Frames per section over its budget since the last report, with the
+    /// whole frame last
+    overruns: [u16; NUM_SECTIONS + 1],
+
+    /// Set when the last frame exceeded any budget
+    over_budget: bool,
+}
+
+impl<W> Profiler<W> where W: uWrite {
+
+    /// Create a new profiler
+    /// 
+    /// This takes full ownership of the serial device. The cycle counter
+    /// must already be started, see [`clock::start`](crate::clock::start)
+    pub fn new(serial: W) -> Self {
+        Self {
+            last_report: now(),
+            serial,
+            frames: 0,
+            totals: [0; NUM_SECTIONS],
+            overruns: [0; NUM_SECTIONS + 1],
+            over_budget: false,
+        }
+    }
+
+    /// Serial device the profiler reports to, for other diagnostics to share
+    #[allow(unused)]
+    pub fn serial(&mut self) -> &mut W {
+        &mut self.serial
+    }
+
+    /// Whether the last frame exceeded the frame budget or that of any
+    /// section
+    /// 
+    /// The backdrop and filling are skipped for a frame while this is set,
+    /// to keep the frame rate stable.
+    pub fn over_budget(&self) -> bool {
+        self.over_budget
+    }
+
+    /// Update the profiler, once per frame
+    /// 
+    /// Checks the cycles spent in each section this frame against its budget.
+    /// About once a second, prints the frames counted, the average cycles
+    /// per frame spent in each section and how many frames went over budget
+    /// to serial, then starts accumulating again
+    pub fn update(&mut self) {
+        let mut frame = [0; NUM_SECTIONS];
+        avr_device::interrupt::free(|cs| {
+            for (cycles, cell) in frame.iter_mut().zip(TOTALS.borrow(&cs)) {
+                *cycles = cell.replace(0);
+            }
+        });
+
+        self.frames = self.frames.saturating_add(1);
+        self.over_budget = false;
+        let sections = self.totals.iter_mut()
+            .zip(&mut self.overruns)
+            .zip(frame.iter().zip(SECTION_BUDGETS));
+        for ((total, overruns), (&cycles, budget)) in sections {
+            *total = total.wrapping_add(cycles);
+            if cycles > budget {
+                *overruns = overruns.saturating_add(1);
+                self.over_budget = true;
+            }
+        }
+        if frame.iter().sum::<u32>() > FRAME_BUDGET {
+            self.overruns[NUM_SECTIONS] = self.overruns[NUM_SECTIONS].saturating_add(1);
+            self.over_budget = true;
+        }
+
+        let now = now();
+        if now.wrapping_sub(self.last_report) < CLOCK_FREQ {
+            return;
+        }
+        self.last_report = now;
+
+        // The frame count first, as the fps feature prints it
+        let _ = uwriteln!(self.serial, "{}", self.frames);
+        for ((name, total), overruns) in SECTION_NAMES.iter().zip(self.totals).zip(self.overruns) {
+            let average = total / self.frames as u32;
+            let _ = uwriteln!(self.serial, "{} {} {} {}", *name, average, Text::Over, overruns);
+        }
+        let _ = uwriteln!(self.serial, "{} {} {}", Text::Frame, Text::Over, self.overruns[NUM_SECTIONS]);
+
+        #[cfg(feature = "isr-budget")]
+        crate::isr::report(&mut self.serial);
+
+        self.frames = 0;
+        self.totals = [0; NUM_SECTIONS];
+        self.overruns = [0; NUM_SECTIONS + 1];
+    }
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "remote")]
+
+//! Remote control over serial
+//! 
+//! Single-byte commands received over serial let host tooling reset the
+//! device, put it into the bootloader to flash it or time its frames, without
+//! anyone touching the reset button. Every command is acknowledged with a
+//! line of text before it takes effect.
+//! 
+//! | Command | Effect                                  |
+//! |---------|-----------------------------------------|
+//! | `r`     | Soft reset through the watchdog         |
+//! | `b`     | Jump to the bootloader                  |
+//! | `t`     | Time the next [`BENCH_FRAMES`] frames   |
+//! | `?`     | Print the version and the command list  |
+//! 
+//! The benchmark reports `bench <frames> <cycles>` once the frames are done,
+//! with the cycles they took together on the [cycle counter](crate::clock).
+//! 
+//! Meshes are tables in program memory, which only the bootloader can write,
+//! so a new mesh is uploaded by flashing it after `b`.
+//! 
+//! The bytes are shared with the console, see [`command`](crate::command).
+
+use embedded_hal::serial::{Read, Write};
+use ufmt::{uwriteln, uWrite};
+
+use crate::clock;
+use crate::command::Command;
+use crate::hooks::FrameHooks;
+use crate::panic_policy::watchdog_reset;
+use crate::text::Text;
+
+/// Pick how many frames the benchmark times here
+const BENCH_FRAMES: u16 = 64;
+
+/// Word address of the Optiboot bootloader on the UNO
+/// 
+/// Optiboot occupies the last 512 bytes of flash. Entered by a jump with
+/// `MCUSR` cleared, it waits for an upload instead of starting the
+/// application right away as it does after a watchdog reset.
+const BOOTLOADER: usize = (32 * 1024 - 512) / 2;
+
+pub struct Remote<S: uWrite + Read<u8> + Write<u8>> {
+    serial: S,
+
+    /// Cycle count at the start of the benchmark and the frames timed so
+    /// far, while one runs
+    bench: Option<(u32, u16)>,
+}
+
+impl<S> Remote<S> where S: uWrite + Read<u8> + Write<u8> {
+
+    /// Create a remote control
+    /// 
+    /// This takes full ownership of the serial device, so it cannot be used
+    /// together with the FPS counter, profiler or screenshots
+    pub fn new(serial: S) -> Self {
+        Self { serial, bench: None }
+    }
+
+    /// Act on a command byte
+    fn command(&mut self, byte: u8) {
+        match Command::parse(byte) {
+            Some(Command::Reset) => {
+                let _ = uwriteln!(self.serial, "{}", Text::OkReset);
+                self.flush();
+                watchdog_reset();
+            }
+            Some(Command::Bootloader) => {
+                let _ = uwriteln!(self.serial, "{}", Text::OkBootloader);
+                self.flush();
+                enter_bootloader();
+            }
+            Some(Command::Bench) => {
+                let _ = uwriteln!(self.serial, "{}", Text::OkBench);
+                self.bench = Some((clock::now(), 0));
+            }
+            Some(Command::Help) => {
+                let _ = uwriteln!(self.serial, "uhouse {}", env!("CARGO_PKG_VERSION"));
+                let _ = uwriteln!(self.serial, "{}", Text::Commands);
+            }
+            Some(Command::LineEnd) => {}
+            _ => {
+                let _ = uwriteln!(self.serial, "{} {}", Text::UnknownCommand, byte);
+            }
+        }
+    }
+
+    /// Wait for the acknowledgement to leave the transmitter before a reset
+    fn flush(&mut self) {
+        while self.serial.flush().is_err() {}
+
+        // The last character is still being shifted out
+        arduino_hal::delay_ms(1);
+    }
+}
+
+impl<S> FrameHooks for Remote<S> where S: uWrite + Read<u8> + Write<u8> {
+
+    /// Report the benchmark once it has timed every frame, then handle
+    /// every command received since the last frame
+    fn pre_update(&mut self) {
+        if let Some((start, frames)) = self.bench {
+            if frames == BENCH_FRAMES {
+                let cycles = clock::now().wrapping_sub(start);
+                let _ = uwriteln!(self.serial, "{} {} {}", Text::Bench, frames, cycles);
+                self.bench = None;
+            } else {
+                self.bench = Some((start, frames + 1));
+            }
+        }
+        while let Ok(byte) = self.serial.read() {
+            self.command(byte);
+        }
+    }
+}
+
+/// Jump to the bootloader, to flash the device as if it had just been reset
+/// by the reset pin
+fn enter_bootloader() -> ! {
+    avr_device::interrupt::disable();
+
+    // SAFETY: Interrupts are off and the application is abandoned, so
+    // nothing else uses the CPU registers from here on
+    let dp = unsafe { arduino_hal::Peripherals::steal() };
+
+    // SAFETY: Writing zero clears every reset flag
+    dp.CPU.mcusr.write(|w| unsafe { w.bits(0) });
+
+    // SAFETY: The bootloader is always present at this address on the UNO
+    // and never returns
+    let bootloader: extern "C" fn() -> ! = unsafe { core::mem::transmute(BOOTLOADER) };
+    bootloader()
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "reveal")]
+
+//! Construction reveal of the scene
+//! 
+//! For the first few seconds after start the edges of the mesh appear one
+//! after another rather than all at once, each growing from one end to the
+//! other. Every edge is given a phase, the frame it starts growing on,
+//! according to an [`Order`], and the mesh draws only the part of each edge
+//! that has grown so far.
+//! 
+//! The reveal runs on the clock of the scene's animation. It starts over
+//! whenever the animation does, on moving to the next scene or a restart
+//! over the console, and is paused and sped up along with it. Scenes have no
+//! timeline of their own, so it cannot be scheduled later in a scene.
+
+use uhouse_core::mesh::WHOLE;
+use uhouse_core::validate::MAX_RADIUS;
+use uhouse_core::vec::*;
+
+/// Frames from the first edge starting to grow until every edge is whole
+const REVEAL_FRAMES: u16 = 90;
+
+/// Frames each edge takes to grow
+const EDGE_FRAMES: u16 = 15;
+
+/// Latest frame an edge may start growing on
+const SPAN: u16 = REVEAL_FRAMES - EDGE_FRAMES;
+
+/// Edges whose phase is remembered
+/// 
+/// Edges past this recompute their phase every frame, which only matters
+/// for [`Order::Depth`].
+const MAX_EDGES: usize = 96;
+
+/// Depths ranked either side of the scene depth, the largest model radius
+/// 
+/// An orbiting model may reach past this, and ranks first or last.
+const REACH: i32 = MAX_RADIUS as i32;
+
+/// Phase of an edge not seen yet
+const UNSET: u8 = u8::MAX;
+
+const _: () = assert!(SPAN < UNSET as u16, "reveal phases must fit in a byte");
+
+/// Order edges appear in
+#[allow(unused)]
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Order {
+    /// In the order they are stored in the mesh
+    Index,
+
+    /// Nearest to the camera first, as seen when the edge first shows
+    Depth,
+
+    /// In a fixed pseudorandom order
+    Shuffled,
+}
+
+pub struct Reveal {
+    order: Order,
+
+    /// Edges in the mesh
+    edges: u16,
+
+    /// Frames since the reveal started
+    frame: u16,
+
+    /// Phase of each edge, or [`UNSET`]
+    phases: [u8; MAX_EDGES],
+}
+
+impl Reveal {
+
+    /// Start revealing a mesh of `edges` edges
+    pub const fn new(order: Order, edges: u16) -> Self {
+        Self {
+            order,
+            edges,
+            frame: 0,
+            phases: [UNSET; MAX_EDGES],
+        }
+    }
+
+    /// Advance by `steps` frames
+    pub fn advance(&mut self, steps: u8) {
+        self.frame = self.frame.saturating_add(steps as u16);
+    }
+
+    /// Portion of edge `edge` to draw out of [`WHOLE`], or `None` once the
+    /// reveal is over and every edge is drawn whole
+    /// 
+    /// `depth` gives the depth of the edge from 0 for nearest to 255 for
+    /// farthest, and is only called for [`Order::Depth`].
+    pub fn edge<D: FnOnce() -> u8>(&mut self, edge: u16, depth: D) -> Option<u8> {
+        if self.frame >= REVEAL_FRAMES {
+            return None;
+        }
+
+        let phase = match self.phases.get(edge as usize) {
+            Some(&phase) if phase != UNSET => phase,
+            _ => {
+                let phase = self.phase(edge, depth);
+                if let Some(slot) = self.phases.get_mut(edge as usize) {
+                    *slot = phase;
+                }
+                phase
+            }
+        };
+
+        let grown = self.frame.saturating_sub(phase as u16).min(EDGE_FRAMES);
+        Some((grown * WHOLE as u16 / EDGE_FRAMES) as u8)
+    }
+
+    /// Frame an edge starts growing on
+    fn phase<D: FnOnce() -> u8>(&self, edge: u16, depth: D) -> u8 {
+        let phase = match self.order {
+            Order::Index => edge as u32 * SPAN as u32 / self.edges.max(1) as u32,
+            Order::Depth => depth() as u32 * SPAN as u32 / 255,
+
+            // Multiplicative hash, spreading consecutive edges apart
+            Order::Shuffled => (edge.wrapping_mul(40503) >> 8) as u32 * (SPAN as u32 + 1) >> 8,
+        };
+        phase as u8
+    }
+}
+
+/// Rank a view space depth from 0, the nearest a vertex of a scene at
+/// `depth` gets, to 255, the farthest
+pub fn depth_rank(z: IFixed, depth: IFixed) -> u8 {
+    let near = depth as i32 - REACH;
+    ((z as i32 - near).clamp(0, 2*REACH)*255/(2*REACH)) as u8
+}
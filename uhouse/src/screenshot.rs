@@ -0,0 +1,97 @@
+#![cfg(feature = "screenshot")]
+
+//! Frame-accurate screenshots over serial
+//! 
+//! Pulling the trigger pin low, by a button or an external event such as a
+//! scope trigger, dumps the next completed frame to serial as a plain PBM
+//! image tagged with its frame number.
+
+use arduino_hal::port::{Pin, mode::{Input, PullUp}};
+use ssd1306::prelude::*;
+use ufmt::{uwriteln, uWrite};
+
+use crate::Display;
+use crate::framebuffer::FrameBuffer;
+use crate::hooks::FrameHooks;
+
+const WIDTH: u32 = Display::WIDTH as u32;
+const HEIGHT: u32 = Display::HEIGHT as u32;
+
+/// Pixels per line of output
+/// 
+/// Plain PBM lines should not be longer than 70 characters.
+const LINE_PIXELS: usize = 64;
+
+pub struct Screenshot<W: uWrite> {
+    serial: W,
+    trigger: Pin<Input<PullUp>>,
+
+    /// Trigger level seen at the start of the last frame
+    was_high: bool,
+
+    /// Set when the trigger fired and the current frame is to be captured
+    armed: bool,
+
+    /// Number of the current frame, counting from 0 at power on
+    frame: u32,
+}
+
+impl<W> Screenshot<W> where W: uWrite {
+
+    /// Create a screenshot trigger
+    /// 
+    /// This takes full ownership of the serial device, so it cannot be used
+    /// together with the FPS counter or profiler
+    pub fn new(serial: W, trigger: Pin<Input<PullUp>>) -> Self {
+        Self {
+            serial,
+            was_high: trigger.is_high(),
+            trigger,
+            armed: false,
+            frame: 0,
+        }
+    }
+
+    /// Write the framebuffer to serial as a plain PBM image
+    fn dump(&mut self, frame: &FrameBuffer) -> Result<(), W::Error> {
+        uwriteln!(self.serial, "P1")?;
+        uwriteln!(self.serial, "# frame {}", self.frame)?;
+        uwriteln!(self.serial, "{} {}", WIDTH, HEIGHT)?;
+
+        let mut line = [0; LINE_PIXELS];
+        for y in 0..HEIGHT {
+            for start in (0..WIDTH).step_by(LINE_PIXELS) {
+                let len = ((WIDTH - start) as usize).min(LINE_PIXELS);
+                for (x, c) in (start..).zip(&mut line[..len]) {
+                    *c = if frame.pixel(x, y) { b'1' } else { b'0' };
+                }
+
+                // SAFETY: Only ASCII digits were written
+                self.serial.write_str(unsafe { core::str::from_utf8_unchecked(&line[..len]) })?;
+                self.serial.write_char('\n')?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W> FrameHooks for Screenshot<W> where W: uWrite {
+
+    /// Arm a capture of this frame on a falling edge of the trigger
+    fn pre_update(&mut self) {
+        let high = self.trigger.is_high();
+        if self.was_high && !high {
+            self.armed = true;
+        }
+        self.was_high = high;
+    }
+
+    /// Dump the completed frame if armed
+    fn pre_flush(&mut self, frame: &mut FrameBuffer) {
+        if self.armed {
+            self.armed = false;
+            let _ = self.dump(frame);
+        }
+        self.frame = self.frame.wrapping_add(1);
+    }
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "scene-select")]
+
+//! Scene select screen
+//! 
+//! Shows a thumbnail of every scene in [`scenes!`] in a grid, with a box
+//! around the one highlighted, in place of the scene itself. The thumbnails
+//! are rendered by the build script with the same renderer as the scenes, so
+//! the screen only copies bitmaps out of program memory. Input is handled by
+//! [`SceneCycle`](crate::cycle::SceneCycle).
+
+use avr_progmem::wrapper::ProgMem;
+use uhouse_core::vec::*;
+
+use crate::framebuffer::FrameBuffer;
+use crate::{NUM_SCENES, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Thumbnails of the house and of every imported model, named after their
+/// objects
+#[allow(dead_code)]
+pub mod thumbnails {
+    use avr_progmem::progmem;
+
+    include!(concat!(env!("OUT_DIR"), "/thumbnails.rs"));
+}
+
+/// Width and height of a thumbnail, in pixels
+/// 
+/// Mirrors `THUMBNAIL_SIZE` in `build.rs`.
+const THUMBNAIL_SIZE: usize = 28;
+
+/// Bytes in a row of a thumbnail, with the leftmost pixel in the top bit
+const ROW_BYTES: usize = THUMBNAIL_SIZE.div_ceil(8);
+
+/// Bytes in a thumbnail
+pub const THUMBNAIL_BYTES: usize = ROW_BYTES*THUMBNAIL_SIZE;
+
+/// Width and height of a cell of the grid, a thumbnail with a margin for the
+/// box around it
+const CELL: IFixed = THUMBNAIL_SIZE as IFixed + 4;
+
+/// Cells in a row of the grid
+const COLUMNS: usize = (SCREEN_WIDTH / CELL) as usize;
+
+/// Cells in the widest row of the grid
+const WIDTH: usize = if NUM_SCENES < COLUMNS { NUM_SCENES } else { COLUMNS };
+
+/// Rows of the grid
+const ROWS: usize = NUM_SCENES.div_ceil(COLUMNS);
+
+const _: () = assert!(ROWS*CELL as usize <= SCREEN_HEIGHT as usize, "scene thumbnails do not fit on the screen");
+
+/// Top-left corner of the grid, centered on the screen
+const ORIGIN: Vec2 = vec2!(
+    (SCREEN_WIDTH - WIDTH as IFixed*CELL) / 2,
+    (SCREEN_HEIGHT - ROWS as IFixed*CELL) / 2
+);
+
+/// Draw the thumbnails, one per scene in order, with a box around the one
+/// at `highlighted`
+pub fn draw(frame: &mut FrameBuffer, thumbnails: &[&ProgMem<[u8; THUMBNAIL_BYTES]>], highlighted: usize) {
+    for (i, thumbnail) in thumbnails.iter().enumerate() {
+        let cell = ORIGIN + vec2!((i % COLUMNS) as IFixed*CELL, (i / COLUMNS) as IFixed*CELL);
+        blit(frame, cell + vec2!(2, 2), thumbnail);
+        if i == highlighted {
+            draw_box(frame, cell);
+        }
+    }
+}
+
+/// Copy a thumbnail to the scene with its top-left corner at `at`
+fn blit(frame: &mut FrameBuffer, at: Vec2, thumbnail: &ProgMem<[u8; THUMBNAIL_BYTES]>) {
+    for (i, byte) in thumbnail.iter().enumerate() {
+        let x = at.x as u32 + (i % ROW_BYTES) as u32*8;
+        let y = at.y as u32 + (i / ROW_BYTES) as u32;
+        for bit in 0..8 {
+            if byte & 0x80 >> bit != 0 {
+                frame.set_pixel(x + bit, y);
+            }
+        }
+    }
+}
+
+/// Draw a box along the edge of a cell
+fn draw_box(frame: &mut FrameBuffer, cell: Vec2) {
+    let (x0, y0) = (cell.x as u32, cell.y as u32);
+    let (x1, y1) = (x0 + CELL as u32 - 1, y0 + CELL as u32 - 1);
+    for x in x0..=x1 {
+        frame.set_pixel(x, y0);
+        frame.set_pixel(x, y1);
+    }
+    for y in y0..=y1 {
+        frame.set_pixel(x0, y);
+        frame.set_pixel(x1, y);
+    }
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "settings")]
+
+//! Runtime settings kept in EEPROM
+//! 
+//! With the `settings` feature, what is set over the serial console or with
+//! the joystick survives a reset: the animation speed and pause, the depth of
+//! the scene, the brightness of the display, the camera moved with the
+//! joystick and the scene being shown. They are loaded once at boot and
+//! written back whenever a command changes them, or the joystick comes back
+//! to rest.
+//! 
+//! The settings are stored as a [`VERSION`] byte, the fields and a
+//! CRC-16/CCITT-FALSE checksum of both, past the crash log. Settings from
+//! another version, a blank EEPROM or a save cut short by a reset fail the
+//! check and are replaced by [`Settings::DEFAULT`]. Only bytes that changed
+//! are written, through an [`EepromQueue`] serviced every frame.
+
+use arduino_hal::Eeprom;
+use ssd1306::prelude::Brightness;
+use uhouse_core::validate::crc16_update;
+use uhouse_core::vec::IFixed;
+
+use crate::eeprom_queue::EepromQueue;
+
+#[cfg(feature = "scene-cycle")]
+use crate::NUM_SCENES;
+
+/// EEPROM offset of the settings, past the crash log
+const BASE: u16 = 64;
+
+#[cfg(feature = "crash-log")]
+const _: () = assert!(BASE >= crate::crashlog::EEPROM_BYTES, "the settings overlap the crash log");
+
+/// Layout of the stored settings, to be changed whenever the fields below
+/// change
+const VERSION: u8 = 1;
+
+/// Bytes of the fields, without the version and checksum
+const FIELD_BYTES: usize = 10;
+
+/// Bytes taken in EEPROM
+const STORED_BYTES: usize = 1 + FIELD_BYTES + 2;
+
+/// Display brightnesses to pick from, dimmest first
+pub const BRIGHTNESSES: [Brightness; 5] = [
+    Brightness::DIMMEST,
+    Brightness::DIM,
+    Brightness::NORMAL,
+    Brightness::BRIGHT,
+    Brightness::BRIGHTEST,
+];
+
+/// Everything kept over a reset
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// Animation speed set over the console, as an index into its speeds
+    pub speed: u8,
+
+    /// Whether the animation is paused over the console
+    pub paused: bool,
+
+    /// Depth added to the scene's own over the console
+    pub depth: IFixed,
+
+    /// Index into [`BRIGHTNESSES`]
+    pub brightness: u8,
+
+    /// Index of the scene being shown, with the `scene-cycle` feature
+    pub scene: u8,
+
+    /// Orbit angle of the joystick camera, in 65536ths of a turn
+    pub orbit: u16,
+
+    /// Depth of the joystick camera
+    pub dolly: IFixed,
+}
+
+impl Settings {
+
+    /// Settings on a blank or corrupted EEPROM, those of a fresh start
+    pub const DEFAULT: Self = Self {
+        // One animation step per frame
+        speed: 2,
+        paused: false,
+        depth: 0,
+        brightness: 2,
+        scene: 0,
+        orbit: 0,
+        dolly: uhouse_core::house::MESH_DEPTH,
+    };
+
+    fn to_bytes(self) -> [u8; FIELD_BYTES] {
+        let depth = self.depth.to_le_bytes();
+        let orbit = self.orbit.to_le_bytes();
+        let dolly = self.dolly.to_le_bytes();
+        [
+            self.speed, self.paused as u8, depth[0], depth[1], self.brightness,
+            self.scene, orbit[0], orbit[1], dolly[0], dolly[1],
+        ]
+    }
+
+    fn from_bytes(bytes: [u8; FIELD_BYTES]) -> Self {
+        Self {
+            speed: bytes[0],
+            paused: bytes[1] != 0,
+            depth: IFixed::from_le_bytes([bytes[2], bytes[3]]),
+            brightness: bytes[4],
+            scene: bytes[5],
+            orbit: u16::from_le_bytes([bytes[6], bytes[7]]),
+            dolly: IFixed::from_le_bytes([bytes[8], bytes[9]]),
+        }
+    }
+}
+
+/// Settings as stored, with the version first and the checksum last
+fn stored_bytes(settings: Settings) -> [u8; STORED_BYTES] {
+    let mut bytes = [0; STORED_BYTES];
+    bytes[0] = VERSION;
+    bytes[1..=FIELD_BYTES].copy_from_slice(&settings.to_bytes());
+    let crc = bytes[..=FIELD_BYTES].iter().fold(0xffff, |crc, &byte| crc16_update(crc, byte));
+    bytes[FIELD_BYTES + 1..].copy_from_slice(&crc.to_le_bytes());
+    bytes
+}
+
+/// Settings in EEPROM
+pub struct Store {
+    eeprom: EepromQueue,
+
+    /// Settings last loaded or saved
+    saved: Settings,
+}
+
+impl Store {
+
+    /// Load the settings, falling back to [`Settings::DEFAULT`] if they do
+    /// not check out
+    /// 
+    /// A scene or brightness past the end of those built in, as after
+    /// flashing a build with fewer scenes, is reset to its default.
+    pub fn load(eeprom: Eeprom) -> (Self, Settings) {
+        let eeprom = EepromQueue::new(eeprom);
+        let mut bytes = [0; STORED_BYTES];
+        for (offset, byte) in (BASE..).zip(&mut bytes) {
+            *byte = eeprom.read_byte(offset);
+        }
+
+        let mut fields = [0; FIELD_BYTES];
+        fields.copy_from_slice(&bytes[1..=FIELD_BYTES]);
+        let mut settings = Settings::from_bytes(fields);
+        if stored_bytes(settings) != bytes {
+            settings = Settings::DEFAULT;
+        }
+        #[cfg(feature = "scene-cycle")]
+        if settings.scene as usize >= NUM_SCENES {
+            settings.scene = Settings::DEFAULT.scene;
+        }
+        if settings.brightness as usize >= BRIGHTNESSES.len() {
+            settings.brightness = Settings::DEFAULT.brightness;
+        }
+        (Self { eeprom, saved: settings }, settings)
+    }
+
+    /// Queue the bytes of `settings` that differ from those in EEPROM to be
+    /// written
+    pub fn save(&mut self, settings: Settings) {
+        if settings == self.saved {
+            return;
+        }
+        for (offset, byte) in (BASE..).zip(stored_bytes(settings)) {
+            if self.eeprom.read_byte(offset) != byte {
+                self.eeprom.write_byte(offset, byte);
+            }
+        }
+        self.saved = settings;
+    }
+
+    /// Carry on with pending writes, called once per frame
+    pub fn service(&mut self) {
+        self.eeprom.service();
+    }
+}
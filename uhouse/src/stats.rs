@@ -0,0 +1,146 @@
+#![cfg(feature = "fps-overlay")]
+
+//! On-screen frame statistics
+//! 
+//! Draws the frame rate and frame time into the top-right corner of the
+//! overlay with a tiny 3x5 font, so they can be read without a serial
+//! connection. The corner is kept clear of the scene by
+//! [`SCENE_MASKS`](crate::SCENE_MASKS).
+
+use avr_progmem::progmem;
+use uhouse_core::clip::Rect;
+use uhouse_core::numfmt;
+use uhouse_core::vec::*;
+
+use crate::framebuffer::{FrameBuffer, Layer};
+use crate::text::Text;
+use crate::SCREEN_WIDTH;
+
+/// Glyph height in pixels
+const GLYPH_HEIGHT: usize = 5;
+
+/// Horizontal distance between characters, a 3 pixel glyph and a space
+const ADVANCE: IFixed = 4;
+
+/// Vertical distance between lines
+const LINE_HEIGHT: IFixed = 6;
+
+/// Characters in the font, in [`FONT`] order
+const CHARSET: &[u8] = b"0123456789BFMPS";
+
+progmem! {
+
+    /// Glyphs of [`CHARSET`], one byte per row from the top with the leftmost
+    /// pixel in bit 2
+    static progmem FONT: [[u8; GLYPH_HEIGHT]; 15] = [
+        [0b111, 0b101, 0b101, 0b101, 0b111],
+        [0b010, 0b110, 0b010, 0b010, 0b111],
+        [0b111, 0b001, 0b111, 0b100, 0b111],
+        [0b111, 0b001, 0b111, 0b001, 0b111],
+        [0b101, 0b101, 0b111, 0b001, 0b001],
+        [0b111, 0b100, 0b111, 0b001, 0b111],
+        [0b111, 0b100, 0b111, 0b101, 0b111],
+        [0b111, 0b001, 0b001, 0b010, 0b010],
+        [0b111, 0b101, 0b111, 0b101, 0b111],
+        [0b111, 0b101, 0b111, 0b001, 0b111],
+        [0b110, 0b101, 0b110, 0b101, 0b110],
+        [0b111, 0b100, 0b110, 0b100, 0b100],
+        [0b101, 0b111, 0b111, 0b101, 0b101],
+        [0b110, 0b101, 0b110, 0b100, 0b100],
+        [0b011, 0b100, 0b010, 0b001, 0b110],
+    ];
+}
+
+/// Width of a number, right-aligned
+const NUMBER_COLUMNS: usize = 3;
+
+/// Longest unit, cut off past this
+const UNIT_COLUMNS: usize = 3;
+
+/// Characters per line, a number, a space and a unit
+const COLUMNS: IFixed = (NUMBER_COLUMNS + 1 + UNIT_COLUMNS) as IFixed;
+
+/// Top-left corner of the text without any pixel shift
+const ORIGIN: Vec2 = vec2!(SCREEN_WIDTH - COLUMNS*ADVANCE, 1);
+
+/// Area the text may cover, shifted by up to one pixel either way
+pub const AREA: Rect = Rect {
+    min: vec2!(ORIGIN.x - 1, ORIGIN.y - 1),
+    max: vec2!(SCREEN_WIDTH, ORIGIN.y + 2*LINE_HEIGHT),
+};
+
+pub struct StatsOverlay {
+    fps: u16,
+    offset: Vec2,
+
+    /// Set when what is on screen no longer matches
+    stale: bool,
+}
+
+impl StatsOverlay {
+    pub const fn new() -> Self {
+        Self {
+            fps: 0,
+            offset: vec2!(0, 0),
+            stale: true,
+        }
+    }
+
+    /// Show a new frame rate
+    pub fn set_fps(&mut self, fps: u16) {
+        self.stale |= fps != self.fps;
+        self.fps = fps;
+    }
+
+    /// Move the text by the burn-in pixel shift
+    #[allow(unused)]
+    pub fn set_offset(&mut self, offset: Vec2) {
+        self.stale |= offset != self.offset;
+        self.offset = offset;
+    }
+
+    /// Redraw the text if it changed or clearing the scene erased part of it
+    pub fn draw(&mut self, frame: &mut FrameBuffer) {
+        if !self.stale && !frame.is_damaged(Layer::Overlay) {
+            return;
+        }
+        self.stale = false;
+        frame.clear_layer(Layer::Overlay);
+
+        let frame_ms = match self.fps {
+            0 => 999,
+            fps => (1000 / fps).min(999),
+        };
+        let origin = ORIGIN + self.offset;
+        draw_line(frame, origin, self.fps, Text::FpsUnit);
+        draw_line(frame, origin + vec2!(0, LINE_HEIGHT), frame_ms, Text::MsUnit);
+    }
+}
+
+/// Draw a right-aligned number followed by a unit
+fn draw_line(frame: &mut FrameBuffer, at: Vec2, value: u16, unit: Text) {
+    let mut buf = [0; numfmt::U16_STR_LEN];
+    let digits = numfmt::fmt_u16(value.min(999), &mut buf).as_bytes();
+    let start = (NUMBER_COLUMNS - digits.len()) as IFixed;
+    draw_text(frame, at + vec2!(start*ADVANCE, 0), digits);
+    let mut buf = [0; UNIT_COLUMNS];
+    draw_text(frame, at + vec2!((NUMBER_COLUMNS as IFixed + 1)*ADVANCE, 0), unit.copy_to(&mut buf));
+}
+
+/// Draw text from [`CHARSET`], skipping any other character
+fn draw_text(frame: &mut FrameBuffer, at: Vec2, text: &[u8]) {
+    for (column, c) in text.iter().enumerate() {
+        let glyph = match CHARSET.iter().position(|&known| known == *c) {
+            Some(i) => FONT.load_at(i),
+            None => continue,
+        };
+        let x = at.x + column as IFixed*ADVANCE;
+        for (dy, row) in glyph.iter().enumerate() {
+            for dx in 0..3 {
+                if row & 0b100 >> dx != 0 {
+                    frame.set_layer_pixel(Layer::Overlay, (x + dx) as u32, (at.y + dy as IFixed) as u32);
+                }
+            }
+        }
+    }
+}
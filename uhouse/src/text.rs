@@ -0,0 +1,205 @@
+#![cfg(any(feature = "serial", feature = "fps-overlay", feature = "panic-display"))]
+
+//! Text printed over serial or drawn on the display
+//! 
+//! String literals are copied from flash into RAM at startup on AVR, so every
+//! message written inline costs RAM for as long as the program runs. Instead,
+//! every message is listed once in [`texts!`] and printed by its [`Text`]
+//! index, which streams the bytes out of one table in program memory. Labels
+//! drawn on the display are copied out with [`Text::copy_to`].
+//! 
+//! Each message is listed with its English and German text side by side, and
+//! only the table of the language picked at build time is kept. Build with
+//! `--features lang-de` for German. Words that host tooling matches on, such
+//! as `ok` and `error`, are the same in every language. Text drawn on the
+//! display is in capitals, the only letters its fonts have.
+
+use avr_progmem::progmem;
+
+#[cfg(feature = "serial")]
+use ufmt::{uDisplay, uWrite, Formatter};
+
+/// Bytes copied out of program memory at a time while printing
+const CHUNK: usize = 8;
+
+/// Declare every message with its text in each language
+macro_rules! texts {
+    ($($(#[$attr:meta])* $name:ident => $en:literal, $de:literal;)*) => {
+
+        /// Index of a message in the text table
+        #[derive(Copy, Clone, PartialEq, Eq)]
+        #[repr(u8)]
+        pub enum Text {
+            $($(#[$attr])* $name,)*
+        }
+
+        const NUM_TEXTS: usize = [$(Text::$name),*].len();
+
+        #[cfg(not(feature = "lang-de"))]
+        const TEXTS: [&str; NUM_TEXTS] = [$($en),*];
+
+        #[cfg(feature = "lang-de")]
+        const TEXTS: [&str; NUM_TEXTS] = [$($de),*];
+    };
+}
+
+texts! {
+    OkReset => "ok reset", "ok Neustart";
+    OkBootloader => "ok bootloader", "ok Bootloader";
+    OkBench => "ok bench", "ok Messung";
+    UnknownCommand => "error unknown command", "error unbekannter Befehl";
+
+    /// Remote command list
+    Commands => "r reset, b bootloader, t bench", "r Neustart, b Bootloader, t Messung";
+
+    /// Benchmark result, recognized by `uhouse-run` in English
+    Bench => "bench", "Messung";
+
+    OkPause => "ok pause", "ok Pause";
+    OkResume => "ok resume", "ok weiter";
+    OkSpeed => "ok speed", "ok Tempo";
+    OkDepth => "ok depth", "ok Tiefe";
+    OkRestart => "ok restart", "ok von vorn";
+    OkScene => "ok scene", "ok Szene";
+    OkBrightness => "ok brightness", "ok Helligkeit";
+
+    /// Console command list
+    ConsoleCommands => "p pause, +/- speed, z/Z depth, a restart, s scene, c/C brightness", "p Pause, +/- Tempo, z/Z Tiefe, a von vorn, s Szene, c/C Helligkeit";
+
+    CrashLog => "crash log", "Absturzprotokoll";
+    Reset => "reset", "Reset";
+    Error => "error", "Fehler";
+    Frame => "frame", "Bild";
+
+    PathA => "path a", "Pfad a";
+    PathB => "path b", "Pfad b";
+    Match => "match", "gleich";
+    Differ => "differ", "verschieden";
+
+    Transform => "transform", "Transformation";
+    Draw => "draw", "Zeichnen";
+    Flush => "flush", "Ausgabe";
+    Over => "over", "ueber";
+
+    Isr => "isr", "isr";
+    Timer0 => "timer0", "timer0";
+    Timer1 => "timer1", "timer1";
+    Timer2 => "timer2", "timer2";
+    Twi => "twi", "twi";
+
+    /// Units of the frame statistics on the display, drawn with the letters
+    /// `B`, `F`, `M`, `P` and `S` only
+    FpsUnit => "FPS", "BPS";
+    MsUnit => "MS", "MS";
+
+    /// Start of the panic message on the display, followed by the line
+    PanicAtLine => "PANIC AT LINE ", "PANIK IN ZEILE ";
+}
+
+/// Total length of all messages
+const TABLE_LEN: usize = table_len(&TEXTS);
+
+const _: () = assert!(TABLE_LEN <= u16::MAX as usize, "text table too long for 16-bit offsets");
+const _: () = assert!(is_ascii(&TEXTS), "text must be ASCII so it can be printed in chunks");
+
+progmem! {
+
+    /// Every message back to back
+    static progmem TABLE: [u8; TABLE_LEN] = table(&TEXTS);
+
+    /// Start of every message in [`TABLE`], followed by the end of the last
+    static progmem OFFSETS: [u16; NUM_TEXTS + 1] = offsets(&TEXTS);
+}
+
+const fn table_len(texts: &[&str]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < texts.len() {
+        len += texts[i].len();
+        i += 1;
+    }
+    len
+}
+
+const fn is_ascii(texts: &[&str]) -> bool {
+    let mut i = 0;
+    while i < texts.len() {
+        if !texts[i].is_ascii() {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn table(texts: &[&str; NUM_TEXTS]) -> [u8; TABLE_LEN] {
+    let mut table = [0; TABLE_LEN];
+    let mut pos = 0;
+    let mut i = 0;
+    while i < NUM_TEXTS {
+        let bytes = texts[i].as_bytes();
+        let mut j = 0;
+        while j < bytes.len() {
+            table[pos] = bytes[j];
+            pos += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+const fn offsets(texts: &[&str; NUM_TEXTS]) -> [u16; NUM_TEXTS + 1] {
+    let mut offsets = [0; NUM_TEXTS + 1];
+    let mut i = 0;
+    while i < NUM_TEXTS {
+        offsets[i + 1] = offsets[i] + texts[i].len() as u16;
+        i += 1;
+    }
+    offsets
+}
+
+impl Text {
+
+    /// Start and end of the text in [`TABLE`]
+    fn span(self) -> (usize, usize) {
+        let index = self as usize;
+        (OFFSETS.load_at(index) as usize, OFFSETS.load_at(index + 1) as usize)
+    }
+
+    /// Copy the text into `buf`, returning the part of `buf` it fills
+    /// 
+    /// Text that does not fit is cut off.
+    #[allow(unused)]
+    pub fn copy_to(self, buf: &mut [u8]) -> &[u8] {
+        let (start, end) = self.span();
+        let len = (end - start).min(buf.len());
+        for (i, byte) in buf[..len].iter_mut().enumerate() {
+            *byte = TABLE.load_at(start + i);
+        }
+        &buf[..len]
+    }
+}
+
+#[cfg(feature = "serial")]
+impl uDisplay for Text {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        let (mut start, end) = self.span();
+        let mut chunk = [0; CHUNK];
+        while start < end {
+            let len = (end - start).min(CHUNK);
+            for (i, byte) in chunk[..len].iter_mut().enumerate() {
+                *byte = TABLE.load_at(start + i);
+            }
+
+            // SAFETY: The table is checked to be ASCII, so any run of its
+            // bytes is valid UTF-8
+            f.write_str(unsafe { core::str::from_utf8_unchecked(&chunk[..len]) })?;
+            start += len;
+        }
+        Ok(())
+    }
+}
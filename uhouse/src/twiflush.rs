@@ -0,0 +1,221 @@
+#![cfg(feature = "async-flush")]
+
+//! Display flush driven by the TWI interrupt
+//! 
+//! A blocking flush keeps the CPU waiting on the bus for every byte sent to
+//! the display, which at 400 kHz is a large part of a frame for a busy
+//! picture. With the `async-flush` feature the changed bytes of a frame are
+//! copied into a staging buffer, along with the commands that place them,
+//! and the TWI interrupt sends them one byte at a time while the main loop
+//! goes on to transform and draw the next frame.
+//! 
+//! The framebuffer can be drawn to again as soon as its bytes are staged, so
+//! the staging buffer is the second buffer of a double-buffered flush, sized
+//! for what changes in a frame rather than the whole screen. Changes that do
+//! not fit in [`STAGE_BYTES`] are sent in batches, waiting for all but the
+//! last, so a frame redrawing most of the screen still flushes correctly,
+//! only with less of it overlapped.
+//! 
+//! The staging buffer holds a list of I2C transactions, each a length byte
+//! followed by the control byte and the bytes sent after it.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use arduino_hal::pac::TWI;
+use display_interface::DisplayError;
+
+/// Pick the size of the staging buffer here, in bytes, with the
+/// `async-flush` feature
+/// 
+/// Larger buffers overlap more of a busy frame with drawing the next one,
+/// at the cost of RAM. Each page of each changed region takes three bytes
+/// more than its pixels, and each region eight more.
+pub const STAGE_BYTES: usize = 384;
+
+/// Address of the display on the bus, the one `I2CDisplayInterface::new`
+/// uses
+const ADDRESS: u8 = 0x3C;
+
+/// Control byte starting a transaction of commands
+const CONTROL_COMMANDS: u8 = 0x00;
+
+/// Control byte starting a transaction of display data
+const CONTROL_DATA: u8 = 0x40;
+
+/// TWI status codes of a master transmitter, with the prescaler bits masked
+/// off
+mod status {
+    pub const START: u8 = 0x08;
+    pub const REPEATED_START: u8 = 0x10;
+    pub const ADDRESS_ACK: u8 = 0x18;
+    pub const DATA_ACK: u8 = 0x28;
+}
+
+/// Transactions to send, written by the main loop while idle and read by the
+/// interrupt while busy
+static mut STAGED: [u8; STAGE_BYTES] = [0; STAGE_BYTES];
+
+/// Length of the transactions in [`STAGED`]
+static mut STAGED_LEN: u16 = 0;
+
+/// Index of the next byte of [`STAGED`] to send, only used by the interrupt
+static mut NEXT: u16 = 0;
+
+/// Bytes left in the current transaction, only used by the interrupt
+static mut LEFT: u8 = 0;
+
+/// Set while the interrupt is sending the staged transactions
+static BUSY: AtomicBool = AtomicBool::new(false);
+
+/// Set when the display did not acknowledge a byte, or the bus was lost
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Sender of staged transactions to the display
+pub struct AsyncFlush {
+    twi: TWI,
+}
+
+impl AsyncFlush {
+
+    /// Take over the display bus, keeping the bit rate it was set up with
+    /// 
+    /// # Safety
+    /// 
+    /// The blocking display interface sharing the TWI must not be used
+    /// afterwards. Interrupts must be enabled before calling [`start`].
+    /// 
+    /// [`start`]: Self::start
+    pub unsafe fn new(twi: TWI) -> Self {
+        Self { twi }
+    }
+
+    /// Wait for the staged transactions to be sent, then empty the staging
+    /// buffer
+    pub fn wait(&mut self) -> Result<(), DisplayError> {
+        while BUSY.load(Ordering::Acquire) {}
+        self.wait_for_stop();
+
+        // SAFETY: The interrupt is done with the staging buffer
+        unsafe {
+            STAGED_LEN = 0;
+        }
+        if FAILED.load(Ordering::Acquire) {
+            FAILED.store(false, Ordering::Release);
+            return Err(DisplayError::BusWriteError);
+        }
+        Ok(())
+    }
+
+    /// Stage a transaction of commands
+    pub fn commands(&mut self, commands: &[u8]) -> Result<(), DisplayError> {
+        self.stage(CONTROL_COMMANDS, commands)
+    }
+
+    /// Stage a transaction of display data
+    pub fn data(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+        self.stage(CONTROL_DATA, data)
+    }
+
+    /// Stage a transaction, sending what is staged first if it does not fit
+    fn stage(&mut self, control: u8, bytes: &[u8]) -> Result<(), DisplayError> {
+        // The length byte counts the control byte
+        let len = bytes.len() + 1;
+        if len > u8::MAX as usize || len + 1 > STAGE_BYTES {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+
+        // SAFETY: Only the main loop touches the staging buffer while the
+        // interrupt is idle, which it stays until started
+        unsafe {
+            if STAGED_LEN as usize + len + 1 > STAGE_BYTES {
+                self.start();
+                self.wait()?;
+            }
+            let start = STAGED_LEN as usize;
+            STAGED[start] = len as u8;
+            STAGED[start + 1] = control;
+            STAGED[start + 2..start + len + 1].copy_from_slice(bytes);
+            STAGED_LEN = (start + len + 1) as u16;
+        }
+        Ok(())
+    }
+
+    /// Start sending the staged transactions from the interrupt
+    /// 
+    /// Returns right away. Call [`wait`](Self::wait) before staging more.
+    pub fn start(&mut self) {
+        // SAFETY: The interrupt is idle until the start condition below
+        unsafe {
+            if STAGED_LEN == 0 {
+                return;
+            }
+            NEXT = 0;
+            LEFT = 0;
+        }
+        self.wait_for_stop();
+        BUSY.store(true, Ordering::Release);
+        self.twi.twcr.write(|w| w.twint().set_bit().twsta().set_bit().twen().set_bit().twie().set_bit());
+    }
+
+    /// Wait for the stop condition that ended the last transfer to leave
+    /// the bus
+    /// 
+    /// The interrupt returns as soon as it requests the stop, and the TWI
+    /// ignores a new start requested before the stop has been sent.
+    fn wait_for_stop(&self) {
+        while self.twi.twcr.read().twsto().bit_is_set() {}
+    }
+}
+
+/// Abandon any transfer in progress and release the bus for a blocking
+/// interface
+/// 
+/// # Safety
+/// 
+/// The [`AsyncFlush`] must not be used afterwards.
+#[allow(unused)]
+pub unsafe fn stop() {
+    let twi = &*TWI::ptr();
+    twi.twcr.write(|w| w.twint().set_bit().twsto().set_bit().twen().set_bit());
+    BUSY.store(false, Ordering::Release);
+}
+
+interrupt! {
+    fn TWI() {
+        isr_budget!(Twi, {
+            // SAFETY: The interrupt only runs while a transfer started by the
+            // AsyncFlush is in progress, during which the main loop leaves the
+            // registers and the staging buffer alone
+            unsafe {
+                let twi = &*TWI::ptr();
+                match twi.twsr.read().bits() & 0xF8 {
+                    status::START | status::REPEATED_START => {
+                        LEFT = STAGED[NEXT as usize];
+                        NEXT += 1;
+                        twi.twdr.write(|w| w.bits(ADDRESS << 1));
+                        twi.twcr.write(|w| w.twint().set_bit().twen().set_bit().twie().set_bit());
+                    }
+                    status::ADDRESS_ACK | status::DATA_ACK => {
+                        if LEFT > 0 {
+                            twi.twdr.write(|w| w.bits(STAGED[NEXT as usize]));
+                            NEXT += 1;
+                            LEFT -= 1;
+                            twi.twcr.write(|w| w.twint().set_bit().twen().set_bit().twie().set_bit());
+                        } else if NEXT < STAGED_LEN {
+                            twi.twcr.write(|w| w.twint().set_bit().twsta().set_bit().twen().set_bit().twie().set_bit());
+                        } else {
+                            twi.twcr.write(|w| w.twint().set_bit().twsto().set_bit().twen().set_bit());
+                            BUSY.store(false, Ordering::Release);
+                        }
+                    }
+
+                    // Not acknowledged, or the bus was lost
+                    _ => {
+                        twi.twcr.write(|w| w.twint().set_bit().twsto().set_bit().twen().set_bit());
+                        FAILED.store(true, Ordering::Release);
+                        BUSY.store(false, Ordering::Release);
+                    }
+                }
+            }
+        });
+    }
+}
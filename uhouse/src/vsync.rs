@@ -0,0 +1,128 @@
+#![cfg(feature = "vsync")]
+
+//! Flush pacing against the panel refresh
+//! 
+//! The SSD1306 scans its rows out of display RAM on its own clock, so a
+//! flush that lands partway through a scan shows the top of one frame above
+//! the bottom of the last. Modules do not break out the FR sync pin, so
+//! instead the panel clock is set to a known divider and every flush is
+//! started a whole number of refreshes after the last one, as timed by TC2.
+//! The tear then stays in one place rather than rolling through the picture
+//! during fast rotation.
+//! 
+//! The panel oscillator is only accurate to within several percent, so its
+//! frequency is calibrated by hand with [`PANEL_OSC_HZ`](crate::PANEL_OSC_HZ).
+//! 
+//! The divider and calibration are build-time constants rather than settings
+//! changed at runtime: the timer periods are worked out from them at compile
+//! time, and the divider is only sent to the panel as it is initialized.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+use arduino_hal::pac::TC2;
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use ssd1306::{command::Command, size::DisplaySize};
+
+use crate::{CLOCK_FREQ, PANEL_OSC_HZ, REFRESH_DIVIDER, REFRESHES_PER_FRAME};
+
+/// Oscillator frequency setting, left at the value `init` sends
+const OSC_SETTING: u8 = 0x8;
+
+/// Display clocks spent on each row, 50 plus the precharge phases of the
+/// driver's default brightness
+const DCLKS_PER_ROW: u32 = 50 + 1 + 2;
+
+/// TC2 ticks per second, with the clock divided by 1024
+const TIMER_HZ: u32 = CLOCK_FREQ / 1024;
+
+/// TC2 ticks per panel refresh
+const REFRESH_TICKS: u32 = (TIMER_HZ * REFRESH_DIVIDER as u32 * DCLKS_PER_ROW
+    * crate::Display::HEIGHT as u32 + PANEL_OSC_HZ / 2) / PANEL_OSC_HZ;
+
+/// Compare matches per panel refresh, as one refresh may not fit in 8 bits
+const STEPS_PER_REFRESH: u8 = (REFRESH_TICKS / 256 + 1) as u8;
+
+/// TC2 ticks between compare matches
+const STEP_TICKS: u8 = (REFRESH_TICKS / STEPS_PER_REFRESH as u32) as u8;
+
+const _: () = assert!(REFRESH_DIVIDER >= 1 && REFRESH_DIVIDER <= 16, "refresh divider out of range");
+const _: () = assert!(REFRESHES_PER_FRAME >= 1, "frames must be held for at least one refresh");
+
+/// Panel refreshes counted by the timer interrupt
+static REFRESHES: AtomicU8 = AtomicU8::new(0);
+
+/// Compare matches since the last counted refresh
+static STEPS: AtomicU8 = AtomicU8::new(0);
+
+/// Display size that also sets the panel refresh rate
+#[derive(Copy, Clone)]
+pub struct Paced<S>(pub S);
+
+impl<S: DisplaySize> DisplaySize for Paced<S> {
+    const WIDTH: u8 = S::WIDTH;
+    const HEIGHT: u8 = S::HEIGHT;
+    const DRIVER_COLS: u8 = S::DRIVER_COLS;
+    const DRIVER_ROWS: u8 = S::DRIVER_ROWS;
+    const OFFSETX: u8 = S::OFFSETX;
+    const OFFSETY: u8 = S::OFFSETY;
+    type Buffer = S::Buffer;
+
+    fn configure(&self, iface: &mut impl WriteOnlyDataCommand) -> Result<(), DisplayError> {
+        self.0.configure(iface)?;
+
+        // The divider is sent as one less than its value
+        Command::DisplayClockDiv(OSC_SETTING, REFRESH_DIVIDER - 1).send(iface)
+    }
+}
+
+pub struct Pacer {
+    /// Refresh count at the last flush
+    last: u8,
+}
+
+impl Pacer {
+
+    /// Start timing panel refreshes
+    /// 
+    /// Interrupts must not yet be enabled before calling
+    pub unsafe fn new(tc2: TC2) -> Self {
+        use arduino_hal::pac::tc2::tccr2b::CS2_A;
+
+        // Clear on compare match
+        tc2.tccr2a.write(|w| w.wgm2().bits(0b10));
+        tc2.tccr2b.write(|w| w.cs2().variant(CS2_A::PRESCALE_1024));
+        tc2.tcnt2.write(|w| w.bits(0));
+        tc2.ocr2a.write(|w| w.bits(STEP_TICKS - 1));
+        tc2.timsk2.write(|w| w.ocie2a().set_bit()); // Enable this interrupt
+
+        Self {
+            last: 0,
+        }
+    }
+
+    /// Wait for the next refresh at least [`REFRESHES_PER_FRAME`] after the
+    /// last flush
+    /// 
+    /// Call right before flushing. A frame that took longer still waits for
+    /// the next refresh, keeping flushes in step with the panel.
+    pub fn wait(&mut self) {
+        let start = REFRESHES.load(Ordering::Acquire);
+        while REFRESHES.load(Ordering::Acquire) == start {}
+        while REFRESHES.load(Ordering::Acquire).wrapping_sub(self.last) < REFRESHES_PER_FRAME {}
+        self.last = REFRESHES.load(Ordering::Acquire);
+    }
+}
+
+interrupt! {
+    fn TIMER2_COMPA() {
+        isr_budget!(Timer2, {
+            // Only this interrupt writes either counter, so loads and stores suffice
+            let steps = STEPS.load(Ordering::Relaxed) + 1;
+            if steps == STEPS_PER_REFRESH {
+                STEPS.store(0, Ordering::Relaxed);
+                REFRESHES.store(REFRESHES.load(Ordering::Relaxed).wrapping_add(1), Ordering::Release);
+            } else {
+                STEPS.store(steps, Ordering::Relaxed);
+            }
+        });
+    }
+}